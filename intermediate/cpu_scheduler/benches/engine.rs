@@ -0,0 +1,151 @@
+use std::{env, time::Instant};
+
+use cpu_scheduler::{
+    models::cpu_process::{BaseProcess, CriticalSection},
+    scheduler::{
+        Scheduler, SchedulerConfig,
+        cfs::CfsScheduler,
+        fair_share::{FairShareScheduler, InnerPolicy},
+        fcfs::FCFSScheduler,
+        hrrn::HRRNScheduler,
+        locks::LockProtocol,
+        lottery::{DEFAULT_SEED, LotteryScheduler},
+        priority::{AgingPolicy, PriorityScheduler},
+        round_robin::{QuantumPolicy, RoundRobinScheduler},
+        sjf::{BurstPrediction, PredictiveSJFScheduler, SJFNonPreemptiveScheduler, SJFPreemptiveScheduler},
+        stride::StrideScheduler,
+    },
+    utils::generator::{ArrivalDistribution, BurstDistribution, PriorityDistribution, WorkloadGenerator},
+};
+
+const DEFAULT_COUNT: usize = 1_000_000;
+const SEED: u64 = 42;
+const MEAN_ARRIVAL: f64 = 1000.0;
+const MEAN_BURST: f64 = 900.0;
+const TIME_QUANTUM: u32 = 500;
+// A waiting process gains a level every 10 mean bursts
+const AGING: AgingPolicy = AgingPolicy { interval: 9000, step: 1 };
+// Groups and locks of the fair-share and lock workloads, every LOCKED-th process takes a lock
+const GROUPS: usize = 4;
+const LOCKS: usize = 8;
+const LOCKED: usize = 10;
+
+/*
+ *  Seeded workloads of `count` processes.
+ *
+ *  * Steady: exponential arrivals and bursts with a load of 0.9, so the ready queue
+ *    stays short while the clock runs up to about a billion time units.
+ *  * All at once: the same bursts all arriving at 0, every process waits in the ready queue.
+ *  * I/O: half the processes alternate short CPU bursts with I/O, at about the same load.
+ *  * Locks: the steady workload, every tenth process holds one of 8 locks for half its burst.
+ *
+ *  Every workload spreads its processes over 4 groups for the Fair-Share Scheduler.
+ */
+fn workloads(count: usize) -> Vec<(&'static str, Vec<BaseProcess>)> {
+    let generator = WorkloadGenerator::new(count, SEED)
+        .with_arrival(ArrivalDistribution::Exponential { mean: MEAN_ARRIVAL })
+        .with_burst(BurstDistribution::Exponential { mean: MEAN_BURST })
+        .with_priority(PriorityDistribution::Uniform { min: 0, max: 9 });
+
+    let generate = |generator: WorkloadGenerator| generator.generate().expect("the workload fits in the u32 clock");
+
+    let steady = generate(generator.clone());
+    let all_at_once = generate(generator.clone().with_arrival(ArrivalDistribution::Fixed(0)));
+    let io = generate(
+        generator.with_burst(BurstDistribution::Bimodal { io_bound: 0.5, cpu_bound: (600, 2400), short: (60, 240) }),
+    );
+    let locks = steady
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, process)| match i % LOCKED {
+            0 => {
+                let section = CriticalSection::new(&format!("L{}", i / LOCKED % LOCKS), 0, process.burst_time / 2);
+                process.with_sections(vec![section])
+            }
+            _ => process,
+        })
+        .collect();
+
+    [("Steady", steady), ("All at once", all_at_once), ("I/O", io), ("Locks", locks)]
+        .into_iter()
+        .map(|(name, processes)| {
+            let processes = processes
+                .into_iter()
+                .map(|process| {
+                    let group = format!("G{}", process.pid % GROUPS);
+                    process.with_group(&group)
+                })
+                .collect();
+            (name, processes)
+        })
+        .collect()
+}
+
+/*
+ *  Time every algorithm takes on large seeded workloads, see `workloads`.
+ *
+ *  * `cargo bench --bench engine [count]`, 1,000,000 processes by default.
+ *  * The quantum, CFS's latency and granularity and the aging interval are scaled to the
+ *    bursts so the number of events follows the number of processes, not the length of the run.
+ *  * Scheduling and the metrics of the result are timed apart.
+ */
+fn main() {
+    let count = env::args().skip(1).find_map(|arg| arg.parse().ok()).unwrap_or(DEFAULT_COUNT);
+
+    let start = Instant::now();
+    let workloads = workloads(count);
+    println!("Generated {} workloads of {} processes in {:.2?}", workloads.len(), count, start.elapsed());
+
+    let schedulers: Vec<Box<dyn Scheduler>> = vec![
+        Box::new(FCFSScheduler),
+        Box::new(SJFNonPreemptiveScheduler),
+        Box::new(SJFPreemptiveScheduler),
+        Box::new(PredictiveSJFScheduler::new(true, BurstPrediction::new(0.5, MEAN_BURST as f32))),
+        Box::new(RoundRobinScheduler::new(TIME_QUANTUM)),
+        Box::new(RoundRobinScheduler::new(0).with_policy(QuantumPolicy::Mean)),
+        Box::new(RoundRobinScheduler::new(0).with_policy(QuantumPolicy::Median)),
+        Box::new(PriorityScheduler::new(false, None)),
+        Box::new(PriorityScheduler::new(true, None)),
+        Box::new(PriorityScheduler::new(false, Some(AGING))),
+        Box::new(PriorityScheduler::new(true, Some(AGING))),
+        Box::new(PriorityScheduler::new(true, Some(AGING)).with_lock_protocol(LockProtocol::Inheritance)),
+        Box::new(PriorityScheduler::new(true, None).with_lock_protocol(LockProtocol::Ceiling)),
+        Box::new(HRRNScheduler),
+        Box::new(LotteryScheduler::new(TIME_QUANTUM, DEFAULT_SEED)),
+        Box::new(StrideScheduler::new(TIME_QUANTUM)),
+        Box::new(CfsScheduler::new(4 * TIME_QUANTUM, TIME_QUANTUM)),
+        Box::new(FairShareScheduler::new(TIME_QUANTUM, InnerPolicy::RoundRobin)),
+    ];
+    let config = SchedulerConfig::default();
+
+    const HEADER: &str = "|--------------------------------------------------------------------------|------------|------------|------------|";
+
+    for (workload, processes) in &workloads {
+        println!("\n{}", workload);
+        println!("{}", HEADER);
+        println!("|{:^74}|{:^12}|{:^12}|{:^12}|", "Algorithm", "Schedule", "Metrics", "Segments");
+        println!("{}", HEADER);
+
+        for scheduler in &schedulers {
+            let start = Instant::now();
+            let result = scheduler.schedule(processes, &config).expect("the run fits in the u32 clock");
+            let schedule_time = start.elapsed();
+
+            let start = Instant::now();
+            let metrics = result.metrics();
+            let metrics_time = start.elapsed();
+
+            println!(
+                "|{:^74}|{:^12}|{:^12}|{:^12}|",
+                result.algorithm_name,
+                format!("{:.2?}", schedule_time),
+                format!("{:.2?}", metrics_time),
+                result.gantt_chart.segments.len(),
+            );
+            assert_eq!(metrics.processes, count);
+        }
+
+        println!("{}", HEADER);
+    }
+}
@@ -0,0 +1,10 @@
+/*
+ *  CPU scheduling simulator.
+ *
+ *  * `models` holds the workload (processes, periodic tasks) and what a run produces.
+ *  * `scheduler` holds every algorithm behind the `Scheduler` trait, and the engine they share.
+ *  * `utils` loads and generates workloads, and displays, compares, validates and exports results.
+ */
+pub mod models;
+pub mod scheduler;
+pub mod utils;
@@ -1,23 +1,910 @@
-mod models;
-mod scheduler;
-mod utils;
+use std::{env, fs::File, io, path::Path, process};
 
-use crate:: {
-    models::cpu_process::{BaseProcess, FCFSProcess, HRRNProcess, RRProcess, SJFProcess},
+use thiserror::Error;
+
+use cpu_scheduler::{
+    models::{
+        cpu_process::BaseProcess,
+        schedule_result::ScheduleResult,
+        periodic_task::{MAX_HYPERPERIOD, PeriodicTask, hyperperiod, release_jobs}
+    },
     scheduler::{
-        fcfs::{FCFSScheduler, Scheduler},
-        hrrn::{HRRNSched, HRRNScheduler},
-        priority::{PrioritySched, PriorityScheduler}, 
-        round_robin::{RRScheduler, RoundRobinScheduler},
-        sjf::{SJFPreemptiveScheduler, SJFScheduler}
+        ScheduleError,
+        Scheduler,
+        QueueMode,
+        SchedulerConfig,
+        all_schedulers,
+        cfs::{CfsScheduler, DEFAULT_MIN_GRANULARITY, DEFAULT_TARGET_LATENCY},
+        edf::EDFScheduler,
+        fair_share::{FairShareScheduler, InnerPolicy},
+        fcfs::FCFSScheduler,
+        hrrn::HRRNScheduler,
+        locks::LockProtocol,
+        lottery::{DEFAULT_SEED, LotteryScheduler},
+        priority::{AgingPolicy, PriorityScheduler},
+        rate_monotonic::RateMonotonicScheduler,
+        round_robin::{QuantumPolicy, RoundRobinScheduler},
+        sjf::{
+            BurstPrediction,
+            DEFAULT_INITIAL_ESTIMATE,
+            PredictiveSJFScheduler,
+            SJFNonPreemptiveScheduler,
+            SJFPreemptiveScheduler
+        },
+        stride::StrideScheduler
     },
     utils::{
-        input::{get_priority_processes_from_user, get_processes_from_user, user_input, valid_input},
-        try_again::try_again
+        comparison::{display_comparison, display_quantum_sweep, run_comparison, run_quantum_sweep},
+        export::{ExportError, ExportFormat, export},
+        generator::WorkloadGenerator,
+        input::{
+            confirm,
+            get_nice_processes_from_user,
+            get_periodic_tasks_from_user,
+            get_priority_processes_from_user,
+            get_processes_from_user,
+            get_ticket_processes_from_user,
+            user_input,
+            valid_input
+        },
+        prediction::display_prediction,
+        replay::replay,
+        report::Report,
+        schedulability::{RealTimePolicy, display_schedulability},
+        try_again::try_again,
+        validation::{display_validation, validate},
+        workload::{WorkloadError, WorkloadFormat, load_task_set, load_workload, write_workload_csv}
     }
 };
 
-fn main() -> Result<(), Box<dyn std::error::Error>>{
+/*
+ *  Why a command-line run failed, each kind exits with its own code so scripts
+ *  can tell them apart: 2 for a bad command line, 3 for a schedule that broke an
+ *  invariant under `--validate`, 1 for everything else.
+ */
+#[derive(Debug, Error)]
+enum CliError {
+    #[error("{0}\nRun `cpu_scheduler help` for usage")]
+    Usage(String),
+
+    #[error(transparent)]
+    Workload(#[from] WorkloadError),
+
+    #[error(transparent)]
+    Export(#[from] ExportError),
+
+    #[error(transparent)]
+    Schedule(#[from] ScheduleError),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("could not write the workload: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("could not write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    // A run that can't go ahead, e.g. a task set whose hyperperiod is too long
+    #[error("{0}")]
+    Failed(String),
+
+    #[error("the schedule broke {0} invariant(s)")]
+    Violations(usize),
+}
+
+// Argument parsers report their mistakes as strings
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Usage(message)
+    }
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Violations(_) => 3,
+            _ => 1,
+        }
+    }
+}
+
+// How a command-line run prints its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum OutputFormat {
+    #[default]
+    Table,
+    // The export of `report::Report` on stdout and nothing else, for scripts
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+// What a run shows besides its results, from the command line or the menu
+#[derive(Debug, Clone, Copy, Default)]
+struct RunOptions {
+    explain: bool,
+    show_gantt: bool,
+    validate: bool,
+    format: OutputFormat,
+}
+
+fn get_positive_number(message: &str) -> Result<u32, Box<dyn std::error::Error>> {
+    loop {
+        let input = user_input(message)?;
+        match valid_input(&input) {
+            Ok(0) => println!("Value must be greater than 0."),
+            Ok(v) => return Ok(v),
+            Err(e) => println!("{}", e)
+        }
+    }
+}
+
+fn get_priority_scheduler() -> Result<PriorityScheduler, Box<dyn std::error::Error>> {
+    let preemptive = confirm("\nUse preemptive priority scheduling? (yes/y or no/n): ")?;
+
+    let aging = if confirm("Enable aging? (yes/y or no/n): ")? {
+        let interval = get_positive_number("Age every how many ticks of waiting: ")?;
+        let step = get_positive_number("Priority levels gained per aging step: ")?;
+        Some(AgingPolicy { interval, step })
+    } else {
+        None
+    };
+
+    Ok(PriorityScheduler::new(preemptive, aging))
+}
+
+fn get_proportional_share_scheduler(time_quantum: u32) -> Result<Box<dyn Scheduler>, Box<dyn std::error::Error>> {
+    if !confirm("\nUse lottery scheduling? (no/n for stride scheduling) (yes/y or no/n): ")? {
+        return Ok(Box::new(StrideScheduler::new(time_quantum)));
+    }
+
+    let seed = loop {
+        let input = user_input("Enter Random Seed: ")?;
+        match input.trim().parse::<u64>() {
+            Ok(seed) => break seed,
+            Err(_) => println!("Invalid input. Please enter a positive integer.")
+        }
+    };
+
+    Ok(Box::new(LotteryScheduler::new(time_quantum, seed)))
+}
+
+fn get_cfs_scheduler() -> Result<CfsScheduler, Box<dyn std::error::Error>> {
+    let target_latency = get_positive_number("\nEnter Target Latency: ")?;
+    let min_granularity = get_positive_number("Enter Minimum Granularity: ")?;
+
+    Ok(CfsScheduler::new(target_latency, min_granularity))
+}
+
+fn get_round_robin_scheduler() -> Result<RoundRobinScheduler, Box<dyn std::error::Error>> {
+    let policy = loop {
+        let input = user_input("\nQuantum policy (fixed, mean or median): ")?;
+        match QuantumPolicy::from_name(input.trim()) {
+            Some(policy) => break policy,
+            None => println!("Please enter fixed, mean or median."),
+        }
+    };
+
+    match policy {
+        QuantumPolicy::Fixed => Ok(RoundRobinScheduler::new(get_time_quantum()?)),
+        policy => Ok(RoundRobinScheduler::new(0).with_policy(policy)),
+    }
+}
+
+// Smallest and largest quantum of a sweep, asked until the range is valid
+fn get_quantum_range() -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let min = get_positive_number("Enter Smallest Quantum: ")?;
+    loop {
+        let max = get_positive_number("Enter Largest Quantum: ")?;
+        if max >= min {
+            return Ok((min..=max).collect());
+        }
+        println!("The largest quantum can't be smaller than {}.", min);
+    }
+}
+
+fn get_sjf_scheduler() -> Result<Box<dyn Scheduler>, Box<dyn std::error::Error>> {
+    let preemptive = confirm("\nUse preemptive SJF (SRTF)? (yes/y or no/n): ")?;
+
+    if !confirm("Predict burst times with exponential averaging? (yes/y or no/n): ")? {
+        return match preemptive {
+            true => Ok(Box::new(SJFPreemptiveScheduler)),
+            false => Ok(Box::new(SJFNonPreemptiveScheduler)),
+        };
+    }
+
+    let alpha = loop {
+        let input = user_input("Enter Alpha (0 to 1): ")?;
+        match input.trim().parse::<f32>() {
+            Ok(alpha) if (0.0..=1.0).contains(&alpha) => break alpha,
+            _ => println!("Please enter a number from 0 to 1."),
+        }
+    };
+    let initial = get_positive_number("Enter Initial Estimate: ")?;
+
+    Ok(Box::new(PredictiveSJFScheduler::new(preemptive, BurstPrediction::new(alpha, initial as f32))))
+}
+
+fn get_real_time_policy() -> Result<RealTimePolicy, Box<dyn std::error::Error>> {
+    match confirm("\nUse Earliest Deadline First? (no/n for Rate Monotonic) (yes/y or no/n): ")? {
+        true => Ok(RealTimePolicy::EarliestDeadlineFirst),
+        false => Ok(RealTimePolicy::RateMonotonic),
+    }
+}
+
+// Asks for an export path until the export succeeds or the user skips it
+fn ask_export(workload: &[BaseProcess], results: &[ScheduleResult]) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        let path = user_input("\nExport to an .svg, .html, .json or .csv file (empty to skip): ")?;
+        if path.is_empty() {
+            return Ok(());
+        }
+
+        match export(Path::new(&path), workload, results) {
+            Ok(()) => {
+                println!("Exported to {}", path);
+                return Ok(());
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn get_scheduler_config() -> Result<SchedulerConfig, Box<dyn std::error::Error>> {
+    let context_switch_time = loop {
+        let input = user_input("\nEnter Context Switch Time (0 for none): ")?;
+        match valid_input(&input) {
+            Ok(v) => break v,
+            Err(e) => println!("{}", e)
+        }
+    };
+
+    let num_cores = get_positive_number("Enter Number of CPU Cores: ")? as usize;
+    let queue_mode = if num_cores > 1 {
+        loop {
+            let input = user_input("Ready queue (global, per-core or stealing): ")?;
+            match QueueMode::from_name(input.trim()) {
+                Some(mode) => break mode,
+                None => println!("Please enter global, per-core or stealing."),
+            }
+        }
+    } else {
+        QueueMode::Global
+    };
+
+    Ok(SchedulerConfig { context_switch_time, num_cores, queue_mode, trace: false })
+}
+
+// Flags that take a value, given as `--name=value` or `--name value`
+const VALUE_FLAGS: &[&str] = &[
+    "--input", "--algo", "--quantum", "--format", "--quanta", "--aging", "--predict", "--seed", "--transfer",
+    "--latency", "--min-granularity", "--context-switch", "--cores", "--queues", "--export", "--arrival", "--burst",
+    "--priority", "--output", "--lock-protocol", "--groups", "--inner",
+];
+
+// Flags accepted by `run`, `compare`, `sweep` and the positional form
+const RUN_FLAGS: &[&str] = &[
+    "--input", "--algo", "--quantum", "--format", "--quanta", "--aging", "--predict", "--seed", "--transfer",
+    "--compensation", "--latency", "--min-granularity", "--context-switch", "--cores", "--queues", "--gantt",
+    "--explain", "--replay", "--validate", "--export", "--lock-protocol", "--groups", "--inner",
+];
+
+const GENERATE_FLAGS: &[&str] = &["--seed", "--arrival", "--burst", "--priority", "--output"];
+
+// Value of a `--name=value` or `--name value` flag
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix(name)? {
+        "" => args.get(i + 1).map(String::as_str),
+        value => value.strip_prefix('='),
+    })
+}
+
+// A typo in a flag is an error rather than a silently different run
+fn check_flags(args: &[String], known: &[&str]) -> Result<(), String> {
+    for arg in args.iter().filter(|arg| arg.starts_with("--")) {
+        let name = arg.split_once('=').map_or(arg.as_str(), |(name, _)| name);
+        if !known.contains(&name) {
+            return Err(format!("Unknown option '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+// Arguments that are neither a flag nor the value of the flag before them
+fn positionals(args: &[String]) -> impl Iterator<Item = &String> {
+    args.iter().enumerate().filter_map(|(i, arg)| {
+        let is_value = i > 0 && VALUE_FLAGS.contains(&args[i - 1].as_str());
+        (!arg.starts_with("--") && !is_value).then_some(arg)
+    })
+}
+
+// Parses `--context-switch=<time>`, `--cores=<n>`, `--queues=<global|per-core|stealing>`, `--explain` and `--replay`
+fn parse_scheduler_config(args: &[String]) -> Result<SchedulerConfig, String> {
+    let context_switch_time = match flag_value(args, "--context-switch") {
+        Some(value) => valid_input(value).map_err(|e| format!("--context-switch: {}", e))?,
+        None => 0,
+    };
+
+    let num_cores = match flag_value(args, "--cores") {
+        Some(value) => match valid_input(value) {
+            Ok(0) => return Err("--cores: value must be greater than 0".to_string()),
+            Ok(cores) => cores as usize,
+            Err(e) => return Err(format!("--cores: {}", e)),
+        },
+        None => 1,
+    };
+
+    let queue_mode = match flag_value(args, "--queues") {
+        Some(value) => QueueMode::from_name(value)
+            .ok_or_else(|| format!("Unknown ready queue '{}', expected global, per-core or stealing", value))?,
+        None => QueueMode::Global,
+    };
+
+    let trace = args.iter().any(|arg| arg == "--explain" || arg == "--replay");
+
+    Ok(SchedulerConfig { context_switch_time, num_cores, queue_mode, trace })
+}
+
+// Affinity has to name cores that exist
+fn check_affinity(processes: &[BaseProcess], config: &SchedulerConfig) -> Result<(), String> {
+    for process in processes {
+        if let Some(core) = process.affinity.iter().find(|&&core| core >= config.cores()) {
+            return Err(format!(
+                "P{} is pinned to core {} but only {} core(s) are simulated",
+                process.pid, core, config.cores()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Parses `--aging=<interval>:<step>`
+fn parse_aging(args: &[String]) -> Result<Option<AgingPolicy>, String> {
+    let Some(value) = flag_value(args, "--aging") else {
+        return Ok(None);
+    };
+
+    match value.split_once(':').map(|(i, s)| (valid_input(i), valid_input(s))) {
+        Some((Ok(interval), Ok(step))) if interval > 0 => Ok(Some(AgingPolicy { interval, step })),
+        _ => Err(format!("Invalid aging policy '{}', expected --aging=<interval>:<step>", value)),
+    }
+}
+
+// Parses `--lock-protocol=<none|inheritance|ceiling>`
+fn parse_lock_protocol(args: &[String]) -> Result<LockProtocol, String> {
+    match flag_value(args, "--lock-protocol") {
+        Some(value) => LockProtocol::from_name(value).ok_or_else(|| {
+            format!("Unknown lock protocol '{}', expected none, inheritance or ceiling", value)
+        }),
+        None => Ok(LockProtocol::None),
+    }
+}
+
+// Parses `--predict=<alpha>[:<initial estimate>]`
+fn parse_prediction(args: &[String]) -> Result<Option<BurstPrediction>, String> {
+    let Some(value) = flag_value(args, "--predict") else {
+        return Ok(None);
+    };
+
+    let (alpha, initial) = match value.split_once(':') {
+        Some((alpha, initial)) => (alpha.parse::<f32>(), initial.parse::<f32>()),
+        None => (value.parse::<f32>(), Ok(DEFAULT_INITIAL_ESTIMATE)),
+    };
+
+    match (alpha, initial) {
+        (Ok(alpha), Ok(initial)) if (0.0..=1.0).contains(&alpha) && initial >= 0.0 => {
+            Ok(Some(BurstPrediction::new(alpha, initial)))
+        }
+        _ => Err(format!(
+            "Invalid burst prediction '{}', expected --predict=<alpha 0 to 1>[:<initial estimate>]",
+            value
+        )),
+    }
+}
+
+// Parses `--quanta=<min>:<max>[:<step>]`, every quantum from min to max
+fn parse_quanta(args: &[String]) -> Result<Vec<u32>, String> {
+    let Some(value) = flag_value(args, "--quanta") else {
+        return Ok((DEFAULT_SWEEP.0..=DEFAULT_SWEEP.1).collect());
+    };
+
+    let parts: Vec<Result<u32, _>> = value.split(':').map(valid_input).collect();
+    match parts.as_slice() {
+        [Ok(min), Ok(max)] if *min > 0 && min <= max => Ok((*min..=*max).collect()),
+        [Ok(min), Ok(max), Ok(step)] if *min > 0 && min <= max && *step > 0 => {
+            Ok((*min..=*max).step_by(*step as usize).collect())
+        }
+        _ => Err(format!("Invalid quantum range '{}', expected --quanta=<min>:<max>[:<step>] from 1", value)),
+    }
+}
+
+// Parses `--seed=<n>`, `--transfer=<from>:<to>[,<from>:<to>...]` and `--compensation`
+fn parse_lottery(args: &[String], time_quantum: u32) -> Result<LotteryScheduler, String> {
+    let seed = match flag_value(args, "--seed") {
+        Some(value) => value.parse::<u64>().map_err(|_| format!("Invalid seed '{}'", value))?,
+        None => DEFAULT_SEED,
+    };
+
+    let transfers = match flag_value(args, "--transfer") {
+        Some(value) => value
+            .split(',')
+            .map(|pair| match pair.split_once(':').map(|(from, to)| (from.parse(), to.parse())) {
+                Some((Ok(from), Ok(to))) => Ok((from, to)),
+                _ => Err(format!("Invalid ticket transfer '{}', expected --transfer=<from>:<to>", pair)),
+            })
+            .collect::<Result<Vec<(usize, usize)>, String>>()?,
+        None => Vec::new(),
+    };
+
+    let compensation = args.iter().any(|arg| arg == "--compensation");
+
+    Ok(LotteryScheduler::new(time_quantum, seed)
+        .with_transfers(transfers)
+        .with_compensation(compensation))
+}
+
+// Parses `--groups=<group>:<weight>[,<group>:<weight>...]` and `--inner=<rr|priority>`
+fn parse_fair_share(args: &[String], time_quantum: u32) -> Result<FairShareScheduler, String> {
+    let group_weights = match flag_value(args, "--groups") {
+        Some(value) => value
+            .split(',')
+            .map(|pair| match pair.split_once(':').map(|(group, weight)| (group.trim(), valid_input(weight))) {
+                Some((group, Ok(weight))) if !group.is_empty() && weight > 0 => Ok((group.to_string(), weight)),
+                _ => Err(format!("Invalid group weight '{}', expected --groups=<group>:<weight greater than 0>", pair)),
+            })
+            .collect::<Result<Vec<(String, u32)>, String>>()?,
+        None => Vec::new(),
+    };
+
+    let inner = match flag_value(args, "--inner") {
+        Some(value) => InnerPolicy::from_name(value)
+            .ok_or_else(|| format!("Unknown inner policy '{}', expected rr or priority", value))?,
+        None => InnerPolicy::RoundRobin,
+    };
+
+    Ok(FairShareScheduler::new(time_quantum, inner).with_group_weights(group_weights))
+}
+
+// Parses `--latency=<time>` and `--min-granularity=<time>`
+fn parse_cfs(args: &[String]) -> Result<CfsScheduler, String> {
+    let positive = |name: &str, default: u32| match flag_value(args, name) {
+        Some(value) => match valid_input(value) {
+            Ok(0) => Err(format!("{}: value must be greater than 0", name)),
+            Ok(v) => Ok(v),
+            Err(e) => Err(format!("{}: {}", name, e)),
+        },
+        None => Ok(default),
+    };
+
+    Ok(CfsScheduler::new(
+        positive("--latency", DEFAULT_TARGET_LATENCY)?,
+        positive("--min-granularity", DEFAULT_MIN_GRANULARITY)?,
+    ))
+}
+
+fn get_time_quantum() -> Result<u32, Box<dyn std::error::Error>> {
+    get_positive_number("\nEnter Quantum Time: ")
+}
+
+const GENERATE_USAGE: &str = "Usage: cpu_scheduler generate <count> --seed=<n> [--arrival=fixed:<n>|uniform:<min>:<max>|exp:<mean>] [--burst=uniform:<min>:<max>|exp:<mean>|bimodal:<io-bound fraction>] [--priority=fixed:<n>|uniform:<min>:<max>] [--output=<workload.csv>]";
+
+const USAGE: &str = "Usage: cpu_scheduler run --algo <algorithm> --input <workload.csv|json|toml> [--quantum <n>] [options]
+       cpu_scheduler compare --input <workload> [--quantum <n>] [options]
+       cpu_scheduler sweep --input <workload> [--quanta <min>:<max>[:<step>]] [options]
+       cpu_scheduler generate <count> --seed <n> [--arrival <spec>] [--burst <spec>] [--priority <spec>] [--output <workload.csv>]
+       cpu_scheduler <workload> <algorithm> [time_quantum] [options]
+       cpu_scheduler [menu]
+
+Algorithms: fcfs, sjf, srtf, rr, rr_mean, rr_median, priority, priority_preemptive, hrrn, lottery, stride, cfs,
+            fair_share, edf and rm (these two take a periodic task set as input)
+
+Options (as --name <value> or --name=<value>):
+  --format <table|json|csv>      print tables (default), or the JSON or CSV export
+  --aging <interval>:<step>      priority aging
+  --lock-protocol <none|inheritance|ceiling>                priority of processes holding a lock
+  --predict <alpha>[:<initial>]  SJF / SRTF on predicted burst times
+  --seed <n>, --transfer <from>:<to>,..., --compensation    lottery settings
+  --latency <time>, --min-granularity <time>                CFS settings
+  --groups <group>:<weight>,..., --inner <rr|priority>      fair-share settings (unlisted groups weigh 1)
+  --context-switch <time>, --cores <n>, --queues <global|per-core|stealing>
+  --gantt, --explain, --replay, --validate, --export <report.svg|html|json|csv>
+
+Exit codes: 0 success, 1 failed run, 2 bad command line, 3 --validate found violations";
+
+// Round Robin quantum used by compare mode when none is given
+const DEFAULT_TIME_QUANTUM: u32 = 4;
+
+// Quanta tried by sweep mode when no range is given
+const DEFAULT_SWEEP: (u32, u32) = (1, 10);
+
+fn scheduler_from_name(name: &str, time_quantum: Option<u32>, args: &[String]) -> Result<Box<dyn Scheduler>, String> {
+    let name = name.to_lowercase();
+    let time_quantum = match name.as_str() {
+        "rr" | "round_robin" | "lottery" | "stride" | "fair_share" | "fairshare" => match time_quantum {
+            Some(q) if q > 0 => q,
+            _ => return Err(format!("{} needs a time quantum greater than 0", name)),
+        },
+        _ => 0,
+    };
+
+    match name.as_str() {
+        "fcfs" => Ok(Box::new(FCFSScheduler)),
+        "sjf" => match parse_prediction(args)? {
+            Some(prediction) => Ok(Box::new(PredictiveSJFScheduler::new(false, prediction))),
+            None => Ok(Box::new(SJFNonPreemptiveScheduler)),
+        },
+        "srtf" | "sjf_preemptive" => match parse_prediction(args)? {
+            Some(prediction) => Ok(Box::new(PredictiveSJFScheduler::new(true, prediction))),
+            None => Ok(Box::new(SJFPreemptiveScheduler)),
+        },
+        "rr" | "round_robin" => Ok(Box::new(RoundRobinScheduler::new(time_quantum))),
+        "rr_mean" => Ok(Box::new(RoundRobinScheduler::new(0).with_policy(QuantumPolicy::Mean))),
+        "rr_median" => Ok(Box::new(RoundRobinScheduler::new(0).with_policy(QuantumPolicy::Median))),
+        "priority" => Ok(Box::new(
+            PriorityScheduler::new(false, parse_aging(args)?).with_lock_protocol(parse_lock_protocol(args)?)
+        )),
+        "priority_preemptive" => Ok(Box::new(
+            PriorityScheduler::new(true, parse_aging(args)?).with_lock_protocol(parse_lock_protocol(args)?)
+        )),
+        "hrrn" => Ok(Box::new(HRRNScheduler)),
+        "lottery" => Ok(Box::new(parse_lottery(args, time_quantum)?)),
+        "stride" => Ok(Box::new(StrideScheduler::new(time_quantum))),
+        "cfs" => Ok(Box::new(parse_cfs(args)?)),
+        "fair_share" | "fairshare" => Ok(Box::new(parse_fair_share(args, time_quantum)?)),
+        _ => Err(format!("Unknown algorithm '{}'", name)),
+    }
+}
+
+// Writes a seeded synthetic workload as CSV, to `--output` or stdout
+fn generate(args: &[String]) -> Result<(), CliError> {
+    check_flags(args, GENERATE_FLAGS)?;
+
+    let count = match args.first().map(|count| valid_input(count)) {
+        Some(Ok(count)) if count > 0 => count as usize,
+        _ => return Err(CliError::Usage(GENERATE_USAGE.to_string())),
+    };
+
+    let seed = match flag_value(args, "--seed") {
+        Some(value) => value.parse::<u64>().map_err(|_| format!("Invalid seed '{}'", value))?,
+        None => return Err(CliError::Usage(format!("generate needs an explicit --seed\n{}", GENERATE_USAGE))),
+    };
+
+    let mut generator = WorkloadGenerator::new(count, seed);
+    if let Some(spec) = flag_value(args, "--arrival") {
+        generator = generator.with_arrival(spec.parse()?);
+    }
+    if let Some(spec) = flag_value(args, "--burst") {
+        generator = generator.with_burst(spec.parse()?);
+    }
+    if let Some(spec) = flag_value(args, "--priority") {
+        generator = generator.with_priority(spec.parse()?);
+    }
+
+    let processes = generator.generate().map_err(CliError::Failed)?;
+    match flag_value(args, "--output") {
+        Some(path) => {
+            if WorkloadFormat::from_path(Path::new(path))? != WorkloadFormat::Csv {
+                return Err(CliError::Usage("generate only writes CSV workloads, use a .csv output".to_string()));
+            }
+            write_workload_csv(&processes, File::create(path)?)?;
+            println!("Generated {} processes into {}", processes.len(), path);
+        }
+        None => write_workload_csv(&processes, io::stdout())?,
+    }
+
+    Ok(())
+}
+
+// Value of a required flag of a subcommand
+fn required_flag<'a>(args: &'a [String], name: &str, command: &str) -> Result<&'a str, CliError> {
+    flag_value(args, name).ok_or_else(|| CliError::Usage(format!("{} needs {} <value>", command, name)))
+}
+
+// `run`, `compare` and `sweep` only take flags, the workload comes from `--input`
+fn run_command(command: &str, args: &[String]) -> Result<(), CliError> {
+    check_flags(args, RUN_FLAGS)?;
+    if let Some(arg) = positionals(args).next() {
+        return Err(CliError::Usage(format!("Unexpected argument '{}'", arg)));
+    }
+
+    let path = required_flag(args, "--input", command)?;
+    let algorithm = match command {
+        "run" => required_flag(args, "--algo", command)?,
+        _ => command,
+    };
+    let time_quantum = match flag_value(args, "--quantum") {
+        Some(q) => Some(valid_input(q).map_err(|e| format!("--quantum: {}", e))?),
+        None => None,
+    };
+
+    run_workload(path, algorithm, time_quantum, args)
+}
+
+// The original form, `<workload> <algorithm> [time_quantum]` followed by flags
+fn run_from_file(args: &[String]) -> Result<(), CliError> {
+    check_flags(args, RUN_FLAGS)?;
+
+    let mut positionals = positionals(args);
+    let (Some(path), Some(algorithm)) = (positionals.next(), positionals.next()) else {
+        return Err(CliError::Usage(USAGE.to_string()));
+    };
+    let time_quantum = match positionals.next() {
+        Some(q) => Some(valid_input(q).map_err(|e| format!("time quantum: {}", e))?),
+        None => None,
+    };
+    if let Some(arg) = positionals.next() {
+        return Err(CliError::Usage(format!("Unexpected argument '{}'", arg)));
+    }
+
+    run_workload(path, algorithm, time_quantum, args)
+}
+
+// Non-interactive path: every input comes from the command line and the workload file
+fn run_workload(path: &str, algorithm: &str, time_quantum: Option<u32>, args: &[String]) -> Result<(), CliError> {
+    let show_replay = args.iter().any(|arg| arg == "--replay");
+    let options = RunOptions {
+        explain: args.iter().any(|arg| arg == "--explain"),
+        show_gantt: args.iter().any(|arg| arg == "--gantt"),
+        validate: args.iter().any(|arg| arg == "--validate"),
+        format: match flag_value(args, "--format") {
+            Some(format) => OutputFormat::from_name(format)
+                .ok_or_else(|| format!("Unknown output format '{}', expected table, json or csv", format))?,
+            None => OutputFormat::Table,
+        },
+    };
+
+    if show_replay && options.format != OutputFormat::Table {
+        return Err(CliError::Usage("--replay is interactive, it can only be used with --format table".to_string()));
+    }
+
+    // Checked before running anything, so a typo doesn't throw the run away
+    let export_path = flag_value(args, "--export").map(Path::new);
+    if let Some(export_path) = export_path {
+        ExportFormat::from_path(export_path).map_err(|e| CliError::Usage(e.to_string()))?;
+    }
+
+    let policy = match algorithm.to_lowercase().as_str() {
+        "edf" => Some(RealTimePolicy::EarliestDeadlineFirst),
+        "rm" | "rate_monotonic" => Some(RealTimePolicy::RateMonotonic),
+        _ => None,
+    };
+
+    let (workload, results, violations) = match policy {
+        // Real-time schedulers take a periodic task set instead of a workload
+        Some(policy) => {
+            let tasks = load_task_set(Path::new(path))?;
+            let config = parse_scheduler_config(args)?;
+            let (hyperperiod, jobs) = release_task_set(&tasks).map_err(CliError::Failed)?;
+            let (result, violations) = simulate_task_set(&tasks, hyperperiod, &jobs, policy, &config, &options)?;
+            (jobs, vec![result], violations)
+        }
+        None => {
+            let processes = load_workload(Path::new(path))?;
+            let config = parse_scheduler_config(args)?;
+            check_affinity(&processes, &config)?;
+
+            let (results, violations) = if algorithm.eq_ignore_ascii_case("compare") {
+                if show_replay {
+                    return Err(CliError::Usage("--replay steps through a single algorithm, it can't be used with compare".to_string()));
+                }
+
+                let time_quantum = time_quantum.unwrap_or(DEFAULT_TIME_QUANTUM);
+                if time_quantum == 0 {
+                    return Err(CliError::Usage("Round Robin needs a time quantum greater than 0".to_string()));
+                }
+
+                compare(&processes, time_quantum, &config, &options)?
+            } else if algorithm.eq_ignore_ascii_case("sweep") {
+                if show_replay {
+                    return Err(CliError::Usage("--replay steps through a single algorithm, it can't be used with sweep".to_string()));
+                }
+
+                sweep(&processes, &parse_quanta(args)?, &config, &options)?
+            } else {
+                let scheduler = scheduler_from_name(algorithm, time_quantum, args)?;
+                let (result, violations) = run_scheduler(scheduler.as_ref(), &processes, &config, &options)?;
+                (vec![result], violations)
+            };
+            (processes, results, violations)
+        }
+    };
+
+    match options.format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => Report::new(&workload, &results).write_json(io::stdout())?,
+        OutputFormat::Csv => Report::new(&workload, &results).write_csv(io::stdout())?,
+    }
+
+    if let Some(export_path) = export_path {
+        export(export_path, &workload, &results)?;
+        // stdout only holds the export in the data formats
+        match options.format {
+            OutputFormat::Table => println!("Exported to {}", export_path.display()),
+            _ => eprintln!("Exported to {}", export_path.display()),
+        }
+    }
+
+    if show_replay && let Some(result) = results.first() {
+        replay(result)?;
+    }
+
+    match violations {
+        0 => Ok(()),
+        violations => Err(CliError::Violations(violations)),
+    }
+}
+
+// Hyperperiod of the task set and every job it releases over it
+fn release_task_set(tasks: &[PeriodicTask]) -> Result<(u32, Vec<BaseProcess>), String> {
+    let hyperperiod = hyperperiod(tasks)
+        .ok_or_else(|| format!("The hyperperiod of the task set is longer than {}", MAX_HYPERPERIOD))?;
+    Ok((hyperperiod, release_jobs(tasks, hyperperiod)?))
+}
+
+// Runs the jobs released over the hyperperiod, then checks the outcome against the analysis
+fn simulate_task_set(
+    tasks: &[PeriodicTask],
+    hyperperiod: u32,
+    jobs: &[BaseProcess],
+    policy: RealTimePolicy,
+    config: &SchedulerConfig,
+    options: &RunOptions
+) -> Result<(ScheduleResult, usize), ScheduleError> {
+
+    let scheduler: Box<dyn Scheduler> = match policy {
+        RealTimePolicy::EarliestDeadlineFirst => Box::new(EDFScheduler),
+        RealTimePolicy::RateMonotonic => Box::new(RateMonotonicScheduler),
+    };
+
+    let result = scheduler.schedule(jobs, config)?;
+    if options.format == OutputFormat::Table {
+        display_result(&result, options);
+        display_schedulability(tasks, hyperperiod, &result, policy);
+    }
+    let violations = check_schedule(jobs, &result, scheduler.preemptive(), options);
+
+    Ok((result, violations))
+}
+
+// Runs and displays one algorithm, a scheduler that estimates bursts is also measured against its oracle
+fn run_scheduler(
+    scheduler: &dyn Scheduler,
+    processes: &[BaseProcess],
+    config: &SchedulerConfig,
+    options: &RunOptions
+) -> Result<(ScheduleResult, usize), ScheduleError> {
+    let result = scheduler.schedule(processes, config)?;
+    if options.format == OutputFormat::Table {
+        display_result(&result, options);
+
+        if let Some(oracle) = scheduler.oracle() {
+            let oracle_config = SchedulerConfig { trace: false, ..config.clone() };
+            display_prediction(&result, &oracle.schedule(processes, &oracle_config)?);
+        }
+    }
+    let violations = check_schedule(processes, &result, scheduler.preemptive(), options);
+
+    Ok((result, violations))
+}
+
+// The narrated trace first when asked for, then the results
+fn display_result(result: &ScheduleResult, options: &RunOptions) {
+    if options.explain && let Some(trace) = &result.trace {
+        trace.display();
+    }
+    result.display();
+}
+
+// Validates a schedule when asked to and returns how many invariants it broke
+fn check_schedule(processes: &[BaseProcess], result: &ScheduleResult, preemptive: bool, options: &RunOptions) -> usize {
+    if !options.validate {
+        return 0;
+    }
+
+    let violations = validate(processes, result, preemptive);
+    match options.format {
+        OutputFormat::Table => display_validation(result, &violations),
+        _ => {
+            for violation in &violations {
+                eprintln!("{}: {}", result.algorithm_name, violation);
+            }
+        }
+    }
+
+    violations.len()
+}
+
+fn sweep(
+    processes: &[BaseProcess],
+    quanta: &[u32],
+    config: &SchedulerConfig,
+    options: &RunOptions
+) -> Result<(Vec<ScheduleResult>, usize), ScheduleError> {
+    let results = run_quantum_sweep(processes, quanta, config)?;
+    if options.format == OutputFormat::Table {
+        display_quantum_sweep(&results);
+    }
+
+    // Round Robin is always preemptive
+    let violations = results
+        .iter()
+        .map(|(_, result)| check_schedule(processes, result, true, options))
+        .sum();
+
+    Ok((results.into_iter().map(|(_, result)| result).collect(), violations))
+}
+
+fn compare(
+    processes: &[BaseProcess],
+    time_quantum: u32,
+    config: &SchedulerConfig,
+    options: &RunOptions
+) -> Result<(Vec<ScheduleResult>, usize), ScheduleError> {
+    let schedulers = all_schedulers(time_quantum);
+    let results = run_comparison(processes, &schedulers, config)?;
+
+    if options.format == OutputFormat::Table {
+        for result in &results {
+            if let Some(trace) = &result.trace {
+                println!("\n{}", result.algorithm_name);
+                trace.display();
+            }
+        }
+
+        if options.show_gantt {
+            for result in &results {
+                println!("\n{}", result.algorithm_name);
+                result.gantt_chart.display_gantt_chart();
+            }
+        }
+
+        display_comparison(&results);
+    }
+
+    let violations = schedulers
+        .iter()
+        .zip(&results)
+        .map(|(scheduler, result)| check_schedule(processes, result, scheduler.preemptive(), options))
+        .sum();
+
+    Ok((results, violations))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let outcome = match args.first().map(String::as_str) {
+        None | Some("menu") => menu().map_err(|e| CliError::Failed(e.to_string())),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some("generate") => generate(&args[1..]),
+        Some(command @ ("run" | "compare" | "sweep")) => run_command(command, &args[1..]),
+        Some(_) => run_from_file(&args),
+    };
+
+    if let Err(e) = outcome {
+        eprintln!("Error: {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+// Numbered menu, every input is asked for interactively
+fn menu() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         println!("CPU Scheduling Algorithms Simulation");
         println!("1. FCFS Algorithm");
@@ -25,119 +912,135 @@ fn main() -> Result<(), Box<dyn std::error::Error>>{
         println!("3. Round Robin Algorithm");
         println!("4. Priority Scheduling Algorithm");
         println!("5. Highest Response Ratio Next Algorithm");
-        println!("6. Exit");
-        let option = user_input("Enter your choice (1-6): ")?;
+        println!("6. Proportional Share (Lottery / Stride) Algorithm");
+        println!("7. Completely Fair Scheduler (CFS) Algorithm");
+        println!("8. Real-Time Periodic Tasks (EDF / Rate Monotonic)");
+        println!("9. Compare All Algorithms");
+        println!("10. Exit");
+        let option = user_input("Enter your choice (1-10): ")?;
         let valid_option: u8 = match option.trim().parse::<u8>() {
-            Ok(num) if (1..=6).contains(&num) => num,
+            Ok(num) if (1..=10).contains(&num) => num,
             _ => {
-                println!("Please choose between 1 to 6 only.");
+                println!("Please choose between 1 to 10 only.");
                 continue;
             }
         };
 
-        match valid_option {
-            1 => {
-                'fcfc_loop: loop {
-                    println!("\nFCFS Algorithm Simulation\n");
-                    let base_process: Vec<BaseProcess> = get_processes_from_user()?;
-                    let fcfs_processes: Vec<FCFSProcess> = base_process.into_iter().map(FCFSProcess::new).collect();
-
-                    let mut fcfs_scheduler = FCFSScheduler::new(fcfs_processes);
-                    fcfs_scheduler.schedule();
-                    fcfs_scheduler.display();
-
-                    let again: bool = try_again()?;
-                    if again {
-                        continue 'fcfc_loop
-                    } else {
-                        break 'fcfc_loop
+        let title = match valid_option {
+            1 => "FCFS Algorithm Simulation",
+            2 => "Shortest Job First Algorithm Simulation",
+            3 => "Round Robin Algorithm Simulation",
+            4 => "Priority CPU Scheduling Algorithm Simulation",
+            5 => "Highest Response Ratio Next Algorithm Simulation",
+            6 => "Proportional Share Scheduling Simulation",
+            7 => "Completely Fair Scheduler Simulation",
+            8 => "Real-Time Scheduling Simulation",
+            9 => "CPU Scheduling Algorithms Comparison",
+            _ => {
+                println!("CPU Scheduling Algorithm Exiting... Bye!");
+                break
+            }
+        };
+
+        loop {
+            println!("\n{}\n", title);
+
+            if valid_option == 8 {
+                let tasks = get_periodic_tasks_from_user()?;
+                let policy = get_real_time_policy()?;
+                let mut config = get_scheduler_config()?;
+                let explain = confirm("Explain every scheduling decision? (yes/y or no/n): ")?;
+                let show_replay = confirm("Replay the schedule step by step? (yes/y or no/n): ")?;
+                config.trace = explain || show_replay;
+
+                match release_task_set(&tasks) {
+                    Ok((hyperperiod, jobs)) => {
+                        let options = RunOptions { explain, ..Default::default() };
+                        match simulate_task_set(&tasks, hyperperiod, &jobs, policy, &config, &options) {
+                            Ok((result, _)) => {
+                                ask_export(&jobs, std::slice::from_ref(&result))?;
+                                if show_replay {
+                                    replay(&result)?;
+                                }
+                            }
+                            Err(e) => println!("{}", e),
+                        }
                     }
+                    Err(e) => println!("{}", e),
                 }
-            },
-            2 => {
-                'sjf_loop: loop {
-                    println!("\nShortest Job First (Preemptive) Algorithm Simulation\n");
-                    let base_process: Vec<BaseProcess> = get_processes_from_user()?;
-                    let sjf_processes: Vec<SJFProcess> = base_process.into_iter().map(SJFProcess::new).collect();
-
-                    let mut sjf_scheduler = SJFPreemptiveScheduler::new(sjf_processes);
-                    sjf_scheduler.schedule();
-                    sjf_scheduler.display();
-
-                    let again: bool = try_again()?;
-                    if again {
-                        continue 'sjf_loop
-                    } else {
-                        break 'sjf_loop
-                    }
+
+                if !try_again()? {
+                    break
                 }
-            },
-            3 => {
-                'rr_loop: loop {
-                    println!("\nRound Robin Algorithm Simulation\n");
-                    let base_process: Vec<BaseProcess> = get_processes_from_user()?;
-                    let time_quantum: u32 = loop {
-                        let input = user_input("\nEnter Quantum Time: ")?;
-                        match valid_input(&input) {
-                            Ok(v) => break v as u32,
-                            Err(e) => println!("{}", e)
-                        }
-                    };
+                continue;
+            }
 
-                    let rr_processes: Vec<RRProcess> = base_process.into_iter().map(RRProcess::new).collect();
+            let processes: Vec<BaseProcess> = match valid_option {
+                4 | 9 => get_priority_processes_from_user()?,
+                6 => get_ticket_processes_from_user()?,
+                7 => get_nice_processes_from_user()?,
+                _ => get_processes_from_user()?,
+            };
 
-                    let mut rr_scheduler = RoundRobinScheduler::new(time_quantum, rr_processes);
-                    rr_scheduler.schedule();
-                    rr_scheduler.display();
+            if valid_option == 3 && confirm("\nSweep a range of time quanta? (yes/y or no/n): ")? {
+                let quanta = get_quantum_range()?;
+                let config = get_scheduler_config()?;
+                match sweep(&processes, &quanta, &config, &RunOptions::default()) {
+                    Ok((results, _)) => ask_export(&processes, &results)?,
+                    Err(e) => println!("{}", e),
+                }
 
-                    let again: bool = try_again()?;
-                    if again {
-                        continue 'rr_loop
-                    } else {
-                        break 'rr_loop
-                    }
+                if !try_again()? {
+                    break
                 }
-            },
-            4 => {
-                'priority_loop: loop {
-                    println!("\nNon-Preemptive Priority CPU Scheduling Algorithm Simulation\n");
-                    let priority_process = get_priority_processes_from_user()?;
-                    
-                    let mut priority_scheduler  = PriorityScheduler::new(priority_process);
-                    priority_scheduler.schedule();
-                    priority_scheduler.display();
-
-                    let again: bool = try_again()?;
-                    if again {
-                        continue 'priority_loop
-                    } else {
-                        break 'priority_loop
-                    }
+                continue;
+            }
+
+            if valid_option == 9 {
+                let time_quantum = get_time_quantum()?;
+                let config = get_scheduler_config()?;
+                let show_gantt = confirm("\nShow the Gantt chart of each algorithm? (yes/y or no/n): ")?;
+                let options = RunOptions { show_gantt, ..Default::default() };
+                match compare(&processes, time_quantum, &config, &options) {
+                    Ok((results, _)) => ask_export(&processes, &results)?,
+                    Err(e) => println!("{}", e),
+                }
+
+                if !try_again()? {
+                    break
                 }
-            },
-            5 => {
-                'hrrn_loop: loop {
-                    println!("\nHighest Response Ratio Next Algorithm Simulation\n");
-                    let base_process: Vec<BaseProcess> = get_processes_from_user()?;
-                    let hrrn_process:Vec<HRRNProcess> = base_process.into_iter().map(HRRNProcess::new).collect();
-
-                    let mut hrrn_scheduler = HRRNScheduler::new(hrrn_process);
-                    hrrn_scheduler.schedule();
-                    hrrn_scheduler.display();
-
-                    let again: bool = try_again()?;
-                    if again {
-                        continue 'hrrn_loop
-                    } else {
-                        break 'hrrn_loop
+                continue;
+            }
+
+            let scheduler: Box<dyn Scheduler> = match valid_option {
+                1 => Box::new(FCFSScheduler),
+                2 => get_sjf_scheduler()?,
+                3 => Box::new(get_round_robin_scheduler()?),
+                4 => Box::new(get_priority_scheduler()?),
+                5 => Box::new(HRRNScheduler),
+                6 => get_proportional_share_scheduler(get_time_quantum()?)?,
+                _ => Box::new(get_cfs_scheduler()?),
+            };
+
+            let mut config = get_scheduler_config()?;
+            let explain = confirm("Explain every scheduling decision? (yes/y or no/n): ")?;
+            let show_replay = confirm("Replay the schedule step by step? (yes/y or no/n): ")?;
+            config.trace = explain || show_replay;
+
+            let options = RunOptions { explain, ..Default::default() };
+            match run_scheduler(scheduler.as_ref(), &processes, &config, &options) {
+                Ok((result, _)) => {
+                    ask_export(&processes, std::slice::from_ref(&result))?;
+                    if show_replay {
+                        replay(&result)?;
                     }
                 }
-            },
-            6 => {
-                println!("CPU Scheduling Algorithm Exiting... Bye!");
+                Err(e) => println!("{}", e),
+            }
+
+            if !try_again()? {
                 break
-            },
-            _ => println!("\nInvalid option, try again.")
+            }
         }
     }
 
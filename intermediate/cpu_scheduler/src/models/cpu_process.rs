@@ -1,9 +1,115 @@
+use std::{fmt, str::FromStr};
 
-#[derive(Debug, Clone)]
+use serde::Serialize;
+
+// Lottery / stride tickets of a process that doesn't ask for a share
+pub const DEFAULT_TICKETS: u32 = 100;
+
+// Group of a process whose owner isn't given
+pub const DEFAULT_GROUP: &str = "default";
+
+// Weight of a nice 0 process, every nice level is ~10% more or less CPU than the next
+pub const NICE_0_WEIGHT: u32 = 1024;
+
+// Linux `sched_prio_to_weight`, nice -20 to 19
+const NICE_TO_WEIGHT: [u32; 40] = [
+    88761, 71755, 56483, 46273, 36291,
+    29154, 23254, 18705, 14949, 11916,
+    9548, 7620, 6100, 4904, 3906,
+    3121, 2501, 1991, 1586, 1277,
+    1024, 820, 655, 526, 423,
+    335, 272, 215, 172, 137,
+    110, 87, 70, 56, 45,
+    36, 29, 23, 18, 15,
+];
+
+pub fn nice_to_weight(nice: i32) -> u32 {
+    NICE_TO_WEIGHT[(nice.clamp(-20, 19) + 20) as usize]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Burst {
+    Cpu(u32),
+    Io(u32),
+}
+
+/*
+ *  A stretch of a process that holds a named lock.
+ *
+ *  * `start` and `length` count the CPU time the process has received, as if its
+ *    CPU bursts ran back to back. The lock is taken once `start` units have run
+ *    and released `length` units later.
+ *  * A section may span an I/O burst, the lock stays held during the I/O.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CriticalSection {
+    pub lock: String,
+    pub start: u32,
+    pub length: u32,
+}
+
+impl CriticalSection {
+    pub fn new(lock: &str, start: u32, length: u32) -> Self {
+        CriticalSection { lock: lock.to_string(), start, length }
+    }
+
+    // CPU time at which the lock is released
+    pub fn end(&self) -> u32 {
+        self.start + self.length
+    }
+}
+
+// `<lock>:<start>:<length>`, e.g. `A:2:3`
+impl FromStr for CriticalSection {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid critical section '{}', expected <lock>:<start>:<length>", spec);
+
+        match spec.split(':').collect::<Vec<&str>>().as_slice() {
+            [lock, start, length] if !lock.trim().is_empty() => Ok(CriticalSection::new(
+                lock.trim(),
+                start.trim().parse().map_err(|_| invalid())?,
+                length.trim().parse().map_err(|_| invalid())?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// `<lock>:<start>:<length>`, the form `from_str` reads
+impl fmt::Display for CriticalSection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.lock, self.start, self.length)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct BaseProcess {
     pub pid: usize,
     pub arrival_time: u32,
+    // Total CPU time over every CPU burst
     pub burst_time: u32,
+    pub priority: u32,
+    // Alternating CPU and I/O bursts, always starts and ends with a CPU burst
+    pub bursts: Vec<Burst>,
+    // Cores the process may run on, empty means any core
+    pub affinity: Vec<usize>,
+    // Requested CPU share for the proportional-share schedulers
+    pub tickets: u32,
+    // -20 (most CPU) to 19 (least CPU), only used by the CFS Scheduler
+    pub nice: i32,
+    // Absolute deadline, only used by the real-time schedulers
+    pub deadline: Option<u32>,
+    // Period of the periodic task that released this job, only used by the Rate Monotonic Scheduler
+    pub period: Option<u32>,
+    // CPU bursts of earlier runs, oldest first, only used to seed burst prediction
+    pub history: Vec<u32>,
+    // Locks the process takes while it runs, sorted by start and never overlapping
+    pub sections: Vec<CriticalSection>,
+    // User or team the process belongs to, only used by the Fair-Share Scheduler
+    pub group: String,
 }
 
 impl BaseProcess {
@@ -12,111 +118,202 @@ impl BaseProcess {
             pid,
             arrival_time,
             burst_time,
+            priority: 0,
+            bursts: vec![Burst::Cpu(burst_time)],
+            affinity: Vec::new(),
+            tickets: DEFAULT_TICKETS,
+            nice: 0,
+            deadline: None,
+            period: None,
+            history: Vec::new(),
+            sections: Vec::new(),
+            group: DEFAULT_GROUP.to_string(),
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct FCFSProcess {
-    pub base: BaseProcess,
-    pub start_time: u32,
-    pub completion_time: u32,
-    pub turnaround_time: u32,
-    pub waiting_time: u32,
-}
+    // Lower value = higher priority, only used by the Priority Scheduler
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
 
-impl FCFSProcess {
-    pub fn new(base: BaseProcess) -> Self {
-        FCFSProcess {
-            base,
-            start_time: 0,
-            completion_time: 0,
-            turnaround_time: 0,
-            waiting_time: 0,
-        }
+    // Replaces the single CPU burst with a CPU / I/O sequence, `burst_time` becomes the total CPU time
+    pub fn with_bursts(mut self, bursts: Vec<Burst>) -> Self {
+        self.burst_time = bursts
+            .iter()
+            .map(|b| match b {
+                Burst::Cpu(time) => *time,
+                Burst::Io(_) => 0,
+            })
+            .sum();
+        self.bursts = bursts;
+        self
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct SJFProcess {
-    pub base: BaseProcess,
-    pub remaining_time: u32,
-    pub completion_time: u32,
-    pub turnaround_time: u32,
-    pub waiting_time: u32,
-}
+    pub fn with_affinity(mut self, affinity: Vec<usize>) -> Self {
+        self.affinity = affinity;
+        self
+    }
 
-impl SJFProcess {
-    pub fn new(base: BaseProcess) -> Self {
-        SJFProcess {
-            remaining_time: base.burst_time,
-            base,
-            completion_time: 0,
-            turnaround_time: 0,
-            waiting_time: 0,
-        }
+    pub fn with_tickets(mut self, tickets: u32) -> Self {
+        self.tickets = tickets;
+        self
+    }
+
+    pub fn with_nice(mut self, nice: i32) -> Self {
+        self.nice = nice.clamp(-20, 19);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: u32) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_period(mut self, period: u32) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn with_history(mut self, history: Vec<u32>) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_sections(mut self, mut sections: Vec<CriticalSection>) -> Self {
+        sections.sort_by_key(|section| section.start);
+        self.sections = sections;
+        self
+    }
+
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    pub fn weight(&self) -> u32 {
+        nice_to_weight(self.nice)
+    }
+
+    pub fn allows_core(&self, core: usize) -> bool {
+        self.affinity.is_empty() || self.affinity.contains(&core)
+    }
+
+    pub fn io_time(&self) -> u32 {
+        self.bursts
+            .iter()
+            .map(|b| match b {
+                Burst::Io(time) => *time,
+                Burst::Cpu(_) => 0,
+            })
+            .sum()
     }
 }
 
+/*
+ *  Runtime state of a process while a scheduler is running it.
+ *
+ *  * `burst_index` points at the current CPU burst in `base.bursts`.
+ *  * `ready_since` is the last time the process entered the ready queue
+ *    (arrival, I/O completion or preemption).
+ *  * `last_core` is the core the process last ran on, a dispatch on any other
+ *    core counts as a migration.
+ *  * `last_run` is the CPU time of the current (or last) dispatch.
+ *  * `blocked_until` is set while the process waits for its I/O, until it is dispatched again.
+ *  * `lock_priority` is the priority a lock the process holds lends it (inheritance
+ *    or ceiling), the process runs at the higher of it and `priority`.
+ *  * `lock_wait_time` and `inversion_time` add up the time spent blocked on a lock and
+ *    the time a process of lower priority ran while this one waited on its lock.
+ */
 #[derive(Debug, Clone)]
-pub struct RRProcess {
+pub struct ProcessState {
     pub base: BaseProcess,
+    pub burst_index: usize,
     pub remaining_time: u32,
-    pub completion_time: u32,
-    pub turnaround_time: u32,
-    pub waiting_time: u32,
+    // Own priority, starts at `base.priority` and only changes through aging
+    pub priority: u32,
+    // (time, effective priority) every time the effective priority changed
+    pub priority_history: Vec<(u32, u32)>,
+    pub ready_since: u32,
+    pub start_time: Option<u32>,
+    pub completion_time: Option<u32>,
+    pub last_core: Option<usize>,
+    pub migrations: u32,
+    pub last_run: u32,
+    pub blocked_until: Option<u32>,
+    pub lock_priority: Option<u32>,
+    pub lock_wait_time: u32,
+    pub inversion_time: u32,
 }
 
-impl RRProcess {
+impl ProcessState {
     pub fn new(base: BaseProcess) -> Self {
-        RRProcess {
-            remaining_time: base.burst_time,
+        let remaining_time = match base.bursts.first() {
+            Some(Burst::Cpu(time)) => *time,
+            _ => base.burst_time,
+        };
+
+        ProcessState {
+            remaining_time,
+            priority: base.priority,
+            priority_history: vec![(base.arrival_time, base.priority)],
+            ready_since: base.arrival_time,
             base,
-            completion_time: 0,
-            turnaround_time: 0,
-            waiting_time: 0,
+            burst_index: 0,
+            start_time: None,
+            completion_time: None,
+            last_core: None,
+            migrations: 0,
+            last_run: 0,
+            blocked_until: None,
+            lock_priority: None,
+            lock_wait_time: 0,
+            inversion_time: 0,
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct PriorityProcess {
-    pub base: BaseProcess,
-    pub priority: u32,
-    pub completion_time: u32,
-    pub turnaround_time: u32,
-    pub waiting_time: u32
-}
+    // Priority the process is scheduled at, a lock it holds may raise it above its own
+    pub fn effective_priority(&self) -> u32 {
+        self.lock_priority.map_or(self.priority, |lock_priority| lock_priority.min(self.priority))
+    }
 
-impl PriorityProcess {
-    pub fn new(base: BaseProcess, priority: u32) -> Self {
-        PriorityProcess { 
-            base,
-            priority,
-            completion_time: 0,
-            turnaround_time: 0,
-            waiting_time: 0 
+    // Records the effective priority at `time` if it changed
+    pub fn record_priority(&mut self, time: u32) {
+        let priority = self.effective_priority();
+        if self.priority_history.last().is_none_or(|&(_, last)| last != priority) {
+            self.priority_history.push((time, priority));
         }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct HRRNProcess {
-    pub base: BaseProcess,
-    pub completion_time: u32,
-    pub response_time: u32,
-    pub turnaround_time: u32,
-    pub waiting_time: u32
-}
+    pub fn set_lock_priority(&mut self, lock_priority: Option<u32>, time: u32) {
+        self.lock_priority = lock_priority;
+        self.record_priority(time);
+    }
 
-impl HRRNProcess {
-    pub fn new(base: BaseProcess) -> Self {
-        HRRNProcess {
-            base,
-            completion_time: 0,
-            response_time: 0,
-            turnaround_time: 0, 
-            waiting_time: 0 
+    // Inside a critical section, the process holds its lock or takes it as soon as it runs
+    pub fn in_critical_section(&self) -> bool {
+        if self.base.sections.is_empty() {
+            return false;
         }
+
+        let cpu_time = self.cpu_time();
+        self.base.sections.iter().any(|section| section.start <= cpu_time && cpu_time - section.start < section.length)
     }
-}
\ No newline at end of file
+
+    // CPU time received so far, over every CPU burst
+    pub fn cpu_time(&self) -> u32 {
+        let current = match self.base.bursts.get(self.burst_index) {
+            Some(Burst::Cpu(time)) => *time,
+            _ => 0,
+        };
+        let done: u32 = self.base.bursts[..self.burst_index.min(self.base.bursts.len())]
+            .iter()
+            .map(|b| match b {
+                Burst::Cpu(time) => *time,
+                Burst::Io(_) => 0,
+            })
+            .sum();
+
+        done + current.saturating_sub(self.remaining_time)
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+
+use crate::models::schedule_result::{ProcessResult, ScheduleResult};
+
+/*
+ *  Every aggregate of a schedule, computed the same way for every algorithm.
+ *
+ *  * Normalized turnaround is turnaround / CPU time, 1.0 means the process never waited.
+ *  * Percentiles use the nearest-rank method over every process.
+ *  * Standard deviations are over the whole population, not a sample.
+ *  * Fairness is Jain's index of the CPU share by tickets for lottery and stride and by nice
+ *    weight otherwise, see `ScheduleResult::fairness_basis`.
+ *  * Everything is added up in f64, a million waits of a billion units still average exactly.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metrics {
+    pub processes: usize,
+    pub avg_turnaround_time: f64,
+    pub avg_waiting_time: f64,
+    pub avg_response_time: f64,
+    pub avg_normalized_turnaround: f64,
+    pub max_normalized_turnaround: f64,
+    pub max_waiting_time: u32,
+    pub p50_waiting_time: u32,
+    pub p95_waiting_time: u32,
+    pub waiting_time_std_dev: f64,
+    pub turnaround_time_std_dev: f64,
+    pub throughput: f64,
+    pub cpu_utilization: f64,
+    pub makespan: u32,
+    pub context_switches: usize,
+    pub fairness_index: f64,
+}
+
+fn normalized_turnaround(p: &ProcessResult) -> f64 {
+    p.turnaround_time as f64 / p.burst_time.max(1) as f64
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let mean = mean(values);
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+// Smallest value with at least `percentile`% of the values at or below it
+fn percentile(sorted: &[u32], percentile: u32) -> u32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    let rank = (sorted.len() * percentile as usize).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+impl Metrics {
+    pub fn new(result: &ScheduleResult) -> Self {
+        let turnaround: Vec<f64> = result.processes.iter().map(|p| p.turnaround_time as f64).collect();
+        let waiting: Vec<f64> = result.processes.iter().map(|p| p.waiting_time as f64).collect();
+        let normalized: Vec<f64> = result.processes.iter().map(normalized_turnaround).collect();
+
+        let mut sorted_waiting: Vec<u32> = result.processes.iter().map(|p| p.waiting_time).collect();
+        sorted_waiting.sort_unstable();
+
+        Metrics {
+            processes: result.processes.len(),
+            avg_turnaround_time: result.avg_turnaround_time(),
+            avg_waiting_time: result.avg_waiting_time(),
+            avg_response_time: result.avg_response_time(),
+            avg_normalized_turnaround: mean(&normalized),
+            max_normalized_turnaround: normalized.iter().copied().fold(0.0, f64::max),
+            max_waiting_time: sorted_waiting.last().copied().unwrap_or(0),
+            p50_waiting_time: percentile(&sorted_waiting, 50),
+            p95_waiting_time: percentile(&sorted_waiting, 95),
+            waiting_time_std_dev: std_dev(&waiting),
+            turnaround_time_std_dev: std_dev(&turnaround),
+            throughput: result.throughput(),
+            cpu_utilization: result.cpu_utilization(),
+            makespan: result.makespan(),
+            context_switches: result.context_switches(),
+            fairness_index: result.fairness_index(result.fairness_basis()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{cpu_process::BaseProcess, schedule_result::ShareBasis},
+        scheduler::{Scheduler, SchedulerConfig, round_robin::RoundRobinScheduler, stride::StrideScheduler},
+        utils::gantt_display::GanttChart,
+    };
+
+    #[test]
+    fn long_waits_average_exactly() {
+        // Waits of 1,000,000,000 and 1,000,000,001 units, one after the other
+        let processes = (0..100_000)
+            .map(|pid| {
+                let process = BaseProcess::new(pid, 0, 1);
+                let completion_time = 1_000_000_001 + (pid % 2) as u32;
+                ProcessResult::new(&process, completion_time - 1, completion_time)
+            })
+            .collect();
+        let metrics = ScheduleResult::new("test".to_string(), processes, GanttChart::new()).metrics();
+
+        assert_eq!(metrics.avg_waiting_time, 1_000_000_000.5);
+        assert_eq!(metrics.waiting_time_std_dev, 0.5);
+    }
+
+    #[test]
+    fn fairness_follows_the_share_the_scheduler_works_with() {
+        let processes: Vec<BaseProcess> = (1..=3)
+            .map(|pid| BaseProcess::new(pid, 0, 600).with_tickets(100 * pid as u32))
+            .collect();
+        let result = StrideScheduler::new(1).schedule(&processes, &SchedulerConfig::default()).unwrap();
+        let metrics = result.metrics();
+
+        assert_eq!(result.fairness_basis(), ShareBasis::Tickets);
+        assert_eq!(metrics.fairness_index, result.fairness_index(ShareBasis::Tickets));
+        assert!(metrics.fairness_index > 0.99);
+        assert!(result.fairness_index(ShareBasis::Weight) < 0.9);
+
+        let result = RoundRobinScheduler::new(1).schedule(&processes, &SchedulerConfig::default()).unwrap();
+        assert_eq!(result.metrics().fairness_index, result.fairness_index(ShareBasis::Weight));
+    }
+}
@@ -1 +1,5 @@
 pub mod cpu_process;
+pub mod metrics;
+pub mod periodic_task;
+pub mod schedule_result;
+pub mod trace;
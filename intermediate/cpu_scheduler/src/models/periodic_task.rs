@@ -0,0 +1,135 @@
+use crate::models::cpu_process::BaseProcess;
+
+// Longest hyperperiod that gets simulated, past this the job list gets too long to read
+pub const MAX_HYPERPERIOD: u32 = 10_000;
+
+/*
+ *  Periodic real-time task, released every `period` starting at `offset`.
+ *
+ *  * Every job needs at most `wcet` time units of CPU.
+ *  * A job released at `r` must complete by `r + deadline`, the deadline
+ *    is relative and defaults to the period (implicit deadline).
+ */
+#[derive(Debug, Clone)]
+pub struct PeriodicTask {
+    pub id: usize,
+    pub period: u32,
+    // Worst-case execution time
+    pub wcet: u32,
+    pub deadline: u32,
+    pub offset: u32,
+}
+
+impl PeriodicTask {
+    pub fn new(id: usize, period: u32, wcet: u32) -> Self {
+        PeriodicTask {
+            id,
+            period,
+            wcet,
+            deadline: period,
+            offset: 0,
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: u32) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn utilization(&self) -> f64 {
+        self.wcet as f64 / self.period as f64
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Least common multiple of the periods, None past `MAX_HYPERPERIOD`
+pub fn hyperperiod(tasks: &[PeriodicTask]) -> Option<u32> {
+    let mut lcm: u64 = 1;
+
+    for task in tasks {
+        let period = task.period.max(1) as u64;
+        lcm = lcm / gcd(lcm, period) * period;
+        if lcm > MAX_HYPERPERIOD as u64 {
+            return None;
+        }
+    }
+
+    Some(lcm as u32)
+}
+
+/*
+ *  Jobs released over the simulation window, one process per job.
+ *
+ *  * The window is the largest offset plus one hyperperiod, after that the
+ *    release pattern repeats.
+ *  * Every job of a task keeps the task id as its pid, so the Gantt chart
+ *    and the results group jobs by task.
+ *  * A window or a deadline past the last time unit is an error.
+ */
+pub fn release_jobs(tasks: &[PeriodicTask], hyperperiod: u32) -> Result<Vec<BaseProcess>, String> {
+    let max_offset = tasks.iter().map(|t| t.offset).max().unwrap_or(0);
+    let horizon = max_offset
+        .checked_add(hyperperiod)
+        .ok_or_else(|| format!("Offset {} plus the hyperperiod {} goes past {}, the last time unit", max_offset, hyperperiod, u32::MAX))?;
+    let mut jobs = Vec::new();
+
+    for task in tasks {
+        let mut release = Some(task.offset);
+        while let Some(at) = release.filter(|&at| at < horizon) {
+            let deadline = at.checked_add(task.deadline).ok_or_else(|| {
+                format!("Task {} released at {} has its deadline past {}, the last time unit", task.id, at, u32::MAX)
+            })?;
+            jobs.push(
+                BaseProcess::new(task.id, at, task.wcet)
+                    .with_deadline(deadline)
+                    .with_period(task.period),
+            );
+            release = at.checked_add(task.period);
+        }
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hyperperiod_is_the_lcm_of_the_periods() {
+        let tasks = vec![PeriodicTask::new(1, 4, 1), PeriodicTask::new(2, 6, 2), PeriodicTask::new(3, 12, 3)];
+        assert_eq!(hyperperiod(&tasks), Some(12));
+
+        let tasks = vec![PeriodicTask::new(1, 9_999, 1), PeriodicTask::new(2, 9_998, 1)];
+        assert_eq!(hyperperiod(&tasks), None);
+    }
+
+    #[test]
+    fn releases_every_job_of_the_window() {
+        let tasks = vec![PeriodicTask::new(1, 4, 1).with_offset(2), PeriodicTask::new(2, 6, 2).with_deadline(5)];
+
+        let jobs = release_jobs(&tasks, 12).unwrap();
+        let releases: Vec<(usize, u32, Option<u32>)> = jobs.iter().map(|j| (j.pid, j.arrival_time, j.deadline)).collect();
+        assert_eq!(releases, vec![
+            (1, 2, Some(6)), (1, 6, Some(10)), (1, 10, Some(14)),
+            (2, 0, Some(5)), (2, 6, Some(11)), (2, 12, Some(17)),
+        ]);
+    }
+
+    #[test]
+    fn rejects_releases_past_the_last_time_unit() {
+        let offset = vec![PeriodicTask::new(1, 4, 1).with_offset(4_294_967_290)];
+        assert!(release_jobs(&offset, 12).is_err());
+
+        let deadline = vec![PeriodicTask::new(1, 4, 1).with_offset(4_294_967_280).with_deadline(20)];
+        assert!(release_jobs(&deadline, 4).is_err());
+    }
+}
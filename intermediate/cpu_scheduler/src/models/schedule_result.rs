@@ -0,0 +1,638 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use crate::{
+    models::{cpu_process::{BaseProcess, CriticalSection, ProcessState}, metrics::Metrics, trace::Trace},
+    scheduler::locks::LockProtocol,
+    utils::gantt_display::{GanttChart, SegmentKind},
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessResult {
+    pub pid: usize,
+    pub arrival_time: u32,
+    pub burst_time: u32,
+    pub io_time: u32,
+    pub priority: u32,
+    pub tickets: u32,
+    pub nice: i32,
+    pub weight: u32,
+    pub group: String,
+    pub start_time: u32,
+    pub completion_time: u32,
+    pub turnaround_time: u32,
+    pub waiting_time: u32,
+    pub response_time: u32,
+    // (time, effective priority), only changes past the first entry through aging or a lock protocol
+    pub priority_history: Vec<(u32, u32)>,
+    // Dispatches on a different core than the previous one
+    pub migrations: u32,
+    // (time, vruntime) whenever it changed, only filled by the CFS Scheduler
+    pub vruntime_history: Vec<(u32, f32)>,
+    // Absolute deadline, only set for real-time jobs
+    pub deadline: Option<u32>,
+    // (predicted, actual) length of every CPU burst, only filled by the predictive SJF schedulers
+    pub burst_predictions: Vec<(f32, u32)>,
+    pub sections: Vec<CriticalSection>,
+    // Time spent blocked on a lock, counted in the waiting time
+    pub lock_wait_time: u32,
+    // Time a process of lower priority ran while this one waited on its lock, see `Locks::account`
+    pub inversion_time: u32,
+}
+
+impl ProcessResult {
+    pub fn new(base: &BaseProcess, start_time: u32, completion_time: u32) -> Self {
+        let turnaround_time = completion_time - base.arrival_time;
+        let io_time = base.io_time();
+
+        ProcessResult {
+            pid: base.pid,
+            arrival_time: base.arrival_time,
+            burst_time: base.burst_time,
+            io_time,
+            priority: base.priority,
+            tickets: base.tickets,
+            nice: base.nice,
+            weight: base.weight(),
+            group: base.group.clone(),
+            start_time,
+            completion_time,
+            turnaround_time,
+            // Time spent in the ready queue, I/O time is not waiting for the CPU
+            waiting_time: turnaround_time.saturating_sub(base.burst_time + io_time),
+            response_time: start_time.saturating_sub(base.arrival_time),
+            priority_history: Vec::new(),
+            migrations: 0,
+            vruntime_history: Vec::new(),
+            deadline: base.deadline,
+            burst_predictions: Vec::new(),
+            sections: base.sections.clone(),
+            lock_wait_time: 0,
+            inversion_time: 0,
+        }
+    }
+
+    pub fn missed_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| self.completion_time > deadline)
+    }
+}
+
+impl From<&ProcessState> for ProcessResult {
+    fn from(p: &ProcessState) -> Self {
+        let mut result = ProcessResult::new(
+            &p.base,
+            p.start_time.unwrap_or(p.base.arrival_time),
+            p.completion_time.unwrap_or(p.base.arrival_time),
+        );
+        result.priority_history = p.priority_history.clone();
+        result.migrations = p.migrations;
+        result.lock_wait_time = p.lock_wait_time;
+        result.inversion_time = p.inversion_time;
+        result
+    }
+}
+
+// What a process' requested CPU share is proportional to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ShareBasis {
+    Tickets,
+    // Weight of the nice value
+    Weight,
+}
+
+impl fmt::Display for ShareBasis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShareBasis::Tickets => write!(f, "Tickets"),
+            ShareBasis::Weight => write!(f, "Weight"),
+        }
+    }
+}
+
+// CPU share of one group of processes, see `ScheduleResult::group_shares`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupShare {
+    pub group: String,
+    pub weight: u32,
+    pub processes: usize,
+    // Percent of the CPU time
+    pub target: f64,
+    pub achieved: f64,
+}
+
+/*
+ *  Common output of every scheduler, so the same workload can be run through
+ *  any algorithm and compared on the same fields.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleResult {
+    pub algorithm_name: String,
+    pub processes: Vec<ProcessResult>,
+    pub gantt_chart: GanttChart,
+    // Set by the share based schedulers (lottery, stride, CFS), adds the CPU share report to `display`
+    pub share_basis: Option<ShareBasis>,
+    // (group, weight) of every group, set by the Fair-Share Scheduler, adds the group share report to `display`
+    pub group_weights: Option<Vec<(String, u32)>>,
+    // Set when the workload has critical sections, adds the priority inversion report to `display`
+    pub lock_protocol: Option<LockProtocol>,
+    // Every scheduling decision, only recorded when `SchedulerConfig::trace` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Trace>,
+    // (name, value) of the algorithm settings followed by the run settings (cores, context switch...)
+    pub parameters: Vec<(&'static str, String)>,
+}
+
+impl ScheduleResult {
+    pub fn new(algorithm_name: String, mut processes: Vec<ProcessResult>, mut gantt_chart: GanttChart) -> Self {
+        processes.sort_by_key(|p| p.pid);
+
+        gantt_chart.deadline_misses = processes
+            .iter()
+            .filter(|p| p.missed_deadline())
+            .filter_map(|p| p.deadline.map(|deadline| (p.pid, deadline)))
+            .collect();
+
+        ScheduleResult {
+            algorithm_name,
+            processes,
+            gantt_chart,
+            share_basis: None,
+            group_weights: None,
+            lock_protocol: None,
+            trace: None,
+            parameters: Vec::new(),
+        }
+    }
+
+    // First result of `pid`, a binary search since `processes` is sorted by pid
+    pub fn process_mut(&mut self, pid: usize) -> Option<&mut ProcessResult> {
+        let position = self.processes.partition_point(|p| p.pid < pid);
+        self.processes.get_mut(position).filter(|p| p.pid == pid)
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        Metrics::new(self)
+    }
+
+    fn average(&self, value: impl Fn(&ProcessResult) -> u32) -> f64 {
+        if self.processes.is_empty() {
+            return 0.0;
+        }
+
+        self.processes
+            .iter()
+            .map(|p| value(p) as f64)
+            .sum::<f64>() / self.processes.len() as f64
+    }
+
+    pub fn avg_turnaround_time(&self) -> f64 {
+        self.average(|p| p.turnaround_time)
+    }
+
+    pub fn avg_waiting_time(&self) -> f64 {
+        self.average(|p| p.waiting_time)
+    }
+
+    pub fn avg_response_time(&self) -> f64 {
+        self.average(|p| p.response_time)
+    }
+
+    // Dispatches of a different process than the one that just ran on the same core
+    pub fn context_switches(&self) -> usize {
+        (0..self.gantt_chart.cores)
+            .map(|core| {
+                let pids: Vec<usize> = self.gantt_chart.process_segments(core).map(|s| s.pid).collect();
+
+                pids.windows(2)
+                    .filter(|pair| pair[0] != pair[1])
+                    .count()
+            })
+            .sum()
+    }
+
+    pub fn migrations(&self) -> u32 {
+        self.processes.iter().map(|p| p.migrations).sum()
+    }
+
+    // Jobs that completed after their deadline
+    pub fn deadline_misses(&self) -> usize {
+        self.processes.iter().filter(|p| p.missed_deadline()).count()
+    }
+
+    // Time from the first arrival until the last completion
+    pub fn makespan(&self) -> u32 {
+        let first_arrival = self.processes.iter().map(|p| p.arrival_time).min().unwrap_or(0);
+        let last_completion = self.processes.iter().map(|p| p.completion_time).max().unwrap_or(0);
+
+        last_completion.saturating_sub(first_arrival)
+    }
+
+    // Completed processes per time unit
+    pub fn throughput(&self) -> f64 {
+        match self.makespan() {
+            0 => 0.0,
+            makespan => self.processes.len() as f64 / makespan as f64,
+        }
+    }
+
+    // Percentage of the makespan the CPUs spent running a process, averaged over every core
+    pub fn cpu_utilization(&self) -> f64 {
+        let busy_time: u64 = self.processes.iter().map(|p| p.burst_time as u64).sum();
+
+        match self.makespan() {
+            0 => 0.0,
+            makespan => busy_time as f64 / (makespan as u64 * self.gantt_chart.cores as u64) as f64 * 100.0,
+        }
+    }
+
+    // Percentage of the makespan `core` spent running a process
+    pub fn core_utilization(&self, core: usize) -> f64 {
+        let busy_time: u64 = self.gantt_chart
+            .process_segments(core)
+            .map(|s| (s.end_time - s.start_time) as u64)
+            .sum();
+
+        match self.makespan() {
+            0 => 0.0,
+            makespan => busy_time as f64 / makespan as f64 * 100.0,
+        }
+    }
+
+    // From the last arrival to the first completion, when every process competes for the CPU
+    pub fn contention_window(&self) -> (u32, u32) {
+        let last_arrival = self.processes.iter().map(|p| p.arrival_time).max().unwrap_or(0);
+        let first_completion = self.processes.iter().map(|p| p.completion_time).min().unwrap_or(0);
+
+        if last_arrival < first_completion {
+            (last_arrival, first_completion)
+        } else {
+            // Some process finished before the last one arrived, measure over the whole run
+            let first_arrival = self.processes.iter().map(|p| p.arrival_time).min().unwrap_or(0);
+            let last_completion = self.processes.iter().map(|p| p.completion_time).max().unwrap_or(0);
+            (first_arrival, last_completion)
+        }
+    }
+
+    /*
+     *  (pid, requested %, achieved %) for every process.
+     *
+     *  * Requested: its tickets (or weight) out of every ticket (or weight) in the workload.
+     *  * Achieved: its CPU time out of all CPU time given out in the contention window.
+     */
+    pub fn cpu_shares(&self, basis: ShareBasis) -> Vec<(usize, f64, f64)> {
+        let (window_start, window_end) = self.contention_window();
+        let requested_by = |p: &ProcessResult| match basis {
+            ShareBasis::Tickets => p.tickets,
+            ShareBasis::Weight => p.weight,
+        };
+        let total_requested: u64 = self.processes.iter().map(|p| requested_by(p) as u64).sum();
+
+        // CPU time of every pid in one pass over the chart
+        let mut cpu_times: BTreeMap<usize, u64> = BTreeMap::new();
+        for s in self.gantt_chart.segments.iter().filter(|s| s.kind == SegmentKind::Process) {
+            *cpu_times.entry(s.pid).or_default() +=
+                s.end_time.min(window_end).saturating_sub(s.start_time.max(window_start)) as u64;
+        }
+        let cpu_time = |pid: usize| cpu_times.get(&pid).copied().unwrap_or(0);
+
+        let total_cpu_time: u64 = self.processes.iter().map(|p| cpu_time(p.pid)).sum();
+
+        self.processes
+            .iter()
+            .map(|p| {
+                let requested = match total_requested {
+                    0 => 0.0,
+                    total => requested_by(p) as f64 / total as f64 * 100.0,
+                };
+                let achieved = match total_cpu_time {
+                    0 => 0.0,
+                    total => cpu_time(p.pid) as f64 / total as f64 * 100.0,
+                };
+
+                (p.pid, requested, achieved)
+            })
+            .collect()
+    }
+
+    // Share the fairness of the schedule is measured against, tickets for lottery and stride, nice weight otherwise
+    pub fn fairness_basis(&self) -> ShareBasis {
+        self.share_basis.unwrap_or(ShareBasis::Weight)
+    }
+
+    /*
+     *  Jain's fairness index of achieved / requested share over the contention window,
+     *  1.0 when every process got exactly its share, down to 1/n when one process got everything.
+     */
+    pub fn fairness_index(&self, basis: ShareBasis) -> f64 {
+        let ratios: Vec<f64> = self.cpu_shares(basis)
+            .into_iter()
+            .filter(|&(_, requested, _)| requested > 0.0)
+            .map(|(_, requested, achieved)| achieved / requested)
+            .collect();
+
+        jain_index(&ratios)
+    }
+
+    // From the last group to arrive to the first group to finish, when every group competes for the CPU
+    pub fn group_window(&self) -> (u32, u32) {
+        let mut spans: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+        for p in &self.processes {
+            let span = spans.entry(p.group.as_str()).or_insert((p.arrival_time, p.completion_time));
+            span.0 = span.0.min(p.arrival_time);
+            span.1 = span.1.max(p.completion_time);
+        }
+
+        let last_arrival = spans.values().map(|span| span.0).max().unwrap_or(0);
+        let first_completion = spans.values().map(|span| span.1).min().unwrap_or(0);
+
+        if last_arrival < first_completion {
+            (last_arrival, first_completion)
+        } else {
+            // Some group was done before the last one arrived, measure over the whole run
+            let first_arrival = spans.values().map(|span| span.0).min().unwrap_or(0);
+            let last_completion = spans.values().map(|span| span.1).max().unwrap_or(0);
+            (first_arrival, last_completion)
+        }
+    }
+
+    /*
+     *  Share of every group in `group_weights`, empty for the other schedulers.
+     *
+     *  * Target: its weight out of the weights of every group.
+     *  * Achieved: the CPU time of its processes out of all CPU time given out in the group window.
+     */
+    pub fn group_shares(&self) -> Vec<GroupShare> {
+        let Some(group_weights) = &self.group_weights else {
+            return Vec::new();
+        };
+
+        let (window_start, window_end) = self.group_window();
+        let total_weight: u64 = group_weights.iter().map(|(_, weight)| *weight as u64).sum();
+
+        let groups: BTreeMap<usize, &str> = self.processes.iter().map(|p| (p.pid, p.group.as_str())).collect();
+        let mut cpu_times: BTreeMap<&str, u64> = BTreeMap::new();
+        for s in self.gantt_chart.segments.iter().filter(|s| s.kind == SegmentKind::Process) {
+            let Some(&group) = groups.get(&s.pid) else {
+                continue;
+            };
+            *cpu_times.entry(group).or_default() +=
+                s.end_time.min(window_end).saturating_sub(s.start_time.max(window_start)) as u64;
+        }
+        let total_cpu_time: u64 = cpu_times.values().sum();
+
+        group_weights
+            .iter()
+            .map(|(group, weight)| GroupShare {
+                group: group.clone(),
+                weight: *weight,
+                processes: self.processes.iter().filter(|p| p.group == *group).count(),
+                target: match total_weight {
+                    0 => 0.0,
+                    total => *weight as f64 / total as f64 * 100.0,
+                },
+                achieved: match total_cpu_time {
+                    0 => 0.0,
+                    total => cpu_times.get(group.as_str()).copied().unwrap_or(0) as f64 / total as f64 * 100.0,
+                },
+            })
+            .collect()
+    }
+
+    fn display_group_shares(&self) {
+        const HEADER: &str = "|------------|--------|-----------|----------|----------|";
+
+        let (window_start, window_end) = self.group_window();
+        println!("\nGroup Share from {} to {}:", window_start, window_end);
+        println!("{}", HEADER);
+        println!("|{:^12}|{:^8}|{:^11}|{:^10}|{:^10}|", "Group", "Weight", "Processes", "Target", "Achieved");
+        println!("{}", HEADER);
+
+        let shares = self.group_shares();
+        for share in &shares {
+            println!(
+                "|{:^12}|{:^8}|{:^11}|{:^10}|{:^10}|",
+                share.group,
+                share.weight,
+                share.processes,
+                format!("{:.2}%", share.target),
+                format!("{:.2}%", share.achieved),
+            );
+        }
+
+        let ratios: Vec<f64> = shares
+            .iter()
+            .filter(|share| share.target > 0.0)
+            .map(|share| share.achieved / share.target)
+            .collect();
+
+        println!("{}", HEADER);
+        println!("Fairness between groups (Jain's index): {:.3}", jain_index(&ratios));
+    }
+
+    fn display_cpu_shares(&self, basis: ShareBasis) {
+        const HEADER: &str = "|---------|-------------|-----------|----------|";
+
+        let (window_start, window_end) = self.contention_window();
+        println!("\nCPU Share from {} to {}:", window_start, window_end);
+
+        println!("{}", HEADER);
+        println!("|{:^9}|{:^13}|{:^11}|{:^10}|", "Process", basis.to_string(), "Requested", "Achieved");
+        println!("{}", HEADER);
+
+        for (p, (_, requested, achieved)) in self.processes.iter().zip(self.cpu_shares(basis)) {
+            let share = match basis {
+                ShareBasis::Tickets => p.tickets.to_string(),
+                ShareBasis::Weight => format!("{} ({:+})", p.weight, p.nice),
+            };
+
+            println!(
+                "|{:^9}|{:^13}|{:^11}|{:^10}|",
+                p.pid,
+                share,
+                format!("{:.2}%", requested),
+                format!("{:.2}%", achieved),
+            );
+        }
+
+        println!("{}", HEADER);
+        println!("Fairness (Jain's index): {:.3}", self.fairness_index(basis));
+    }
+
+    fn display_vruntime_history(processes: &[ProcessResult]) {
+        println!("\nVirtual Runtime Progression (vruntime @ time):");
+
+        for p in processes {
+            let history: Vec<String> = p.vruntime_history
+                .iter()
+                .map(|(time, vruntime)| format!("{:.2} @ {}", vruntime, time))
+                .collect();
+
+            println!("  P{} (nice {}): {}", p.pid, p.nice, history.join(" -> "));
+        }
+    }
+
+    fn display_table(processes: &[ProcessResult]) {
+        const HEADER: &str = "|---------|----|----|----|----------|----|----|-----|----|----|";
+
+        println!("\n{}", HEADER);
+        println!(
+            "|{:^9}|{:^4}|{:^4}|{:^4}|{:^10}|{:^4}|{:^4}|{:^5}|{:^4}|{:^4}|",
+            "Process", "AT", "BT", "IO", "Priority", "ST", "CT", "TAT", "WT", "RT"
+        );
+        println!("{}", HEADER);
+
+        for p in processes {
+            println!(
+                "|{:^9}|{:^4}|{:^4}|{:^4}|{:^10}|{:^4}|{:^4}|{:^5}|{:^4}|{:^4}|",
+                p.pid,
+                p.arrival_time,
+                p.burst_time,
+                p.io_time,
+                p.priority,
+                p.start_time,
+                p.completion_time,
+                p.turnaround_time,
+                p.waiting_time,
+                p.response_time,
+            );
+        }
+
+        println!("{}\n", HEADER);
+    }
+
+    fn display_priority_inversion(&self, protocol: LockProtocol) {
+        const HEADER: &str = "|---------|----------|--------------------|-----------|----------|";
+
+        println!("\nPriority Inversion ({}):", protocol);
+        println!("{}", HEADER);
+        println!("|{:^9}|{:^10}|{:^20}|{:^11}|{:^10}|", "Process", "Priority", "Sections", "Lock Wait", "Inverted");
+        println!("{}", HEADER);
+
+        for p in &self.processes {
+            let sections = match p.sections.as_slice() {
+                [] => "-".to_string(),
+                sections => sections
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+
+            println!(
+                "|{:^9}|{:^10}|{:^20}|{:^11}|{:^10}|",
+                p.pid, p.priority, sections, p.lock_wait_time, p.inversion_time
+            );
+        }
+
+        println!("{}", HEADER);
+    }
+
+    fn display_priority_history(processes: &[ProcessResult]) {
+        println!("\nEffective Priority History (priority @ time):");
+
+        for p in processes {
+            let history: Vec<String> = p.priority_history
+                .iter()
+                .map(|(time, priority)| format!("{} @ {}", priority, time))
+                .collect();
+
+            println!("  P{}: {}", p.pid, history.join(" -> "));
+        }
+    }
+
+    // Aggregate lines printed under the table, shared with the SVG / HTML export
+    pub fn summary_lines(&self) -> Vec<String> {
+        let metrics = self.metrics();
+        let mut lines = vec![
+            format!("Average Turnaround Time: {:.2}", metrics.avg_turnaround_time),
+            format!("Average Waiting Time: {:.2}", metrics.avg_waiting_time),
+            format!("Average Response Time: {:.2}", metrics.avg_response_time),
+            format!(
+                "Normalized Turnaround (TAT / BT): avg {:.2}, max {:.2}",
+                metrics.avg_normalized_turnaround, metrics.max_normalized_turnaround
+            ),
+            format!(
+                "Waiting Time: max {}, p50 {}, p95 {}, std dev {:.2}",
+                metrics.max_waiting_time, metrics.p50_waiting_time, metrics.p95_waiting_time, metrics.waiting_time_std_dev
+            ),
+            format!("Turnaround Time Std Dev: {:.2}", metrics.turnaround_time_std_dev),
+            format!(
+                "Context Switches: {} (overhead: {})",
+                metrics.context_switches,
+                self.gantt_chart.context_switch_time()
+            ),
+            format!("CPU Utilization: {:.2}%", metrics.cpu_utilization),
+            format!("Throughput: {:.2} processes per time unit", metrics.throughput),
+            format!("Makespan: {}", metrics.makespan),
+            format!("Fairness (Jain's Index by {}): {:.3}", self.fairness_basis(), metrics.fairness_index),
+        ];
+
+        if self.gantt_chart.cores > 1 {
+            lines.push(format!("Migrations: {}", self.migrations()));
+            for core in 0..self.gantt_chart.cores {
+                lines.push(format!("  Core {} Utilization: {:.2}%", core, self.core_utilization(core)));
+            }
+        }
+
+        if self.processes.iter().any(|p| p.deadline.is_some()) {
+            lines.push(format!("Deadline Misses: {} of {} jobs", self.deadline_misses(), self.processes.len()));
+        }
+
+        if self.lock_protocol.is_some() {
+            let total: u32 = self.processes.iter().map(|p| p.inversion_time).sum();
+            match self.processes.iter().rev().filter(|p| p.inversion_time > 0).max_by_key(|p| p.inversion_time) {
+                Some(longest) => lines.push(format!(
+                    "Priority Inversion: total {}, longest {} (P{})",
+                    total, longest.inversion_time, longest.pid
+                )),
+                None => lines.push("Priority Inversion: none".to_string()),
+            }
+        }
+
+        lines
+    }
+
+    pub fn display(&self) {
+        println!("\n{}", self.algorithm_name);
+        Self::display_table(&self.processes);
+
+        for line in self.summary_lines() {
+            println!("{}", line);
+        }
+
+        if self.processes.iter().any(|p| p.priority_history.len() > 1) {
+            Self::display_priority_history(&self.processes);
+        }
+
+        if self.processes.iter().any(|p| !p.vruntime_history.is_empty()) {
+            Self::display_vruntime_history(&self.processes);
+        }
+
+        if let Some(basis) = self.share_basis {
+            self.display_cpu_shares(basis);
+        }
+
+        if self.group_weights.is_some() {
+            self.display_group_shares();
+        }
+
+        if let Some(protocol) = self.lock_protocol {
+            self.display_priority_inversion(protocol);
+        }
+
+        self.gantt_chart.display_gantt_chart();
+    }
+}
+
+// (sum of x)^2 / (n * sum of x^2) over achieved / requested ratios, see `fairness_index`
+fn jain_index(ratios: &[f64]) -> f64 {
+    let sum: f64 = ratios.iter().sum();
+    let sum_of_squares: f64 = ratios.iter().map(|r| r * r).sum();
+
+    if sum_of_squares == 0.0 {
+        return 0.0;
+    }
+
+    sum * sum / (ratios.len() as f64 * sum_of_squares)
+}
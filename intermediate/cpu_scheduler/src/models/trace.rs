@@ -0,0 +1,196 @@
+use std::fmt;
+
+use serde::Serialize;
+
+// Value a ready queue orders its processes by, e.g. the remaining time for SJF
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DecisionValue {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+impl DecisionValue {
+    pub fn new(name: &'static str, value: f64) -> Self {
+        DecisionValue { name, value }
+    }
+}
+
+impl fmt::Display for DecisionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.value.fract() == 0.0 {
+            write!(f, "{} {:.0}", self.name, self.value)
+        } else {
+            write!(f, "{} {:.2}", self.name, self.value)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueuedProcess {
+    pub pid: usize,
+    pub value: Option<DecisionValue>,
+}
+
+impl fmt::Display for QueuedProcess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "P{} ({})", self.pid, value),
+            None => write!(f, "P{}", self.pid),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TraceEventKind {
+    Arrival { pid: usize },
+    IoCompletion { pid: usize },
+    // `queue` is the ready queue the process joined, always 0 with a global queue
+    Enqueue { pid: usize, queue: usize, value: Option<DecisionValue> },
+    // `starts_at` is later than the event during a context switch
+    Dispatch { pid: usize, value: Option<DecisionValue>, starts_at: u32 },
+    // `by` is the ready process that goes first, the reason for the preemption
+    Preemption { pid: usize, value: Option<DecisionValue>, by: Option<QueuedProcess> },
+    QuantumExpiry { pid: usize, slice: u32 },
+    IoStart { pid: usize, until: u32 },
+    Completion { pid: usize },
+    // `priority` is the ceiling the process now runs at, with the ceiling protocol
+    LockAcquire { pid: usize, lock: String, priority: Option<u32> },
+    // `priority` is the priority `holder` now runs at, with priority inheritance
+    LockBlocked { pid: usize, lock: String, holder: usize, priority: Option<u32> },
+    // `to` is the waiter the lock is handed to
+    LockRelease { pid: usize, lock: String, to: Option<usize> },
+}
+
+/*
+ *  One scheduling event.
+ *
+ *  * `core` is the core the event happened on, None for arrivals and enqueues.
+ *  * `ready_queues` holds every ready queue in the order its processes would be
+ *    picked, right after the event. For a dispatch it is taken right before, so
+ *    it shows the processes the pick was made from.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceEvent {
+    pub time: u32,
+    pub core: Option<usize>,
+    pub kind: TraceEventKind,
+    pub ready_queues: Vec<Vec<QueuedProcess>>,
+}
+
+impl TraceEvent {
+    // Process the event is about
+    pub fn pid(&self) -> usize {
+        match self.kind {
+            TraceEventKind::Arrival { pid }
+            | TraceEventKind::IoCompletion { pid }
+            | TraceEventKind::Enqueue { pid, .. }
+            | TraceEventKind::Dispatch { pid, .. }
+            | TraceEventKind::Preemption { pid, .. }
+            | TraceEventKind::QuantumExpiry { pid, .. }
+            | TraceEventKind::IoStart { pid, .. }
+            | TraceEventKind::Completion { pid }
+            | TraceEventKind::LockAcquire { pid, .. }
+            | TraceEventKind::LockBlocked { pid, .. }
+            | TraceEventKind::LockRelease { pid, .. } => pid,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            TraceEventKind::Arrival { pid } => format!("P{} arrives", pid),
+            TraceEventKind::IoCompletion { pid } => format!("P{} finishes its I/O", pid),
+            TraceEventKind::Enqueue { pid, queue, value } => {
+                let target = match self.ready_queues.len() {
+                    1 => "the ready queue".to_string(),
+                    _ => format!("the ready queue of core {}", queue),
+                };
+                let process = QueuedProcess { pid: *pid, value: *value };
+                format!("{} joins {}", process, target)
+            }
+            TraceEventKind::Dispatch { pid, value, starts_at } => {
+                let process = QueuedProcess { pid: *pid, value: *value };
+                if *starts_at > self.time {
+                    format!("{} is dispatched, runs from {} after the context switch", process, starts_at)
+                } else {
+                    format!("{} is dispatched", process)
+                }
+            }
+            TraceEventKind::Preemption { pid, value, by } => {
+                let process = QueuedProcess { pid: *pid, value: *value };
+                match by {
+                    Some(by) => format!("{} is preempted, {} goes first", process, by),
+                    None => format!("{} is preempted", process),
+                }
+            }
+            TraceEventKind::QuantumExpiry { pid, slice } => format!("P{} used up its time slice of {}", pid, slice),
+            TraceEventKind::IoStart { pid, until } => format!("P{} blocks on I/O until {}", pid, until),
+            TraceEventKind::Completion { pid } => format!("P{} completes", pid),
+            TraceEventKind::LockAcquire { pid, lock, priority } => match priority {
+                Some(priority) => format!("P{} takes lock {}, runs at its ceiling {}", pid, lock, priority),
+                None => format!("P{} takes lock {}", pid, lock),
+            },
+            TraceEventKind::LockBlocked { pid, lock, holder, priority } => match priority {
+                Some(priority) => format!("P{} blocks on lock {} held by P{}, P{} inherits priority {}", pid, lock, holder, holder, priority),
+                None => format!("P{} blocks on lock {} held by P{}", pid, lock, holder),
+            },
+            TraceEventKind::LockRelease { pid, lock, to } => match to {
+                Some(to) => format!("P{} releases lock {}, P{} takes it", pid, lock, to),
+                None => format!("P{} releases lock {}", pid, lock),
+            },
+        }
+    }
+
+    pub fn describe_ready_queues(&self) -> String {
+        let describe = |queue: &[QueuedProcess]| match queue {
+            [] => "empty".to_string(),
+            _ => queue.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "),
+        };
+
+        match self.ready_queues.as_slice() {
+            [queue] => format!("ready: {}", describe(queue)),
+            queues => queues
+                .iter()
+                .enumerate()
+                .map(|(core, queue)| format!("core {} ready: {}", core, describe(queue)))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        }
+    }
+}
+
+// Every scheduling event of a run in the order it happened, only recorded when `SchedulerConfig::trace` is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    pub fn push(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    // One line per event, followed by the ready queue it left behind
+    pub fn narrate(&self) -> Vec<String> {
+        self.events
+            .iter()
+            .flat_map(|event| {
+                let core = match event.core {
+                    Some(core) => format!("core {}", core),
+                    None => String::new(),
+                };
+
+                [
+                    format!("t={:<5} {:<7} {}", event.time, core, event.describe()),
+                    format!("{:16}{}", "", event.describe_ready_queues()),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn display(&self) {
+        println!("\n{:=^90}", " SCHEDULING DECISIONS ");
+        for line in self.narrate() {
+            println!("{}", line);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use crate::{
+    models::{
+        cpu_process::{BaseProcess, NICE_0_WEIGHT, ProcessState},
+        schedule_result::{ScheduleResult, ShareBasis},
+        trace::DecisionValue,
+    },
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
+
+pub const DEFAULT_TARGET_LATENCY: u32 = 12;
+pub const DEFAULT_MIN_GRANULARITY: u32 = 2;
+
+// vruntime is kept in thousandths of a time unit so low weights don't round to 0
+const VRUNTIME_SCALE: u64 = 1000;
+
+/*
+ *  Model of the Linux Completely Fair Scheduler.
+ *
+ *  * Every process has a weight from its nice value, its vruntime grows by
+ *    run time * NICE_0_WEIGHT / weight, so heavier processes age slower.
+ *  * The runnable process with the smallest vruntime runs next.
+ *  * Time slice: the scheduling period (target latency, stretched to
+ *    min granularity * runnable processes) split by weight, never below min granularity.
+ *  * A process becoming ready preempts the running one when its vruntime is
+ *    more than min granularity behind.
+ *  * A new process starts at the queue's min vruntime, a process waking from I/O gets
+ *    at most half a target latency of credit, so sleeping doesn't bank CPU time.
+ */
+#[derive(Debug)]
+pub struct CfsScheduler {
+    pub target_latency: u32,
+    pub min_granularity: u32,
+}
+
+impl CfsScheduler {
+    pub fn new(target_latency: u32, min_granularity: u32) -> Self {
+        CfsScheduler { target_latency, min_granularity }
+    }
+}
+
+// vruntime of every process, shared by the per-core queues so it follows migrations
+#[derive(Debug, Default)]
+struct Accounting {
+    vruntime: Vec<u64>,
+    // (time, vruntime) every time a process entered or left the CPU
+    history: Vec<Vec<(u32, u64)>>,
+}
+
+struct CfsQueue<'a> {
+    // (vruntime, insertion order, index)
+    ready: BTreeSet<(u64, u64, usize)>,
+    inserted: u64,
+    // Sum of the weights of `ready`, so the time slice doesn't walk the queue
+    ready_weight: u64,
+    min_vruntime: u64,
+    accounting: &'a RefCell<Accounting>,
+    scheduler: &'a CfsScheduler,
+}
+
+impl CfsQueue<'_> {
+    fn weighted(run_time: u32, weight: u32) -> u64 {
+        run_time as u64 * VRUNTIME_SCALE * NICE_0_WEIGHT as u64 / weight.max(1) as u64
+    }
+
+    // vruntime including the time the process has been running since its dispatch
+    fn current_vruntime(&self, idx: usize, processes: &[ProcessState]) -> u64 {
+        let process = &processes[idx];
+        self.accounting.borrow().vruntime[idx] + Self::weighted(process.last_run, process.base.weight())
+    }
+}
+
+impl ReadyQueue for CfsQueue<'_> {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        let mut accounting = self.accounting.borrow_mut();
+        if accounting.vruntime.len() < processes.len() {
+            accounting.vruntime.resize(processes.len(), 0);
+            accounting.history.resize(processes.len(), Vec::new());
+        }
+
+        let process = &processes[idx];
+        let vruntime = if process.start_time.is_none() {
+            self.min_vruntime
+        } else if process.blocked_until.is_some() {
+            let sleeper_credit = self.scheduler.target_latency as u64 * VRUNTIME_SCALE / 2;
+            accounting.vruntime[idx].max(self.min_vruntime.saturating_sub(sleeper_credit))
+        } else {
+            accounting.vruntime[idx]
+        };
+
+        if vruntime != accounting.vruntime[idx] || accounting.history[idx].is_empty() {
+            accounting.history[idx].push((time, vruntime));
+        }
+        accounting.vruntime[idx] = vruntime;
+
+        self.inserted += 1;
+        self.ready.insert((vruntime, self.inserted, idx));
+        self.ready_weight += process.base.weight() as u64;
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        let entry = *self.ready
+            .iter()
+            .find(|&&(_, _, idx)| processes[idx].base.allows_core(core))?;
+
+        self.ready.remove(&entry);
+        self.ready_weight -= processes[entry.2].base.weight() as u64;
+        self.min_vruntime = self.min_vruntime.max(entry.0);
+        Some(entry.2)
+    }
+
+    fn len(&self) -> usize {
+        self.ready.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        self.ready.iter().map(|&(_, _, idx)| idx).collect()
+    }
+
+    // A queued process is ordered by its vruntime when it was queued, a running one by its current vruntime
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        let vruntime = match self.ready.iter().find(|&&(_, _, queued)| queued == idx) {
+            Some(&(vruntime, _, _)) => vruntime,
+            None => self.current_vruntime(idx, processes),
+        };
+
+        Some(DecisionValue::new("vruntime", vruntime as f64 / VRUNTIME_SCALE as f64))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], _time: u32) -> bool {
+        let wakeup_granularity = Self::weighted(self.scheduler.min_granularity, NICE_0_WEIGHT);
+        let current = self.current_vruntime(running, processes);
+
+        self.ready
+            .iter()
+            .find(|&&(_, _, idx)| processes[idx].base.allows_core(core))
+            .is_some_and(|&(vruntime, _, _)| vruntime + wakeup_granularity < current)
+    }
+
+    fn time_slice(&self, running: usize, processes: &[ProcessState]) -> Option<u32> {
+        let min_granularity = self.scheduler.min_granularity.max(1);
+        let runnable = self.ready.len() as u32 + 1;
+        let period = self.scheduler.target_latency.max(min_granularity.saturating_mul(runnable));
+
+        let weight = processes[running].base.weight() as u64;
+        let slice = (period as u64 * weight / (weight + self.ready_weight)) as u32;
+        Some(slice.max(min_granularity))
+    }
+
+    fn stop(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        let vruntime = self.current_vruntime(idx, processes);
+
+        let mut accounting = self.accounting.borrow_mut();
+        accounting.vruntime[idx] = vruntime;
+        accounting.history[idx].push((time, vruntime));
+    }
+}
+
+impl Scheduler for CfsScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let accounting = RefCell::new(Accounting::default());
+        let new_queue = || CfsQueue {
+            ready: BTreeSet::new(),
+            inserted: 0,
+            ready_weight: 0,
+            min_vruntime: 0,
+            accounting: &accounting,
+            scheduler: self,
+        };
+
+        let mut result = simulate(self, processes, config, new_queue)?;
+        let accounting = accounting.into_inner();
+
+        for (idx, history) in accounting.history.into_iter().enumerate() {
+            if let Some(p) = result.process_mut(processes[idx].pid) {
+                p.vruntime_history = history
+                    .into_iter()
+                    .map(|(time, vruntime)| (time, vruntime as f32 / VRUNTIME_SCALE as f32))
+                    .collect();
+            }
+        }
+
+        result.share_basis = Some(ShareBasis::Weight);
+        Ok(result)
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "CFS (Latency: {}, Min Granularity: {})",
+            self.target_latency, self.min_granularity
+        )
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("target_latency", self.target_latency.to_string()),
+            ("min_granularity", self.min_granularity.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_cpu_by_nice_weight() {
+        // Weights 1024 and 335, about 75% and 25% of the CPU while both run
+        let processes = vec![BaseProcess::new(1, 0, 300), BaseProcess::new(2, 0, 300).with_nice(5)];
+        let result = CfsScheduler::new(DEFAULT_TARGET_LATENCY, DEFAULT_MIN_GRANULARITY)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+
+        for (pid, requested, achieved) in result.cpu_shares(ShareBasis::Weight) {
+            assert!((requested - achieved).abs() < 2.0, "P{} asked for {:.1}%, got {:.1}%", pid, requested, achieved);
+        }
+        assert!(result.fairness_index(result.fairness_basis()) > 0.99);
+
+        // The heavier process gets its 300 units in about 400
+        let first = &result.processes[0];
+        assert!((390..=410).contains(&first.completion_time), "P1 completed at {}", first.completion_time);
+    }
+
+    #[test]
+    fn a_new_process_starts_at_the_min_vruntime() {
+        let processes = vec![BaseProcess::new(1, 0, 50), BaseProcess::new(2, 20, 10)];
+        let result = CfsScheduler::new(DEFAULT_TARGET_LATENCY, DEFAULT_MIN_GRANULARITY)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+
+        // P2 starts where P1 was last dispatched, at 12, not at 0 with 20 units of credit
+        let late = &result.processes[1];
+        assert_eq!(late.vruntime_history[0], (20, 12.0));
+    }
+}
@@ -0,0 +1,67 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, peek_for_core, pop_for_core, simulate}},
+};
+
+/*
+ *  Earliest Deadline First, dynamic priority real-time scheduling.
+ *
+ *  * The ready queue is ordered by (absolute deadline, ready time, index).
+ *  * Preemptive: a job released with a strictly earlier deadline takes the CPU.
+ *  * Processes without a deadline run only when no job with one is ready.
+ *  * Optimal on a single core, any task set with utilization <= 1 meets every deadline.
+ */
+#[derive(Debug, Default)]
+struct DeadlineQueue {
+    heap: BinaryHeap<Reverse<(u32, u32, usize)>>,
+}
+
+fn deadline_of(process: &ProcessState) -> u32 {
+    process.base.deadline.unwrap_or(u32::MAX)
+}
+
+impl ReadyQueue for DeadlineQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let process = &processes[idx];
+        self.heap.push(Reverse((deadline_of(process), process.ready_since, idx)));
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        pop_for_core(&mut self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+            .map(|Reverse((_, _, idx))| idx)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        let mut entries: Vec<_> = self.heap.iter().map(|Reverse(entry)| *entry).collect();
+        entries.sort_unstable();
+        entries.into_iter().map(|(_, _, idx)| idx).collect()
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        processes[idx].base.deadline.map(|deadline| DecisionValue::new("deadline", deadline as f64))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], _time: u32) -> bool {
+        peek_for_core(&self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+            .is_some_and(|Reverse((deadline, _, _))| *deadline < deadline_of(&processes[running]))
+    }
+}
+
+#[derive(Debug)]
+pub struct EDFScheduler;
+
+impl Scheduler for EDFScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        simulate(self, processes, config, DeadlineQueue::default)
+    }
+
+    fn name(&self) -> String {
+        "Earliest Deadline First".to_string()
+    }
+}
@@ -0,0 +1,785 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    models::{
+        cpu_process::{BaseProcess, Burst, ProcessState},
+        schedule_result::{ProcessResult, ScheduleResult},
+        trace::{DecisionValue, QueuedProcess, Trace, TraceEvent, TraceEventKind},
+    },
+    scheduler::{QueueMode, ScheduleError, Scheduler, SchedulerConfig, locks::{LockEvent, Locks}},
+    utils::gantt_display::GanttChart,
+};
+
+/*
+ *  The part that differs between algorithms: how the ready queue is ordered.
+ *
+ *  * Processes are referred to by their index in the `processes` slice.
+ *  * `push` is called when a process becomes ready (arrival, I/O completion,
+ *    quantum expiry or preemption), `ready_since` is already set.
+ *  * `pop` only returns a process whose affinity allows it on `core`.
+ *  * `ready` and `decision_value` only feed the trace, they never change a decision.
+ *  * The default methods describe a non-preemptive algorithm without a quantum.
+ */
+pub trait ReadyQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32);
+
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<usize>;
+
+    fn len(&self) -> usize;
+
+    // Queued processes in the order they would be picked
+    fn ready(&self, processes: &[ProcessState], time: u32) -> Vec<usize>;
+
+    // Value `idx` is ordered by at `time`, queued or running
+    fn decision_value(&self, _idx: usize, _processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        None
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Checked every time something happened, true if `running` has to give up `core`
+    fn should_preempt(&self, _running: usize, _core: usize, _processes: &[ProcessState], _time: u32) -> bool {
+        false
+    }
+
+    // Longest continuous run before the process goes back to the ready queue
+    fn time_slice(&self, _running: usize, _processes: &[ProcessState]) -> Option<u32> {
+        None
+    }
+
+    // Extra moment the running process should be re-evaluated at (e.g. aging)
+    fn next_event(&self, _processes: &[ProcessState], _time: u32) -> Option<u32> {
+        None
+    }
+
+    // Brings time dependent state (e.g. aging) up to `time`
+    fn update(&mut self, _processes: &mut [ProcessState], _time: u32) {}
+
+    // `idx` left the CPU (end of burst, time slice, preemption or lock), `last_run` is how long it ran
+    fn stop(&mut self, _idx: usize, _processes: &[ProcessState], _time: u32) {}
+
+    // The effective priority of `idx` changed while it may be queued (a lock lent it one)
+    fn reprioritize(&mut self, _idx: usize, _processes: &[ProcessState]) {}
+}
+
+// Best entry of a heap based queue whose process may run on `core`, entries skipped over stay queued
+pub fn pop_for_core<T: Ord>(
+    heap: &mut BinaryHeap<T>,
+    processes: &[ProcessState],
+    core: usize,
+    index: impl Fn(&T) -> usize
+) -> Option<T> {
+    let mut skipped = Vec::new();
+
+    let found = loop {
+        match heap.pop() {
+            Some(entry) if processes[index(&entry)].base.allows_core(core) => break Some(entry),
+            Some(entry) => skipped.push(entry),
+            None => break None,
+        }
+    };
+
+    heap.extend(skipped);
+    found
+}
+
+pub fn peek_for_core<'a, T: Ord>(
+    heap: &'a BinaryHeap<T>,
+    processes: &[ProcessState],
+    core: usize,
+    index: impl Fn(&T) -> usize
+) -> Option<&'a T> {
+    match heap.peek() {
+        Some(top) if processes[index(top)].base.allows_core(core) => Some(top),
+        _ => heap.iter().filter(|entry| processes[index(entry)].base.allows_core(core)).max(),
+    }
+}
+
+/*
+ *  Pending arrivals and I/O completions.
+ *
+ *  * Arrivals are released in (arrival time, input order).
+ *  * At the same time, new arrivals enter the ready queue before processes coming back from I/O.
+ */
+struct PendingEvents {
+    arrival_order: Vec<usize>,
+    next_arrival: usize,
+    io_returns: BinaryHeap<Reverse<(u32, usize)>>,
+}
+
+impl PendingEvents {
+    fn new(processes: &[ProcessState]) -> Self {
+        let mut arrival_order: Vec<usize> = (0..processes.len()).collect();
+        arrival_order.sort_by_key(|&idx| processes[idx].base.arrival_time);
+
+        PendingEvents {
+            arrival_order,
+            next_arrival: 0,
+            io_returns: BinaryHeap::new(),
+        }
+    }
+
+    // Next arrival or I/O completion that happened up to `time`, already marked as ready
+    fn pop_due(&mut self, processes: &mut [ProcessState], time: u32) -> Option<usize> {
+        let arrival = self.arrival_order
+            .get(self.next_arrival)
+            .map(|&idx| (processes[idx].base.arrival_time, idx));
+        let io_return = self.io_returns.peek().map(|Reverse(event)| *event);
+
+        let (ready_time, idx) = match (arrival, io_return) {
+            (Some(a), Some(io)) if a.0 <= io.0 => a,
+            (Some(a), None) => a,
+            (_, Some(io)) => io,
+            (None, None) => return None,
+        };
+
+        if ready_time > time {
+            return None;
+        }
+
+        if arrival == Some((ready_time, idx)) {
+            self.next_arrival += 1;
+        } else {
+            self.io_returns.pop();
+        }
+
+        processes[idx].ready_since = ready_time;
+        Some(idx)
+    }
+
+    // Every process released so far, in arrival order
+    fn arrived(&self) -> &[usize] {
+        &self.arrival_order[..self.next_arrival]
+    }
+
+    fn next_time(&self, processes: &[ProcessState]) -> Option<u32> {
+        let arrival = self.arrival_order
+            .get(self.next_arrival)
+            .map(|&idx| processes[idx].base.arrival_time);
+        let io_return = self.io_returns.peek().map(|Reverse((time, _))| *time);
+
+        match (arrival, io_return) {
+            (Some(a), Some(io)) => Some(a.min(io)),
+            (a, io) => a.or(io),
+        }
+    }
+}
+
+/*
+ *  The ready queue(s) of every core.
+ *
+ *  * Global: one queue shared by every core, a process runs on whichever core is free.
+ *  * PerCore: new and returning processes go to the least loaded core they may run on
+ *    and stay there, a preempted process goes back to the queue of its core.
+ *  * WorkStealing: like PerCore, but a core with an empty queue takes a process
+ *    from the longest queue of another core.
+ */
+struct CoreQueues<Q> {
+    queues: Vec<Q>,
+    mode: QueueMode,
+}
+
+impl<Q: ReadyQueue> CoreQueues<Q> {
+    fn new(config: &SchedulerConfig, new_queue: impl Fn() -> Q) -> Self {
+        let count = match config.queue_mode {
+            QueueMode::Global => 1,
+            QueueMode::PerCore | QueueMode::WorkStealing => config.cores(),
+        };
+
+        CoreQueues {
+            queues: (0..count).map(|_| new_queue()).collect(),
+            mode: config.queue_mode,
+        }
+    }
+
+    fn queue_of(&self, core: usize) -> usize {
+        match self.mode {
+            QueueMode::Global => 0,
+            QueueMode::PerCore | QueueMode::WorkStealing => core,
+        }
+    }
+
+    // Queue a process entering the system (arrival or I/O completion), returns the queue it joined
+    fn push_new(&mut self, idx: usize, processes: &[ProcessState], time: u32, cores: &[Core]) -> usize {
+        let target = match self.mode {
+            QueueMode::Global => 0,
+            QueueMode::PerCore | QueueMode::WorkStealing => {
+                let process = &processes[idx];
+
+                // Least loaded core, the core the process last ran on wins ties
+                (0..self.queues.len())
+                    .filter(|&core| process.base.allows_core(core))
+                    .min_by_key(|&core| {
+                        let load = self.queues[core].len() + usize::from(cores[core].running.is_some());
+                        (load, process.last_core != Some(core), core)
+                    })
+                    .unwrap_or(0)
+            }
+        };
+
+        self.queues[target].push(idx, processes, time);
+        target
+    }
+
+    // Queue a process that just left `core` (preemption or end of its time slice), returns the queue it joined
+    fn push_back(&mut self, idx: usize, core: usize, processes: &[ProcessState], time: u32) -> usize {
+        let queue = self.queue_of(core);
+        self.queues[queue].push(idx, processes, time);
+        queue
+    }
+
+    // Next process for `core` and the queue it came from
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<(usize, usize)> {
+        let own = self.queue_of(core);
+        if let Some(idx) = self.queues[own].pop(processes, time, core) {
+            return Some((idx, own));
+        }
+
+        if self.mode != QueueMode::WorkStealing {
+            return None;
+        }
+
+        let mut victims: Vec<usize> = (0..self.queues.len())
+            .filter(|&queue| queue != own && !self.queues[queue].is_empty())
+            .collect();
+        victims.sort_by_key(|&queue| Reverse(self.queues[queue].len()));
+
+        victims
+            .into_iter()
+            .find_map(|queue| self.queues[queue].pop(processes, time, core).map(|idx| (idx, queue)))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], time: u32) -> bool {
+        self.queues[self.queue_of(core)].should_preempt(running, core, processes, time)
+    }
+
+    fn time_slice(&self, core: usize, running: usize, processes: &[ProcessState]) -> Option<u32> {
+        self.queues[self.queue_of(core)].time_slice(running, processes)
+    }
+
+    fn next_event(&self, processes: &[ProcessState], time: u32) -> Option<u32> {
+        self.queues.iter().filter_map(|queue| queue.next_event(processes, time)).min()
+    }
+
+    fn update(&mut self, processes: &mut [ProcessState], time: u32) {
+        for queue in &mut self.queues {
+            queue.update(processes, time);
+        }
+    }
+
+    fn stop(&mut self, idx: usize, core: usize, processes: &[ProcessState], time: u32) {
+        let queue = self.queue_of(core);
+        self.queues[queue].stop(idx, processes, time);
+    }
+
+    fn reprioritize(&mut self, idx: usize, processes: &[ProcessState]) {
+        for queue in &mut self.queues {
+            queue.reprioritize(idx, processes);
+        }
+    }
+
+    fn decision_value(&self, queue: usize, idx: usize, processes: &[ProcessState], time: u32) -> Option<DecisionValue> {
+        self.queues[queue].decision_value(idx, processes, time)
+    }
+
+    fn queued(&self, queue: usize, idx: usize, processes: &[ProcessState], time: u32) -> QueuedProcess {
+        QueuedProcess {
+            pid: processes[idx].base.pid,
+            value: self.decision_value(queue, idx, processes, time),
+        }
+    }
+
+    // Every queue in pick order, for the trace
+    fn snapshot(&self, processes: &[ProcessState], time: u32) -> Vec<Vec<QueuedProcess>> {
+        (0..self.queues.len())
+            .map(|queue| {
+                self.queues[queue]
+                    .ready(processes, time)
+                    .into_iter()
+                    .map(|idx| self.queued(queue, idx, processes, time))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Process of its own queue that `core` would pick next
+    fn candidate(&self, core: usize, processes: &[ProcessState], time: u32) -> Option<QueuedProcess> {
+        let queue = self.queue_of(core);
+        self.queues[queue]
+            .ready(processes, time)
+            .into_iter()
+            .find(|&idx| processes[idx].base.allows_core(core))
+            .map(|idx| self.queued(queue, idx, processes, time))
+    }
+}
+
+// Adds an event to the trace with the ready queues as they are now, `kind` is only built while tracing
+fn record<Q: ReadyQueue>(
+    trace: &mut Option<Trace>,
+    queues: &CoreQueues<Q>,
+    processes: &[ProcessState],
+    time: u32,
+    core: Option<usize>,
+    kind: impl FnOnce() -> TraceEventKind
+) {
+    if let Some(trace) = trace {
+        trace.push(TraceEvent {
+            time,
+            core,
+            kind: kind(),
+            ready_queues: queues.snapshot(processes, time),
+        });
+    }
+}
+
+// Trace event of what running process `idx` did with a lock
+fn lock_event_kind(locks: &Locks, processes: &[ProcessState], idx: usize, event: LockEvent) -> TraceEventKind {
+    let pid = processes[idx].base.pid;
+
+    match event {
+        LockEvent::Acquired { lock, ceiling } => {
+            TraceEventKind::LockAcquire { pid, lock: locks.name(lock).to_string(), priority: ceiling }
+        }
+        LockEvent::Blocked { lock, holder, inherited } => TraceEventKind::LockBlocked {
+            pid,
+            lock: locks.name(lock).to_string(),
+            holder: processes[holder].base.pid,
+            priority: inherited,
+        },
+        LockEvent::Released { lock, to } => TraceEventKind::LockRelease {
+            pid,
+            lock: locks.name(lock).to_string(),
+            to: to.map(|to| processes[to].base.pid),
+        },
+    }
+}
+
+// Moves every arrival and I/O completion due by `time` into the ready queues
+fn release_due<Q: ReadyQueue>(
+    events: &mut PendingEvents,
+    processes: &mut [ProcessState],
+    queues: &mut CoreQueues<Q>,
+    cores: &[Core],
+    time: u32,
+    trace: &mut Option<Trace>
+) {
+    while let Some(idx) = events.pop_due(processes, time) {
+        let pid = processes[idx].base.pid;
+        record(trace, queues, processes, time, None, || match processes[idx].blocked_until {
+            Some(_) => TraceEventKind::IoCompletion { pid },
+            None => TraceEventKind::Arrival { pid },
+        });
+
+        let queue = queues.push_new(idx, processes, time, cores);
+        record(trace, queues, processes, time, None, || TraceEventKind::Enqueue {
+            pid,
+            queue,
+            value: queues.decision_value(queue, idx, processes, time),
+        });
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Core {
+    running: Option<usize>,
+    // Time the running process gets the core, later than now during a context switch
+    run_from: u32,
+    slice_used: u32,
+}
+
+/*
+ *  Runs `processes` on `config.cores` cores with the ordering given by the queues
+ *  built by `new_queue`.
+ *
+ *  A core only looks at the ready queue when something happens: an arrival,
+ *  an I/O completion, the end of a burst, the end of a time slice, a lock taken
+ *  or released or an event asked for by the queue. Between those moments the running process keeps the core,
+ *  and a process in its context switch can't be preempted.
+ *
+ *  The clock jumps straight from one event to the next, so the time values don't
+ *  matter, only the number of events. Each one costs O(cores) on top of the queue:
+ *  O(1) or O(log n) for the FIFO, heap and tree based queues, O(log n) amortized for
+ *  the HRRN tournament and the lottery ticket tree, O(log n) per aging bucket for
+ *  priority aging.
+ *
+ *  A process reaching a critical section whose lock is held leaves the CPU until
+ *  the lock is handed to it, see `locks::Locks`. Affinity to cores that don't exist is ignored. With `config.trace` every
+ *  event is recorded into `ScheduleResult::trace`. The result is named after
+ *  `scheduler` and records its parameters.
+ *
+ *  Fails as soon as the run is bound to go past the last u32 time unit: a context
+ *  switch, a CPU burst or an I/O burst that would end after it. Nothing is checked
+ *  up front, so only a run that really needs a later time fails.
+ */
+pub fn simulate<Q: ReadyQueue>(
+    scheduler: &dyn Scheduler,
+    processes: &[BaseProcess],
+    config: &SchedulerConfig,
+    new_queue: impl Fn() -> Q
+) -> Result<ScheduleResult, ScheduleError> {
+    let clock = |time: u64| {
+        u32::try_from(time).map_err(|_| ScheduleError::ClockOverflow { algorithm: scheduler.name(), time })
+    };
+
+    let num_cores = config.cores();
+    let mut processes: Vec<ProcessState> = processes
+        .iter()
+        .cloned()
+        .map(|mut base| {
+            base.affinity.retain(|&core| core < num_cores);
+            ProcessState::new(base)
+        })
+        .collect();
+
+    let mut gantt_chart = GanttChart::with_cores(num_cores);
+    let mut events = PendingEvents::new(&processes);
+    let mut queues = CoreQueues::new(config, new_queue);
+    let mut cores = vec![Core::default(); num_cores];
+    let mut trace = config.trace.then(Trace::default);
+    let mut locks = Locks::new(&processes, scheduler.lock_protocol());
+
+    let num_processes = processes.len();
+    let mut completed_process = 0;
+    let mut current_time = 0;
+
+    while completed_process < num_processes {
+        release_due(&mut events, &mut processes, &mut queues, &cores, current_time, &mut trace);
+
+        // Waiters handed a lock, and holders lent a priority while they may be queued
+        let mut woken = Vec::new();
+        let mut lent = Vec::new();
+
+        // Locks given up at `current_time` first, a process reaching one at the same moment finds it free
+        if let Some(locks) = &mut locks {
+            for (core_id, core) in cores.iter().enumerate() {
+                let Some(idx) = core.running.filter(|_| current_time >= core.run_from) else {
+                    continue;
+                };
+
+                for event in locks.release_due(idx, &mut processes, current_time) {
+                    record(&mut trace, &queues, &processes, current_time, Some(core_id), || {
+                        lock_event_kind(locks, &processes, idx, event)
+                    });
+                    if let LockEvent::Released { to, .. } = event {
+                        woken.extend(to);
+                    }
+                }
+            }
+        }
+
+        // Locks taken, bursts and time slices that ended at `current_time`
+        for (core_id, core) in cores.iter_mut().enumerate() {
+            let Some(idx) = core.running else {
+                continue;
+            };
+
+            let pid = processes[idx].base.pid;
+
+            if let Some(locks) = &mut locks
+                && current_time >= core.run_from
+                && let Some(event) = locks.acquire_due(idx, &mut processes, current_time)
+            {
+                if let LockEvent::Blocked { holder, .. } = event {
+                    core.running = None;
+                    queues.stop(idx, core_id, &processes, current_time);
+                    lent.push(holder);
+                }
+                record(&mut trace, &queues, &processes, current_time, Some(core_id), || {
+                    lock_event_kind(locks, &processes, idx, event)
+                });
+
+                if core.running.is_none() {
+                    continue;
+                }
+            }
+
+            if processes[idx].remaining_time == 0 {
+                core.running = None;
+                queues.stop(idx, core_id, &processes, current_time);
+
+                let process = &mut processes[idx];
+                let io_end = match process.base.bursts.get(process.burst_index + 1).copied() {
+                    Some(Burst::Io(io_time)) => {
+                        // Blocked until the I/O completes, then back for the next CPU burst
+                        let io_end = clock(current_time as u64 + io_time as u64)?;
+                        process.burst_index += 2;
+                        process.remaining_time = match process.base.bursts.get(process.burst_index) {
+                            Some(Burst::Cpu(time)) => *time,
+                            _ => 0,
+                        };
+                        process.blocked_until = Some(io_end);
+                        events.io_returns.push(Reverse((io_end, idx)));
+                        Some(io_end)
+                    }
+                    _ => {
+                        process.completion_time = Some(current_time);
+                        completed_process += 1;
+                        None
+                    }
+                };
+
+                record(&mut trace, &queues, &processes, current_time, Some(core_id), || match io_end {
+                    Some(until) => TraceEventKind::IoStart { pid, until },
+                    None => TraceEventKind::Completion { pid },
+                });
+            } else if queues.time_slice(core_id, idx, &processes).is_some_and(|slice| core.slice_used >= slice) {
+                queues.stop(idx, core_id, &processes, current_time);
+                processes[idx].ready_since = current_time;
+                record(&mut trace, &queues, &processes, current_time, Some(core_id), || {
+                    TraceEventKind::QuantumExpiry { pid, slice: core.slice_used }
+                });
+
+                let queue = queues.push_back(idx, core_id, &processes, current_time);
+                record(&mut trace, &queues, &processes, current_time, None, || TraceEventKind::Enqueue {
+                    pid,
+                    queue,
+                    value: queues.decision_value(queue, idx, &processes, current_time),
+                });
+                core.running = None;
+            }
+        }
+
+        for idx in woken {
+            let pid = processes[idx].base.pid;
+            processes[idx].ready_since = current_time;
+            let queue = queues.push_new(idx, &processes, current_time, &cores);
+            record(&mut trace, &queues, &processes, current_time, None, || TraceEventKind::Enqueue {
+                pid,
+                queue,
+                value: queues.decision_value(queue, idx, &processes, current_time),
+            });
+        }
+
+        for holder in lent {
+            if cores.iter().all(|core| core.running != Some(holder)) {
+                queues.reprioritize(holder, &processes);
+            }
+        }
+
+        // An I/O of length 0 is already over
+        release_due(&mut events, &mut processes, &mut queues, &cores, current_time, &mut trace);
+
+        queues.update(&mut processes, current_time);
+
+        for (core_id, core) in cores.iter_mut().enumerate() {
+            if let Some(idx) = core.running
+                && current_time > core.run_from
+                && queues.should_preempt(idx, core_id, &processes, current_time)
+            {
+                let pid = processes[idx].base.pid;
+                record(&mut trace, &queues, &processes, current_time, Some(core_id), || {
+                    TraceEventKind::Preemption {
+                        pid,
+                        value: queues.decision_value(queues.queue_of(core_id), idx, &processes, current_time),
+                        by: queues.candidate(core_id, &processes, current_time),
+                    }
+                });
+
+                queues.stop(idx, core_id, &processes, current_time);
+                processes[idx].ready_since = current_time;
+                let queue = queues.push_back(idx, core_id, &processes, current_time);
+                record(&mut trace, &queues, &processes, current_time, None, || TraceEventKind::Enqueue {
+                    pid,
+                    queue,
+                    value: queues.decision_value(queue, idx, &processes, current_time),
+                });
+                core.running = None;
+            }
+        }
+
+        for (core_id, core) in cores.iter_mut().enumerate() {
+            if core.running.is_some() {
+                continue;
+            }
+
+            // The queues as the pick saw them
+            let candidates = trace.as_ref().map(|_| queues.snapshot(&processes, current_time));
+
+            let Some((idx, queue)) = queues.pop(&processes, current_time, core_id) else {
+                continue;
+            };
+
+            let process = &mut processes[idx];
+            if process.last_core.is_some_and(|last| last != core_id) {
+                process.migrations += 1;
+            }
+            process.last_core = Some(core_id);
+            process.last_run = 0;
+            process.blocked_until = None;
+
+            let run_from = gantt_chart
+                .dispatch(core_id, process.base.pid, current_time, config.context_switch_time)
+                .or_else(clock)?;
+
+            if let (Some(trace), Some(candidates)) = (&mut trace, candidates) {
+                trace.push(TraceEvent {
+                    time: current_time,
+                    core: Some(core_id),
+                    kind: TraceEventKind::Dispatch {
+                        pid: processes[idx].base.pid,
+                        value: queues.decision_value(queue, idx, &processes, current_time),
+                        starts_at: run_from,
+                    },
+                    ready_queues: candidates,
+                });
+            }
+
+            *core = Core {
+                running: Some(idx),
+                run_from,
+                slice_used: 0,
+            };
+        }
+
+        // Brings the dispatched processes up to `current_time` too
+        queues.update(&mut processes, current_time);
+
+        // Next moment something happens on any core
+        let mut next_time = events.next_time(&processes);
+
+        for (core_id, core) in cores.iter().enumerate() {
+            let Some(idx) = core.running else {
+                continue;
+            };
+
+            let from = core.run_from.max(current_time);
+            // The burst can't end any earlier, whatever happens to it on the way
+            let mut end_time = clock(from as u64 + processes[idx].remaining_time as u64)?;
+
+            if let Some(slice) = queues.time_slice(core_id, idx, &processes) {
+                end_time = end_time.min(from.saturating_add(slice.saturating_sub(core.slice_used).max(1)));
+            }
+
+            if let Some(point) = locks.as_ref().and_then(|locks| locks.until_next_point(idx, &processes)) {
+                end_time = end_time.min(from.saturating_add(point));
+            }
+
+            next_time = Some(next_time.map_or(end_time, |time| time.min(end_time)));
+        }
+
+        let any_running = cores.iter().any(|core| core.running.is_some());
+        if any_running && let Some(event_time) = queues.next_event(&processes, current_time) {
+            let event_time = event_time.max(current_time.saturating_add(1));
+            next_time = Some(next_time.map_or(event_time, |time| time.min(event_time)));
+        }
+
+        // Nothing running and nothing left to happen
+        let Some(next_time) = next_time else {
+            break;
+        };
+
+        // A core sits idle while work is still going on elsewhere or a process is doing I/O
+        let work_in_flight = any_running || !events.io_returns.is_empty();
+
+        if let Some(locks) = &mut locks {
+            let running: Vec<usize> = cores.iter().filter_map(|core| core.running).collect();
+            locks.account(&mut processes, events.arrived(), &running, current_time, next_time);
+        }
+
+        for (core_id, core) in cores.iter_mut().enumerate() {
+            match core.running {
+                Some(idx) => {
+                    let from = core.run_from.max(current_time);
+                    if next_time > from {
+                        gantt_chart.add_process(core_id, processes[idx].base.pid, from, next_time);
+
+                        // Set on the first CPU time, a process can block on a lock the moment it is dispatched
+                        processes[idx].start_time.get_or_insert(from);
+
+                        processes[idx].remaining_time -= next_time - from;
+                        processes[idx].last_run += next_time - from;
+                        core.slice_used += next_time - from;
+                    }
+                }
+                None if work_in_flight => gantt_chart.add_idle(core_id, current_time, next_time),
+                None => {}
+            }
+        }
+
+        current_time = next_time;
+    }
+
+    if let Some(locks) = &mut locks {
+        gantt_chart.lock_waits = std::mem::take(&mut locks.waits);
+    }
+
+    let mut result = ScheduleResult::new(
+        scheduler.name(),
+        processes.iter().map(ProcessResult::from).collect(),
+        gantt_chart,
+    );
+    result.trace = trace;
+
+    // Settings of the algorithm, then the ones every run shares
+    result.parameters = scheduler.parameters();
+    result.parameters.extend([
+        ("preemptive", scheduler.preemptive().to_string()),
+        ("context_switch_time", config.context_switch_time.to_string()),
+        ("cores", num_cores.to_string()),
+        ("queue_mode", config.queue_mode.name().to_string()),
+    ]);
+
+    // Only a workload with critical sections has anything for the protocol to do
+    if let Some(locks) = &locks {
+        result.parameters.push(("lock_protocol", locks.protocol().name().to_string()));
+        result.lock_protocol = Some(locks.protocol());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{fcfs::FCFSScheduler, round_robin::RoundRobinScheduler};
+
+    fn config(context_switch_time: u32) -> SchedulerConfig {
+        SchedulerConfig { context_switch_time, ..Default::default() }
+    }
+
+    #[test]
+    fn runs_up_to_the_last_time_unit() {
+        let processes = vec![BaseProcess::new(1, 0, 3_000_000_000), BaseProcess::new(2, 0, 1_294_967_293)];
+
+        let result = FCFSScheduler.schedule(&processes, &config(2)).unwrap();
+        assert_eq!(result.processes[1].completion_time, u32::MAX);
+
+        // Four switches between the quanta, far fewer than one per unit of CPU time
+        let processes = vec![BaseProcess::new(1, 0, 3_000_000_000), BaseProcess::new(2, 0, 1_294_967_287)];
+        let result = RoundRobinScheduler::new(1_000_000_000).schedule(&processes, &config(2)).unwrap();
+        assert_eq!(result.processes[0].completion_time, u32::MAX);
+    }
+
+    #[test]
+    fn fails_on_a_burst_past_the_last_time_unit() {
+        let processes = vec![BaseProcess::new(1, 0, 3_000_000_000), BaseProcess::new(2, 0, 1_294_967_294)];
+
+        match FCFSScheduler.schedule(&processes, &config(2)) {
+            Err(ScheduleError::ClockOverflow { time, .. }) => assert_eq!(time, u32::MAX as u64 + 1),
+            other => panic!("expected a clock overflow, got {:?}", other.map(|r| r.algorithm_name)),
+        }
+    }
+
+    #[test]
+    fn fails_on_a_context_switch_past_the_last_time_unit() {
+        let processes = vec![BaseProcess::new(1, 0, u32::MAX - 1), BaseProcess::new(2, 0, 1)];
+
+        match FCFSScheduler.schedule(&processes, &config(2)) {
+            Err(ScheduleError::ClockOverflow { time, .. }) => assert_eq!(time, u32::MAX as u64 + 1),
+            other => panic!("expected a clock overflow, got {:?}", other.map(|r| r.algorithm_name)),
+        }
+    }
+
+    #[test]
+    fn fails_on_io_past_the_last_time_unit() {
+        let processes = vec![
+            BaseProcess::new(1, 0, 0).with_bursts(vec![Burst::Cpu(2), Burst::Io(u32::MAX - 1), Burst::Cpu(1)]),
+        ];
+
+        match FCFSScheduler.schedule(&processes, &config(0)) {
+            Err(ScheduleError::ClockOverflow { time, .. }) => assert_eq!(time, u32::MAX as u64 + 1),
+            other => panic!("expected a clock overflow, got {:?}", other.map(|r| r.algorithm_name)),
+        }
+    }
+}
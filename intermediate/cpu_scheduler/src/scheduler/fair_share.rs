@@ -0,0 +1,309 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::{
+    models::{
+        cpu_process::{BaseProcess, ProcessState},
+        schedule_result::ScheduleResult,
+        trace::DecisionValue,
+    },
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
+
+// Weight of a group the scheduler wasn't given a weight for
+pub const DEFAULT_GROUP_WEIGHT: u32 = 1;
+
+// Pass advanced per time unit by a group of weight 1
+const STRIDE1: u64 = 1 << 20;
+
+// How the processes of one group share the CPU time of the group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InnerPolicy {
+    #[default]
+    RoundRobin,
+    // Lowest priority value first, the order they became ready among equals
+    Priority,
+}
+
+impl InnerPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rr" | "round_robin" | "round-robin" => Some(InnerPolicy::RoundRobin),
+            "priority" => Some(InnerPolicy::Priority),
+            _ => None,
+        }
+    }
+
+    // Name accepted by `from_name`
+    pub fn name(&self) -> &'static str {
+        match self {
+            InnerPolicy::RoundRobin => "rr",
+            InnerPolicy::Priority => "priority",
+        }
+    }
+}
+
+impl fmt::Display for InnerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InnerPolicy::RoundRobin => write!(f, "Round Robin"),
+            InnerPolicy::Priority => write!(f, "Priority"),
+        }
+    }
+}
+
+// Groups of one run, numbered in name order
+#[derive(Debug)]
+struct Groups {
+    // Pass a group gains per time unit of CPU, STRIDE1 / weight
+    strides: Vec<u64>,
+    // Group of every process, by index in the `processes` slice
+    group_of: Vec<usize>,
+}
+
+// Shared by the per-core queues, so a group is charged on whichever core its processes run
+#[derive(Debug, Default)]
+struct Accounting {
+    pass: Vec<u64>,
+    // Processes of each group on a CPU
+    running: Vec<u32>,
+    global_pass: u64,
+}
+
+/*
+ *  Stride scheduling between groups, then the inner policy within the chosen group.
+ *
+ *  * The group with the lowest pass that has a ready process runs its first process
+ *    for one time quantum. Its pass grows by its stride for every time unit the
+ *    process ran, so each group gets CPU in proportion to its weight however many
+ *    processes it has.
+ *  * A group with nothing ready or running that gets a process back (arrival or
+ *    I/O completion) starts at the pass of the last dispatched group, it can't claim
+ *    the time it had no work.
+ *  * Equal passes go to the group named first. Within a group, processes run in the
+ *    order they were queued (Round Robin) or by priority, only at quantum boundaries.
+ */
+struct FairShareQueue<'a> {
+    groups: &'a Groups,
+    accounting: &'a RefCell<Accounting>,
+    // (priority, or 0 for Round Robin, insertion order, index) of the ready processes of each group
+    ready: Vec<BTreeSet<(u32, u64, usize)>>,
+    len: usize,
+    inserted: u64,
+    inner: InnerPolicy,
+    time_quantum: u32,
+}
+
+impl FairShareQueue<'_> {
+    // Groups with a ready process, lowest pass first
+    fn group_order(&self) -> Vec<usize> {
+        let accounting = self.accounting.borrow();
+        let mut groups: Vec<usize> = (0..self.ready.len()).filter(|&group| !self.ready[group].is_empty()).collect();
+        groups.sort_by_key(|&group| (accounting.pass[group], group));
+        groups
+    }
+}
+
+impl ReadyQueue for FairShareQueue<'_> {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let group = self.groups.group_of[idx];
+        let process = &processes[idx];
+
+        let mut accounting = self.accounting.borrow_mut();
+        let joining = process.start_time.is_none() || process.blocked_until.is_some();
+        if joining && self.ready[group].is_empty() && accounting.running[group] == 0 {
+            accounting.pass[group] = accounting.pass[group].max(accounting.global_pass);
+        }
+
+        let key = match self.inner {
+            InnerPolicy::RoundRobin => 0,
+            InnerPolicy::Priority => process.effective_priority(),
+        };
+
+        self.inserted += 1;
+        self.ready[group].insert((key, self.inserted, idx));
+        self.len += 1;
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        let (group, entry) = self.group_order().into_iter().find_map(|group| {
+            self.ready[group]
+                .iter()
+                .find(|&&(_, _, idx)| processes[idx].base.allows_core(core))
+                .map(|&entry| (group, entry))
+        })?;
+
+        self.ready[group].remove(&entry);
+        self.len -= 1;
+
+        let mut accounting = self.accounting.borrow_mut();
+        accounting.global_pass = accounting.global_pass.max(accounting.pass[group]);
+        accounting.running[group] += 1;
+        Some(entry.2)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        self.group_order()
+            .into_iter()
+            .flat_map(|group| self.ready[group].iter().map(|&(_, _, idx)| idx))
+            .collect()
+    }
+
+    // Pass of the group in time units per weight, the pass a running process gains is added when it stops
+    fn decision_value(&self, idx: usize, _processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        let pass = self.accounting.borrow().pass[self.groups.group_of[idx]];
+        Some(DecisionValue::new("group pass", pass as f64 / STRIDE1 as f64))
+    }
+
+    fn time_slice(&self, _running: usize, _processes: &[ProcessState]) -> Option<u32> {
+        Some(self.time_quantum)
+    }
+
+    fn stop(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let group = self.groups.group_of[idx];
+
+        let mut accounting = self.accounting.borrow_mut();
+        accounting.pass[group] += self.groups.strides[group] * processes[idx].last_run as u64;
+        accounting.running[group] = accounting.running[group].saturating_sub(1);
+    }
+}
+
+/*
+ *  Hierarchical fair-share scheduling: CPU time is split between the groups of the
+ *  workload by weight, then between the processes of each group by the inner policy.
+ *
+ *  `group_weights` lists (group, weight), every other group has DEFAULT_GROUP_WEIGHT.
+ */
+#[derive(Debug)]
+pub struct FairShareScheduler {
+    pub time_quantum: u32,
+    pub inner: InnerPolicy,
+    pub group_weights: Vec<(String, u32)>,
+}
+
+impl FairShareScheduler {
+    pub fn new(time_quantum: u32, inner: InnerPolicy) -> Self {
+        FairShareScheduler { time_quantum, inner, group_weights: Vec::new() }
+    }
+
+    pub fn with_group_weights(mut self, group_weights: Vec<(String, u32)>) -> Self {
+        self.group_weights = group_weights;
+        self
+    }
+
+    pub fn weight_of(&self, group: &str) -> u32 {
+        self.group_weights
+            .iter()
+            .find(|(name, _)| name == group)
+            .map_or(DEFAULT_GROUP_WEIGHT, |&(_, weight)| weight.max(1))
+    }
+}
+
+impl Scheduler for FairShareScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let ids: BTreeMap<&str, usize> = processes
+            .iter()
+            .map(|p| p.group.as_str())
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| (name, id))
+            .collect();
+
+        let groups = Groups {
+            strides: ids.keys().map(|name| STRIDE1 / self.weight_of(name) as u64).collect(),
+            group_of: processes.iter().map(|p| ids[p.group.as_str()]).collect(),
+        };
+        let accounting = RefCell::new(Accounting {
+            pass: vec![0; ids.len()],
+            running: vec![0; ids.len()],
+            global_pass: 0,
+        });
+
+        let new_queue = || FairShareQueue {
+            groups: &groups,
+            accounting: &accounting,
+            ready: vec![BTreeSet::new(); ids.len()],
+            len: 0,
+            inserted: 0,
+            inner: self.inner,
+            time_quantum: self.time_quantum.max(1),
+        };
+
+        let mut result = simulate(self, processes, config, new_queue)?;
+        result.group_weights = Some(ids.keys().map(|name| (name.to_string(), self.weight_of(name))).collect());
+        Ok(result)
+    }
+
+    fn name(&self) -> String {
+        format!("Fair Share (Time Quantum: {}, {} within groups)", self.time_quantum, self.inner)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        let weights: Vec<String> = self.group_weights
+            .iter()
+            .map(|(group, weight)| format!("{}:{}", group, weight))
+            .collect();
+
+        vec![
+            ("time_quantum", self.time_quantum.to_string()),
+            ("inner_policy", self.inner.name().to_string()),
+            ("group_weights", weights.join(",")),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completions(result: &ScheduleResult) -> Vec<u32> {
+        result.processes.iter().map(|p| p.completion_time).collect()
+    }
+
+    #[test]
+    fn splits_the_cpu_by_group_weight_not_process_count() {
+        // One process in "a" against three in "b", "a" still gets three quarters of the CPU
+        let processes = vec![
+            BaseProcess::new(1, 0, 60).with_group("a"),
+            BaseProcess::new(2, 0, 60).with_group("b"),
+            BaseProcess::new(3, 0, 60).with_group("b"),
+            BaseProcess::new(4, 0, 60).with_group("b"),
+        ];
+        let result = FairShareScheduler::new(1, InnerPolicy::RoundRobin)
+            .with_group_weights(vec![("a".to_string(), 3)])
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+
+        let shares = result.group_shares();
+        let summary: Vec<(&str, u32, usize, f64)> =
+            shares.iter().map(|s| (s.group.as_str(), s.weight, s.processes, s.target)).collect();
+        assert_eq!(summary, vec![("a", 3, 1, 75.0), ("b", 1, 3, 25.0)]);
+        for share in &shares {
+            assert!((share.achieved - share.target).abs() < 2.0, "{} got {:.1}%", share.group, share.achieved);
+        }
+        assert_eq!(completions(&result)[0], 80);
+    }
+
+    #[test]
+    fn a_late_group_starts_level_with_the_others() {
+        // "b" arrives after "a" ran alone for 20 and joins at the pass "a" was last dispatched at,
+        // so the two take turns from then on instead of "b" running 20 to 30 to catch up
+        let processes = vec![
+            BaseProcess::new(1, 0, 40).with_group("a"),
+            BaseProcess::new(2, 20, 10).with_group("b"),
+        ];
+        let result = FairShareScheduler::new(1, InnerPolicy::RoundRobin)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+
+        assert_eq!(completions(&result), vec![50, 39]);
+    }
+}
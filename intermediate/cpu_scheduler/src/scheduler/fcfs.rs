@@ -1,87 +1,53 @@
-use crate::models::cpu_process::FCFSProcess;
+use std::collections::VecDeque;
 
-pub trait Scheduler {
-    fn schedule(&mut self);
-    fn display(&self);
-}
+use crate::{
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
 
-pub struct FCFSScheduler {
-    pub processes: Vec<FCFSProcess>,
-    pub avg_tat: f32,
-    pub avg_wt: f32,
+// Plain FIFO ready queue, processes run in the order they became ready
+#[derive(Debug, Default)]
+pub struct FifoQueue {
+    queue: VecDeque<usize>,
 }
 
-impl FCFSScheduler {
-    pub fn new(processes: Vec<FCFSProcess>) -> Self {
-        Self {
-            processes,
-            avg_tat: 0.0,
-            avg_wt: 0.0,
-        }
+impl ReadyQueue for FifoQueue {
+    fn push(&mut self, idx: usize, _processes: &[ProcessState], _time: u32) {
+        self.queue.push_back(idx);
     }
-}
-
-impl Scheduler for FCFSScheduler {
-    fn schedule(&mut self) {
-        self.processes.sort_by_key(|p: &FCFSProcess| p.base.arrival_time);
-
-        let mut current_time = 0;
-        let mut total_tat = 0;
-        let mut total_wt = 0;
-
-        for p in &mut self.processes {
-            p.start_time = if current_time < p.base.arrival_time { p.base.arrival_time } else { current_time };
-            p.completion_time = p.start_time + p.base.burst_time;
-            p.turnaround_time = p.completion_time - p.base.arrival_time;
-            p.waiting_time = p.turnaround_time - p.base.burst_time;
-
-            current_time = p.completion_time;
 
-            total_tat += p.turnaround_time;
-            total_wt += p.waiting_time;
-        }
+    // First process in line that may run on `core`
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        let position = self.queue.iter().position(|&idx| processes[idx].base.allows_core(core))?;
+        self.queue.remove(position)
+    }
 
-        self.avg_tat = total_tat as f32 / self.processes.len() as f32;
-        self.avg_wt = total_wt as f32 / self.processes.len() as f32;
+    fn len(&self) -> usize {
+        self.queue.len()
     }
 
-    fn display(&self) {
-        println!("|---------|----|----|----|----|-----|----|");
-        println!(
-            "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|",
-            "Process", "AT", "BT", "ST", "CT", "TAT", "WT"
-        );
-        println!("|---------|----|----|----|----|-----|----|");
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        self.queue.iter().copied().collect()
+    }
 
-        for p in &self.processes {
-            println!(
-                "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|",
-                p.base.pid,
-                p.base.arrival_time,
-                p.base.burst_time,
-                p.start_time,
-                p.completion_time,
-                p.turnaround_time,
-                p.waiting_time,
-            );
-        }
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        Some(DecisionValue::new("ready at", processes[idx].ready_since as f64))
+    }
+}
 
-        println!("|---------|----|----|----|----|-----|----|");
+#[derive(Debug)]
+pub struct FCFSScheduler;
 
-        println!("\nAverage TAT: {:.2}", self.avg_tat);
-        println!("Average WT: {:.2}", self.avg_wt);
+impl Scheduler for FCFSScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        simulate(self, processes, config, FifoQueue::default)
+    }
 
-        // Gantt Chart
-        print!("\nGantt Chart:\n|");
-        for p in &self.processes {
-            print!(" P{} |", p.base.pid);
-        }
-        println!();
+    fn name(&self) -> String {
+        "First Come First Serve (FCFS)".to_string()
+    }
 
-        print!("0");
-        for p in &self.processes {
-            print!("    {}", p.completion_time);
-        }
-        println!();
+    fn preemptive(&self) -> bool {
+        false
     }
 }
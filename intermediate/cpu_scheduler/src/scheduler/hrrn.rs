@@ -1,149 +1,284 @@
-use crate::{models::cpu_process::HRRNProcess, utils::gantt_display::{GanttChart, GanttSegment}};
+use std::cmp::Ordering;
 
-pub trait HRRNSched {
-    fn schedule(&mut self);
-    fn display(&self);
+use crate::{
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
+
+// A queued process, its response ratio at time t is 1 + (t - ready_since) / burst
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    ready_since: u32,
+    burst: u32,
+    order: u64,
 }
 
-pub struct HRRNScheduler {
-    pub processes: Vec<HRRNProcess>,
-    pub gantt_chart: GanttChart
+impl Entry {
+    // Pick order at `time`: higher response ratio first, compared exactly, the one queued first among equals
+    fn cmp_at(&self, other: &Entry, time: u32) -> Ordering {
+        let waited = |entry: &Entry| time.saturating_sub(entry.ready_since) as u64;
+        let ours = waited(self) * other.burst as u64;
+        let theirs = waited(other) * self.burst as u64;
+        theirs.cmp(&ours).then(self.order.cmp(&other.order))
+    }
+
+    fn beats(&self, other: &Entry, time: u32) -> bool {
+        self.cmp_at(other, time) == Ordering::Less
+    }
+
+    /*
+     *  First time after `time` at which `other` beats `self`, which beats it at `time`.
+     *
+     *  The ratios are lines in time and the shorter burst has the steeper one, so only
+     *  a shorter `other` ever catches up, and then stays ahead.
+     */
+    fn overtaken_at(&self, other: &Entry, time: u32) -> u64 {
+        if other.burst >= self.burst {
+            return NEVER;
+        }
+
+        // other beats self once t * (b_self - b_other) > s_other * b_self - s_self * b_other
+        let slope = (self.burst - other.burst) as i128;
+        let offset = other.ready_since as i128 * self.burst as i128 - self.ready_since as i128 * other.burst as i128;
+        let at = match other.order < self.order {
+            true => offset.div_euclid(slope) + i128::from(offset.rem_euclid(slope) != 0),
+            false => offset.div_euclid(slope) + 1,
+        };
+
+        at.max(time as i128 + 1) as u64
+    }
 }
 
-impl HRRNScheduler {
-    pub fn new(processes: Vec<HRRNProcess>) -> Self {
-        HRRNScheduler { 
-            processes, 
-            gantt_chart: GanttChart::new()
+const NEVER: u64 = u64::MAX;
+
+/*
+ *  Kinetic tournament over the queued processes, a leaf per process index.
+ *
+ *  * Every node holds the process with the highest response ratio below it, valid
+ *    until `expires`, the earliest time a loser below it overtakes its winner.
+ *  * Time only moves forward: before a decision every expired node is replayed,
+ *    children first. Each pair of processes swaps places at most once, so a push
+ *    or pop costs O(log n) amortized instead of a scan of the ready processes.
+ *  * `now` is the latest time the tree was brought up to.
+ */
+#[derive(Debug, Default)]
+struct ResponseRatioQueue {
+    entries: Vec<Option<Entry>>,
+    winner: Vec<Option<usize>>,
+    expires: Vec<u64>,
+    leaves: usize,
+    len: usize,
+    inserted: u64,
+    now: u32,
+}
+
+impl ResponseRatioQueue {
+    // Better of the winners of `a` and `b` at `time`, and when the other one overtakes it
+    fn play(&self, a: Option<usize>, b: Option<usize>, time: u32) -> (Option<usize>, u64) {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let (entry_a, entry_b) = (self.entries[a].unwrap(), self.entries[b].unwrap());
+                if entry_a.beats(&entry_b, time) {
+                    (Some(a), entry_a.overtaken_at(&entry_b, time))
+                } else {
+                    (Some(b), entry_b.overtaken_at(&entry_a, time))
+                }
+            }
+            (a, b) => (a.or(b), NEVER),
         }
     }
 
+    fn pull(&mut self, node: usize, time: u32) {
+        let (left, right) = (2 * node, 2 * node + 1);
+        let (winner, expires) = self.play(self.winner[left], self.winner[right], time);
+        self.winner[node] = winner;
+        self.expires[node] = expires.min(self.expires[left]).min(self.expires[right]);
+    }
+
+    // Replays every node below `node` whose winner changed by `time`
+    fn advance(&mut self, node: usize, time: u32) {
+        if self.expires[node] > time as u64 || node >= self.leaves {
+            return;
+        }
+
+        self.advance(2 * node, time);
+        self.advance(2 * node + 1, time);
+        self.pull(node, time);
+    }
+
+    fn set(&mut self, idx: usize, entry: Option<Entry>, time: u32) {
+        self.entries[idx] = entry;
+
+        let mut node = self.leaves + idx;
+        self.winner[node] = entry.map(|_| idx);
+        while node > 1 {
+            node /= 2;
+            self.pull(node, time);
+        }
+    }
+
+    // Best process below `node` that may run on `core`, only subtrees whose winner can't are searched
+    fn best_for_core(&self, node: usize, processes: &[ProcessState], core: usize, time: u32) -> Option<usize> {
+        let winner = self.winner[node]?;
+        if processes[winner].base.allows_core(core) {
+            return Some(winner);
+        }
+        if node >= self.leaves {
+            return None;
+        }
+
+        let left = self.best_for_core(2 * node, processes, core, time);
+        let right = self.best_for_core(2 * node + 1, processes, core, time);
+        self.play(left, right, time).0
+    }
+}
+
+impl ReadyQueue for ResponseRatioQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        if self.leaves < processes.len() {
+            self.leaves = processes.len().next_power_of_two();
+            self.entries = vec![None; self.leaves];
+            self.winner = vec![None; 2 * self.leaves];
+            self.expires = vec![NEVER; 2 * self.leaves];
+        }
+
+        self.now = self.now.max(time);
+        self.advance(1, self.now);
+
+        let process = &processes[idx];
+        self.inserted += 1;
+        let entry = Entry { ready_since: process.ready_since, burst: process.remaining_time.max(1), order: self.inserted };
+        self.set(idx, Some(entry), self.now);
+        self.len += 1;
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.now = self.now.max(time);
+        self.advance(1, self.now);
+
+        let idx = self.best_for_core(1, processes, core, self.now)?;
+        self.set(idx, None, self.now);
+        self.len -= 1;
+        Some(idx)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    // Highest response ratio first, equal ratios in the order they became ready
+    fn ready(&self, _processes: &[ProcessState], time: u32) -> Vec<usize> {
+        let mut ready: Vec<usize> = (0..self.entries.len()).filter(|&idx| self.entries[idx].is_some()).collect();
+        ready.sort_by(|&a, &b| self.entries[a].unwrap().cmp_at(&self.entries[b].unwrap(), time));
+        ready
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], time: u32) -> Option<DecisionValue> {
+        let ratio = HRRNScheduler::calculate_response_ratio(&processes[idx], time);
+        Some(DecisionValue::new("response ratio", ratio as f64))
+    }
+}
+
+#[derive(Debug)]
+pub struct HRRNScheduler;
+
+impl HRRNScheduler {
     pub fn calculate_response_ratio(
-        &self,
-        process_index: usize,
+        process: &ProcessState,
         current_time: u32
     ) -> f32 {
-        let process: &HRRNProcess = &self.processes[process_index];
-        let waiting_time = current_time.saturating_sub(process.base.arrival_time);
+        let waiting_time = current_time.saturating_sub(process.ready_since);
+        let burst_time = process.remaining_time.max(1);
 
         // Response Ration Formula
-        (waiting_time as f32 + process.base.burst_time as f32) / process.base.burst_time as f32
-    }
-
-    pub fn avg_turnaround_time(&self) -> f32 {
-        self.processes
-            .iter()
-            .map(|p| p.turnaround_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
-    }
-
-    pub fn avg_waiting_time(&self) -> f32 {
-        self.processes
-            .iter()
-            .map(|p| p.waiting_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
-    }
-
-    fn display_table(processes: &[HRRNProcess]) {
-        const HEADER: &str = "|---------|----|----|----|-----|----|----|";
-
-        println!("\n{}", HEADER);
-        println!(
-            "|{:^9}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|{:^4}|",
-            "Process", "AT", "BT", "CT", "TAT", "WT", "RT"
-        );
-        println!("{}", HEADER);
-
-        for p in processes {
-            println!(
-                "|{:^9}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|{:^4}|",
-                p.base.pid,
-                p.base.arrival_time,
-                p.base.burst_time,
-                p.completion_time,
-                p.turnaround_time,
-                p.waiting_time,
-                p.response_time,
-            );
-        }
-
-        println!("{}\n", HEADER);
+        (waiting_time as f32 + burst_time as f32) / burst_time as f32
     }
 }
 
-impl HRRNSched for HRRNScheduler {
-    fn schedule(&mut self) {
-        self.processes.sort_by_key(|p: &HRRNProcess| p.base.arrival_time);
+impl Scheduler for HRRNScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        simulate(self, processes, config, ResponseRatioQueue::default)
+    }
 
-        let num_processes = self.processes.len();
-        let mut completed_process = 0;
-        let mut current_time = 0;
-        let mut is_completed = vec![false; num_processes];
-        let mut next_arrival_index = 0;
+    fn name(&self) -> String {
+        "Highest Response Ratio Next (HRRN)".to_string()
+    }
 
-        while completed_process < num_processes {
-            let mut selected_index = None;
-            let mut highest_response_ratio = f32::MIN;
+    fn preemptive(&self) -> bool {
+        false
+    }
+}
 
-            for i in 0..num_processes {
-                if self.processes[i].base.arrival_time > current_time {
-                    break;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::generator::{ArrivalDistribution, BurstDistribution, WorkloadGenerator};
 
-                if !is_completed[i] {
-                    let response_ratio = self.calculate_response_ratio(i, current_time);
+    // Completion time of every process, picking by a scan of the ready processes at every decision
+    fn naive_completions(processes: &[BaseProcess]) -> Vec<u32> {
+        let mut completions = vec![None; processes.len()];
+        let mut time = 0;
 
-                    if response_ratio > highest_response_ratio {
-                        highest_response_ratio = response_ratio;
-                        selected_index = Some(i);
-                    }
-                }
-            }
+        while completions.contains(&None) {
+            let ready = (0..processes.len()).filter(|&i| completions[i].is_none() && processes[i].arrival_time <= time);
+            // (waited + burst) / burst compared exactly, the earliest arrival among equals
+            let best = ready.max_by(|&a, &b| {
+                let ratio = |i: usize| (time - processes[i].arrival_time) as u64 + processes[i].burst_time as u64;
+                (ratio(a) * processes[b].burst_time as u64)
+                    .cmp(&(ratio(b) * processes[a].burst_time as u64))
+                    .then((processes[b].arrival_time, b).cmp(&(processes[a].arrival_time, a)))
+            });
 
-            match selected_index {
+            match best {
                 Some(i) => {
-                    let process = &mut self.processes[i];
-                    let start_time = current_time;
-                    let end_time = start_time + process.base.burst_time;
-
-                    process.completion_time = end_time;
-                    process.turnaround_time = end_time - process.base.arrival_time;
-                    process.waiting_time = process.turnaround_time - process.base.burst_time;
-                    process.response_time = start_time.saturating_sub(process.base.arrival_time);
-
-                    self.gantt_chart.segments.push(GanttSegment {
-                        pid: process.base.pid,
-                        start_time,
-                        end_time
-                    });
-
-                    is_completed[i] = true;
-                    completed_process += 1;
-                    current_time = end_time;
-
-                    while next_arrival_index < num_processes
-                        && self.processes[next_arrival_index].base.arrival_time <= current_time
-                    {
-                        next_arrival_index += 1;
-                    }
+                    time += processes[i].burst_time;
+                    completions[i] = Some(time);
                 }
                 None => {
-                    if next_arrival_index < num_processes {
-                        current_time = self.processes[next_arrival_index].base.arrival_time;
-                    } else {
-                        current_time += 1;
-                    }
+                    time = (0..processes.len())
+                        .filter(|&i| completions[i].is_none())
+                        .map(|i| processes[i].arrival_time)
+                        .min()
+                        .unwrap();
                 }
             }
         }
+
+        completions.into_iter().map(Option::unwrap).collect()
     }
 
-    fn display(&self) {
-        Self::display_table(&self.processes);
+    #[test]
+    fn matches_a_naive_scan_of_the_ready_processes() {
+        for seed in 0..20 {
+            let processes = WorkloadGenerator::new(200, seed)
+                .with_arrival(ArrivalDistribution::Uniform { min: 0, max: 6 })
+                .with_burst(BurstDistribution::Uniform { min: 1, max: 12 })
+                .generate()
+                .unwrap();
+
+            let result = HRRNScheduler.schedule(&processes, &SchedulerConfig::default()).unwrap();
+            let completions: Vec<u32> = result.processes.iter().map(|p| p.completion_time).collect();
+            assert_eq!(completions, naive_completions(&processes), "seed {}", seed);
+        }
+    }
 
-        println!("Average Turnaround Time: {:.2} ms", self.avg_turnaround_time());
-        println!("Average Waiting Time: {:.2} ms", self.avg_waiting_time());
+    #[test]
+    fn ready_lists_processes_in_pick_order() {
+        // Many equal ratios, the order they were queued in breaks the ties
+        let processes: Vec<ProcessState> = (0..64)
+            .map(|pid| ProcessState::new(BaseProcess::new(pid, 0, 1 + pid as u32 % 3)))
+            .collect();
+        let mut queue = ResponseRatioQueue::default();
+        for idx in 0..processes.len() {
+            queue.push(idx, &processes, 0);
+        }
 
-        self.gantt_chart.display_gantt_chart();
+        let ready = queue.ready(&processes, 6);
+        let picked: Vec<usize> = std::iter::from_fn(|| queue.pop(&processes, 6, 0)).collect();
+        assert_eq!(ready, picked);
     }
-}
\ No newline at end of file
+}
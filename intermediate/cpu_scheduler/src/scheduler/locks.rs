@@ -0,0 +1,368 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use crate::{models::cpu_process::ProcessState, utils::gantt_display::LockWait};
+
+/*
+ *  How a process holding a lock is scheduled while other processes need it.
+ *
+ *  * None: the holder keeps its own priority, a process of medium priority can
+ *    preempt it and hold up every waiter of higher priority (priority inversion).
+ *  * Inheritance: the holder runs at the highest priority of the processes blocked on its lock.
+ *  * Ceiling: the holder runs at the ceiling of the lock as soon as it takes it, the
+ *    highest priority of every process that uses the lock (immediate ceiling protocol).
+ *
+ *  Every scheduler blocks the waiters of a lock, only the Priority Scheduler orders
+ *  processes by the priorities the protocol lends.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockProtocol {
+    #[default]
+    None,
+    Inheritance,
+    Ceiling,
+}
+
+impl LockProtocol {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(LockProtocol::None),
+            "inheritance" | "pip" | "priority-inheritance" => Some(LockProtocol::Inheritance),
+            "ceiling" | "pcp" | "priority-ceiling" => Some(LockProtocol::Ceiling),
+            _ => None,
+        }
+    }
+
+    // Name accepted by `from_name`
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockProtocol::None => "none",
+            LockProtocol::Inheritance => "inheritance",
+            LockProtocol::Ceiling => "ceiling",
+        }
+    }
+}
+
+impl fmt::Display for LockProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockProtocol::None => write!(f, "no protocol"),
+            LockProtocol::Inheritance => write!(f, "priority inheritance"),
+            LockProtocol::Ceiling => write!(f, "priority ceiling"),
+        }
+    }
+}
+
+// What a running process did at one of its lock points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockEvent {
+    // `ceiling` is the priority the process now runs at, with the ceiling protocol
+    Acquired { lock: usize, ceiling: Option<u32> },
+    // The process left the CPU, `inherited` is the priority `holder` now runs at
+    Blocked { lock: usize, holder: usize, inherited: Option<u32> },
+    // `to` is the waiter the lock was handed to, it is ready again
+    Released { lock: usize, to: Option<usize> },
+}
+
+#[derive(Debug)]
+struct Lock {
+    name: String,
+    // Highest priority (lowest value) of every process that uses the lock
+    ceiling: u32,
+    holder: Option<usize>,
+    // (process, time it blocked, holder at that time) in the order they blocked
+    waiters: Vec<(usize, u32, usize)>,
+}
+
+/*
+ *  The locks of a workload while the engine runs it.
+ *
+ *  * Processes are referred to by their index in the `processes` slice, like in the queues.
+ *  * `sections` holds (lock, start, end) of every critical section of a process in CPU time,
+ *    `next` the section it is in or waits for next and `holding` whether it holds its lock.
+ *  * A released lock goes straight to its waiter of highest priority, the one that
+ *    blocked first among equals, so a process that didn't wait can't take it first.
+ *  * `live` holds the processes that arrived and hadn't completed when last accounted,
+ *    `admitted` how many arrivals it has taken in.
+ *  * `waits` collects every finished wait for the Gantt chart.
+ */
+#[derive(Debug)]
+pub struct Locks {
+    protocol: LockProtocol,
+    locks: Vec<Lock>,
+    sections: Vec<Vec<(usize, u32, u32)>>,
+    next: Vec<usize>,
+    holding: Vec<bool>,
+    blocked: Vec<Option<usize>>,
+    live: Vec<usize>,
+    admitted: usize,
+    pub waits: Vec<LockWait>,
+}
+
+impl Locks {
+    // None when no process has a critical section
+    pub fn new(processes: &[ProcessState], protocol: LockProtocol) -> Option<Self> {
+        if processes.iter().all(|p| p.base.sections.is_empty()) {
+            return None;
+        }
+
+        // Locks are numbered in name order
+        let mut ceilings: BTreeMap<&str, u32> = BTreeMap::new();
+        for process in processes {
+            for section in &process.base.sections {
+                let ceiling = ceilings.entry(section.lock.as_str()).or_insert(process.base.priority);
+                *ceiling = (*ceiling).min(process.base.priority);
+            }
+        }
+
+        let ids: BTreeMap<&str, usize> = ceilings.keys().enumerate().map(|(id, &name)| (name, id)).collect();
+        let sections = processes
+            .iter()
+            .map(|p| p.base.sections.iter().map(|s| (ids[s.lock.as_str()], s.start, s.end())).collect())
+            .collect();
+
+        Some(Locks {
+            protocol,
+            locks: ceilings
+                .iter()
+                .map(|(&name, &ceiling)| Lock { name: name.to_string(), ceiling, holder: None, waiters: Vec::new() })
+                .collect(),
+            sections,
+            next: vec![0; processes.len()],
+            holding: vec![false; processes.len()],
+            blocked: vec![None; processes.len()],
+            live: Vec::new(),
+            admitted: 0,
+            waits: Vec::new(),
+        })
+    }
+
+    pub fn protocol(&self) -> LockProtocol {
+        self.protocol
+    }
+
+    pub fn name(&self, lock: usize) -> &str {
+        &self.locks[lock].name
+    }
+
+    // CPU time `idx` can run before its next lock point
+    pub fn until_next_point(&self, idx: usize, processes: &[ProcessState]) -> Option<u32> {
+        let &(_, start, end) = self.sections[idx].get(self.next[idx])?;
+        let point = if self.holding[idx] { end } else { start };
+        Some(point.saturating_sub(processes[idx].cpu_time()))
+    }
+
+    // Releases every lock running process `idx` is done with by `time`, even at the end of a burst
+    pub fn release_due(&mut self, idx: usize, processes: &mut [ProcessState], time: u32) -> Vec<LockEvent> {
+        let mut events = Vec::new();
+
+        while self.holding[idx]
+            && let Some(&(lock, _, end)) = self.sections[idx].get(self.next[idx])
+            && processes[idx].cpu_time() >= end
+        {
+            let to = self.release(idx, lock, processes, time);
+            events.push(LockEvent::Released { lock, to });
+        }
+
+        events
+    }
+
+    /*
+     *  Takes the lock running process `idx` reached by `time`, or blocks it on the lock.
+     *
+     *  * Called once every release due at `time` is done, so a lock given up at the same
+     *    moment is free (or already handed to a waiter).
+     *  * A lock is only taken once the process has CPU time left in its burst, a section
+     *    starting right after an I/O burst is entered when the process runs again.
+     *  * After `Blocked` the process has to leave the CPU.
+     */
+    pub fn acquire_due(&mut self, idx: usize, processes: &mut [ProcessState], time: u32) -> Option<LockEvent> {
+        let &(lock, start, _) = self.sections[idx].get(self.next[idx])?;
+        if self.holding[idx] || processes[idx].cpu_time() < start || processes[idx].remaining_time == 0 {
+            return None;
+        }
+
+        match self.locks[lock].holder {
+            None => {
+                self.take(idx, lock, processes, time);
+                let ceiling = (self.protocol == LockProtocol::Ceiling).then_some(self.locks[lock].ceiling);
+                Some(LockEvent::Acquired { lock, ceiling })
+            }
+            Some(holder) => {
+                self.locks[lock].waiters.push((idx, time, holder));
+                self.blocked[idx] = Some(lock);
+                let inherited = self.inherit(lock, processes, time);
+                Some(LockEvent::Blocked { lock, holder, inherited })
+            }
+        }
+    }
+
+    fn take(&mut self, idx: usize, lock: usize, processes: &mut [ProcessState], time: u32) {
+        self.locks[lock].holder = Some(idx);
+        self.holding[idx] = true;
+
+        if self.protocol == LockProtocol::Ceiling {
+            processes[idx].set_lock_priority(Some(self.locks[lock].ceiling), time);
+        }
+    }
+
+    // Gives the lock up and hands it to the best waiter, returned so it can be queued again
+    fn release(&mut self, idx: usize, lock: usize, processes: &mut [ProcessState], time: u32) -> Option<usize> {
+        self.locks[lock].holder = None;
+        self.holding[idx] = false;
+        self.next[idx] += 1;
+        processes[idx].set_lock_priority(None, time);
+
+        let waiters = &mut self.locks[lock].waiters;
+        let best = (0..waiters.len()).min_by_key(|&i| (processes[waiters[i].0].effective_priority(), i))?;
+        let (waiter, since, holder) = waiters.remove(best);
+
+        self.blocked[waiter] = None;
+        processes[waiter].lock_wait_time += time - since;
+        self.waits.push(LockWait {
+            pid: processes[waiter].base.pid,
+            lock: self.locks[lock].name.clone(),
+            holder: processes[holder].base.pid,
+            start_time: since,
+            end_time: time,
+        });
+
+        self.take(waiter, lock, processes, time);
+        self.inherit(lock, processes, time);
+        Some(waiter)
+    }
+
+    // With priority inheritance, lends the holder of `lock` the priority of its best waiter if that is higher
+    fn inherit(&self, lock: usize, processes: &mut [ProcessState], time: u32) -> Option<u32> {
+        let holder = self.locks[lock].holder.filter(|_| self.protocol == LockProtocol::Inheritance)?;
+        let inherited = self.locks[lock].waiters
+            .iter()
+            .map(|&(waiter, ..)| processes[waiter].effective_priority())
+            .min()
+            .filter(|&priority| priority < processes[holder].priority);
+
+        processes[holder].set_lock_priority(inherited, time);
+        inherited
+    }
+
+    /*
+     *  Adds the time from `time` to `next_time` to the inversion time of every waiting
+     *  process held up by a process of lower priority.
+     *
+     *  * Blocked on a lock: the holder has a lower priority and a process of lower
+     *    priority than the waiter is running, the holder itself or any other.
+     *  * Ready: a running process of lower priority keeps it off the CPU only because
+     *    of the priority a lock lends it.
+     *
+     *  Priorities are the ones given in the workload, aging and protocols aside. `arrived`
+     *  lists every process released so far, only the ones still live are looked at.
+     */
+    pub fn account(
+        &mut self,
+        processes: &mut [ProcessState],
+        arrived: &[usize],
+        running: &[usize],
+        time: u32,
+        next_time: u32,
+    ) {
+        self.live.extend_from_slice(&arrived[self.admitted..]);
+        self.admitted = arrived.len();
+
+        if next_time <= time || self.locks.iter().all(|lock| lock.holder.is_none()) {
+            return;
+        }
+
+        let Some(lowest) = running.iter().map(|&idx| processes[idx].base.priority).max() else {
+            return;
+        };
+
+        self.live.retain(|&idx| processes[idx].completion_time.is_none());
+
+        for &idx in &self.live {
+            let process = &processes[idx];
+            let priority = process.base.priority;
+
+            if priority >= lowest || running.contains(&idx) {
+                continue;
+            }
+
+            let inverted = match self.blocked[idx] {
+                Some(lock) => self.locks[lock].holder.is_some_and(|holder| processes[holder].base.priority > priority),
+                None => {
+                    process.blocked_until.is_none_or(|until| until <= time)
+                        && running.iter().any(|&other| {
+                            self.holding[other]
+                                && processes[other].base.priority > priority
+                                && processes[other].effective_priority() <= priority
+                        })
+                }
+            };
+
+            if inverted {
+                processes[idx].inversion_time += next_time - time;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{cpu_process::{BaseProcess, CriticalSection}, schedule_result::ProcessResult},
+        scheduler::{Scheduler, SchedulerConfig, priority::PriorityScheduler},
+    };
+
+    /*
+     *  The Mars Pathfinder case: L takes lock A, H needs A right after, and M, which
+     *  doesn't need A, arrives while L holds it.
+     *
+     *  Returns the results of L, H and M.
+     */
+    fn pathfinder(protocol: LockProtocol) -> [ProcessResult; 3] {
+        let processes = vec![
+            BaseProcess::new(1, 0, 4).with_priority(3).with_sections(vec![CriticalSection::new("A", 0, 3)]),
+            BaseProcess::new(2, 1, 2).with_priority(1).with_sections(vec![CriticalSection::new("A", 0, 1)]),
+            BaseProcess::new(3, 2, 5).with_priority(2),
+        ];
+
+        let result = PriorityScheduler::new(true, None)
+            .with_lock_protocol(protocol)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+        result.processes.try_into().unwrap()
+    }
+
+    #[test]
+    fn medium_process_holds_up_the_waiter_without_a_protocol() {
+        let [low, high, medium] = pathfinder(LockProtocol::None);
+
+        // M runs 2-7 while H waits on L for A, from 1 until L releases it at 8
+        assert_eq!((medium.completion_time, high.completion_time, low.completion_time), (7, 10, 11));
+        assert_eq!((low.inversion_time, high.inversion_time, medium.inversion_time), (0, 7, 0));
+    }
+
+    #[test]
+    fn holder_inherits_the_priority_of_the_waiter() {
+        let [low, high, medium] = pathfinder(LockProtocol::Inheritance);
+
+        // L runs at 1 from the moment H blocks until it releases A at 3
+        assert_eq!(low.priority_history, vec![(0, 3), (1, 1), (3, 3)]);
+        assert_eq!((high.completion_time, medium.completion_time, low.completion_time), (5, 10, 11));
+        // H blocked 1-3, M kept off the CPU 2-3 only by the lent priority
+        assert_eq!((low.inversion_time, high.inversion_time, medium.inversion_time), (0, 2, 1));
+    }
+
+    #[test]
+    fn holder_runs_at_the_ceiling_as_soon_as_it_takes_the_lock() {
+        let [low, high, medium] = pathfinder(LockProtocol::Ceiling);
+
+        // H never blocks, it can't preempt L at the ceiling
+        assert_eq!(low.priority_history, vec![(0, 3), (0, 1), (3, 3)]);
+        assert_eq!(high.lock_wait_time, 0);
+        assert_eq!((high.completion_time, medium.completion_time, low.completion_time), (5, 10, 11));
+        assert_eq!((low.inversion_time, high.inversion_time, medium.inversion_time), (0, 2, 1));
+    }
+}
@@ -0,0 +1,411 @@
+use std::cell::Cell;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    models::{
+        cpu_process::{BaseProcess, ProcessState},
+        schedule_result::{ScheduleResult, ShareBasis},
+        trace::DecisionValue,
+    },
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
+
+// Seed used when none is given, so two runs of the same workload draw the same winners
+pub const DEFAULT_SEED: u64 = 42;
+
+/*
+ *  A lottery is held every time quantum, each ready process holds `tickets` and
+ *  wins with probability tickets / total tickets of the ready processes.
+ *
+ *  * Ticket transfer: for every (from, to) pair of pids, `from` lends its tickets
+ *    to `to` while it is blocked on I/O (a client waiting on its server).
+ *  * Ticket inflation (compensation tickets): a process that used only a fraction f
+ *    of its last quantum holds tickets / f until it runs again, so I/O bound
+ *    processes still get their share.
+ */
+#[derive(Debug)]
+pub struct LotteryScheduler {
+    pub time_quantum: u32,
+    pub seed: u64,
+    pub transfers: Vec<(usize, usize)>,
+    pub compensation: bool,
+}
+
+impl LotteryScheduler {
+    pub fn new(time_quantum: u32, seed: u64) -> Self {
+        LotteryScheduler {
+            time_quantum,
+            seed,
+            transfers: Vec::new(),
+            compensation: false,
+        }
+    }
+
+    pub fn with_transfers(mut self, transfers: Vec<(usize, usize)>) -> Self {
+        self.transfers = transfers;
+        self
+    }
+
+    pub fn with_compensation(mut self, compensation: bool) -> Self {
+        self.compensation = compensation;
+        self
+    }
+}
+
+/*
+ *  Ready processes in the order they became ready, with a Fenwick tree over their
+ *  tickets so a draw finds its winner in O(log n).
+ *
+ *  * `slots` never shift: a process leaving empties its slot, and the slots are
+ *    compacted once more than half of them are empty.
+ *  * Tickets only change while a process waits when it borrows them, so the tickets
+ *    of `borrowers` are brought up to date before every draw.
+ *  * A draw among processes pinned to some cores walks the slots, the tree holds
+ *    the tickets of every queued process.
+ */
+struct LotteryQueue<'a> {
+    slots: Vec<Option<usize>>,
+    // Tickets of every slot and the Fenwick tree over them, 1-based
+    tickets: Vec<u64>,
+    tree: Vec<u64>,
+    slot_of: Vec<usize>,
+    len: usize,
+    // Queued processes with an affinity
+    pinned: usize,
+    rng: StdRng,
+    scheduler: &'a LotteryScheduler,
+    // (index of `from`, pid of `to`) of every transfer, resolved once per run
+    lenders: &'a [(usize, usize)],
+    // Index of every `to` of a transfer
+    borrowers: &'a [usize],
+}
+
+impl LotteryQueue<'_> {
+    // Tickets `idx` holds at `time`, with transfers and compensation applied
+    fn tickets(&self, idx: usize, processes: &[ProcessState], time: u32) -> u64 {
+        let process = &processes[idx];
+        let mut tickets = process.base.tickets as u64;
+
+        for &(lender, to) in self.lenders {
+            let lender = &processes[lender];
+            if to == process.base.pid && lender.blocked_until.is_some_and(|until| until > time) {
+                tickets += lender.base.tickets as u64;
+            }
+        }
+
+        let time_quantum = self.scheduler.time_quantum.max(1);
+        if self.scheduler.compensation && process.last_run > 0 && process.last_run < time_quantum {
+            tickets = tickets * time_quantum as u64 / process.last_run as u64;
+        }
+
+        tickets
+    }
+
+    fn is_queued(&self, idx: usize) -> bool {
+        self.slot_of.get(idx).is_some_and(|&slot| self.slots.get(slot) == Some(&Some(idx)))
+    }
+
+    fn set_tickets(&mut self, slot: usize, tickets: u64) {
+        let delta = tickets.wrapping_sub(self.tickets[slot]);
+        self.tickets[slot] = tickets;
+
+        let mut position = slot + 1;
+        while position < self.tree.len() {
+            self.tree[position] = self.tree[position].wrapping_add(delta);
+            position += position & position.wrapping_neg();
+        }
+    }
+
+    // Tickets of the first `count` slots
+    fn prefix(&self, count: usize) -> u64 {
+        let mut sum = 0u64;
+        let mut position = count;
+        while position > 0 {
+            sum = sum.wrapping_add(self.tree[position]);
+            position &= position - 1;
+        }
+        sum
+    }
+
+    // First slot whose tickets cover `ticket`, counting from the first slot
+    fn find(&self, mut ticket: u64) -> usize {
+        let mut position = 0;
+        let mut step = (self.tree.len() - 1).checked_next_power_of_two().unwrap_or(0);
+
+        while step > 0 {
+            let next = position + step;
+            if next < self.tree.len() && self.tree[next] <= ticket {
+                position = next;
+                ticket -= self.tree[next];
+            }
+            step /= 2;
+        }
+
+        position
+    }
+
+    // Drops the empty slots, in O(slots)
+    fn compact(&mut self) {
+        let live: Vec<(usize, u64)> = self.slots
+            .iter()
+            .zip(&self.tickets)
+            .filter_map(|(slot, &tickets)| slot.map(|idx| (idx, tickets)))
+            .collect();
+
+        self.slots = live.iter().map(|&(idx, _)| Some(idx)).collect();
+        self.tickets = live.iter().map(|&(_, tickets)| tickets).collect();
+        self.tree = vec![0; live.len() + 1];
+
+        for (slot, &(idx, tickets)) in live.iter().enumerate() {
+            self.slot_of[idx] = slot;
+
+            let position = slot + 1;
+            self.tree[position] = self.tree[position].wrapping_add(tickets);
+            let parent = position + (position & position.wrapping_neg());
+            if parent < self.tree.len() {
+                self.tree[parent] = self.tree[parent].wrapping_add(self.tree[position]);
+            }
+        }
+    }
+
+    fn take(&mut self, slot: usize, processes: &[ProcessState]) -> usize {
+        let idx = self.slots[slot].take().unwrap();
+        self.set_tickets(slot, 0);
+        self.len -= 1;
+        if !processes[idx].base.affinity.is_empty() {
+            self.pinned -= 1;
+        }
+
+        if self.slots.len() > 2 * self.len + 64 {
+            self.compact();
+        }
+        idx
+    }
+}
+
+impl ReadyQueue for LotteryQueue<'_> {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        if self.slot_of.len() < processes.len() {
+            self.slot_of.resize(processes.len(), 0);
+        }
+        if self.tree.is_empty() {
+            self.tree.push(0);
+        }
+
+        let tickets = self.tickets(idx, processes, time);
+        let slot = self.slots.len();
+        self.slots.push(Some(idx));
+        self.tickets.push(tickets);
+        self.slot_of[idx] = slot;
+
+        // A new last node covers the slots since the node that ends just before its range
+        let position = slot + 1;
+        let covered = self.prefix(position - 1).wrapping_sub(self.prefix(position - (position & position.wrapping_neg())));
+        self.tree.push(tickets.wrapping_add(covered));
+
+        self.len += 1;
+        if !processes[idx].base.affinity.is_empty() {
+            self.pinned += 1;
+        }
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+
+        for &borrower in self.borrowers {
+            if self.is_queued(borrower) {
+                let tickets = self.tickets(borrower, processes, time);
+                self.set_tickets(self.slot_of[borrower], tickets);
+            }
+        }
+
+        if self.pinned > 0 {
+            let candidates: Vec<(usize, u64)> = (0..self.slots.len())
+                .filter(|&slot| self.slots[slot].is_some_and(|idx| processes[idx].base.allows_core(core)))
+                .map(|slot| (slot, self.tickets[slot]))
+                .collect();
+
+            let total_tickets: u64 = candidates.iter().map(|(_, tickets)| tickets).sum();
+            let (first, _) = *candidates.first()?;
+
+            // Nobody holds a ticket, fall back to the first in line
+            if total_tickets == 0 {
+                return Some(self.take(first, processes));
+            }
+
+            let mut winning_ticket = self.rng.random_range(0..total_tickets);
+            let winner = candidates
+                .iter()
+                .find(|&&(_, tickets)| {
+                    if winning_ticket < tickets {
+                        return true;
+                    }
+                    winning_ticket -= tickets;
+                    false
+                })
+                .map_or(first, |&(slot, _)| slot);
+
+            return Some(self.take(winner, processes));
+        }
+
+        let total_tickets = self.prefix(self.slots.len());
+        let winner = match total_tickets {
+            0 => self.slots.iter().position(Option::is_some)?,
+            total => {
+                let winning_ticket = self.rng.random_range(0..total);
+                self.find(winning_ticket)
+            }
+        };
+
+        Some(self.take(winner, processes))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    // The winner is drawn, so the order is just the order they became ready
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        self.slots.iter().flatten().copied().collect()
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], time: u32) -> Option<DecisionValue> {
+        Some(DecisionValue::new("tickets", self.tickets(idx, processes, time) as f64))
+    }
+
+    fn time_slice(&self, _running: usize, _processes: &[ProcessState]) -> Option<u32> {
+        Some(self.scheduler.time_quantum.max(1))
+    }
+}
+
+impl Scheduler for LotteryScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let lenders: Vec<(usize, usize)> = self.transfers
+            .iter()
+            .filter_map(|&(from, to)| processes.iter().position(|p| p.pid == from).map(|lender| (lender, to)))
+            .collect();
+        let borrowers: Vec<usize> = processes
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| lenders.iter().any(|&(_, to)| to == p.pid))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        // Every per-core queue draws from its own generator, seeded one after the other
+        let next_seed = Cell::new(self.seed);
+        let new_queue = || {
+            let seed = next_seed.get();
+            next_seed.set(seed.wrapping_add(1));
+
+            LotteryQueue {
+                slots: Vec::new(),
+                tickets: Vec::new(),
+                tree: Vec::new(),
+                slot_of: Vec::new(),
+                len: 0,
+                pinned: 0,
+                rng: StdRng::seed_from_u64(seed),
+                scheduler: self,
+                lenders: &lenders,
+                borrowers: &borrowers,
+            }
+        };
+
+        let mut result = simulate(self, processes, config, new_queue)?;
+        result.share_basis = Some(ShareBasis::Tickets);
+        Ok(result)
+    }
+
+    fn name(&self) -> String {
+        let mut name = format!("Lottery (Time Quantum: {}, Seed: {}", self.time_quantum, self.seed);
+        if !self.transfers.is_empty() {
+            name.push_str(", Transfer");
+        }
+        if self.compensation {
+            name.push_str(", Compensation");
+        }
+        name.push(')');
+        name
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        let transfers: Vec<String> = self.transfers.iter().map(|(from, to)| format!("{}:{}", from, to)).collect();
+        vec![
+            ("time_quantum", self.time_quantum.to_string()),
+            ("seed", self.seed.to_string()),
+            ("transfers", transfers.join(",")),
+            ("compensation", self.compensation.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(scheduler: &LotteryScheduler) -> LotteryQueue<'_> {
+        LotteryQueue {
+            slots: Vec::new(),
+            tickets: Vec::new(),
+            tree: Vec::new(),
+            slot_of: Vec::new(),
+            len: 0,
+            pinned: 0,
+            rng: StdRng::seed_from_u64(scheduler.seed),
+            scheduler,
+            lenders: &[],
+            borrowers: &[],
+        }
+    }
+
+    // Slot of the ready process holding `ticket`, by a scan of the cumulative tickets
+    fn naive_winner(ready: &[(usize, u64)], ticket: u64) -> usize {
+        let mut covered = 0;
+        ready.iter().position(|&(_, tickets)| {
+            covered += tickets;
+            ticket < covered
+        }).unwrap()
+    }
+
+    #[test]
+    fn draws_the_same_winners_as_a_scan_of_the_tickets() {
+        let processes: Vec<ProcessState> = (0..600)
+            .map(|idx| ProcessState::new(BaseProcess::new(idx + 1, 0, 1).with_tickets(1 + (idx * 37 % 50) as u32)))
+            .collect();
+        let scheduler = LotteryScheduler::new(1, 7);
+        let mut queue = queue(&scheduler);
+        let mut draws = StdRng::seed_from_u64(scheduler.seed);
+        // (index, tickets) of the ready processes in the order they were queued
+        let mut ready: Vec<(usize, u64)> = Vec::new();
+        let mut next = 0;
+
+        // Mostly queueing at first, then mostly drawing, so empty slots pile up and get compacted
+        for round in 0..1200 {
+            if next < processes.len() && (round < 400 || round % 3 == 0) {
+                queue.push(next, &processes, 0);
+                ready.push((next, processes[next].base.tickets as u64));
+                next += 1;
+                continue;
+            }
+            if ready.is_empty() {
+                break;
+            }
+
+            // Every ticket maps to the slot a scan finds
+            let total: u64 = ready.iter().map(|&(_, tickets)| tickets).sum();
+            assert_eq!(queue.prefix(queue.slots.len()), total);
+            for ticket in (0..total).step_by(7) {
+                assert_eq!(queue.slots[queue.find(ticket)], Some(ready[naive_winner(&ready, ticket)].0));
+            }
+
+            let winner = ready.remove(naive_winner(&ready, draws.random_range(0..total)));
+            assert_eq!(queue.pop(&processes, 0, 0), Some(winner.0), "round {}", round);
+            assert_eq!(queue.len(), ready.len());
+        }
+
+        assert!(queue.slots.len() < next, "the slots were never compacted");
+    }
+}
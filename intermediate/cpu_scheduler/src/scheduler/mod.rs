@@ -1,5 +1,136 @@
+pub mod cfs;
+pub mod edf;
+pub mod engine;
+pub mod fair_share;
 pub mod fcfs;
 pub mod hrrn;
+pub mod locks;
+pub mod lottery;
 pub mod priority;
+pub mod rate_monotonic;
 pub mod round_robin;
 pub mod sjf;
+pub mod stride;
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{
+    models::{cpu_process::BaseProcess, schedule_result::ScheduleResult},
+    scheduler::locks::LockProtocol,
+};
+
+// How the ready queue is shared between cores, see `engine::CoreQueues`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueueMode {
+    #[default]
+    Global,
+    PerCore,
+    WorkStealing,
+}
+
+impl QueueMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "global" => Some(QueueMode::Global),
+            "per-core" | "per_core" | "percore" => Some(QueueMode::PerCore),
+            "stealing" | "work-stealing" | "work_stealing" => Some(QueueMode::WorkStealing),
+            _ => None,
+        }
+    }
+
+    // Name accepted by `from_name`
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueueMode::Global => "global",
+            QueueMode::PerCore => "per-core",
+            QueueMode::WorkStealing => "stealing",
+        }
+    }
+}
+
+impl fmt::Display for QueueMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueueMode::Global => write!(f, "global queue"),
+            QueueMode::PerCore => write!(f, "per-core queues"),
+            QueueMode::WorkStealing => write!(f, "per-core queues with work stealing"),
+        }
+    }
+}
+
+// Settings shared by every algorithm, algorithm specific ones live on the scheduler
+#[derive(Debug, Clone, Default)]
+pub struct SchedulerConfig {
+    // Dispatch latency charged whenever a core switches to a different process
+    pub context_switch_time: u32,
+    // Number of cores, 0 is treated as a single core
+    pub num_cores: usize,
+    pub queue_mode: QueueMode,
+    // Records every scheduling event into `ScheduleResult::trace`
+    pub trace: bool,
+}
+
+impl SchedulerConfig {
+    pub fn cores(&self) -> usize {
+        self.num_cores.max(1)
+    }
+}
+
+// Why a run could not be completed
+#[derive(Debug, Error)]
+pub enum ScheduleError {
+    // Times are u32, the run needs a later one than the clock can hold
+    #[error("{algorithm} needs time {time} to finish the run, past the last time unit {max}", max = u32::MAX)]
+    ClockOverflow { algorithm: String, time: u64 },
+}
+
+/*
+ *  Shared by every CPU scheduling algorithm.
+ *
+ *  * Algorithm parameters (e.g. the time quantum) live on the scheduler itself,
+ *    so any scheduler can be driven through a `&dyn Scheduler`.
+ *  * The workload is borrowed, the same processes can be fed to every algorithm.
+ */
+pub trait Scheduler: fmt::Debug {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError>;
+
+    fn name(&self) -> String;
+
+    // Same algorithm with exact burst times, only for schedulers that have to estimate them
+    fn oracle(&self) -> Option<Box<dyn Scheduler>> {
+        None
+    }
+
+    // Whether a running process can lose the CPU before its burst is done
+    fn preemptive(&self) -> bool {
+        true
+    }
+
+    // (name, value) of every algorithm setting, recorded on each result for the exports
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    // How holding a lock changes the priority of a process, see `locks::LockProtocol`
+    fn lock_protocol(&self) -> LockProtocol {
+        LockProtocol::None
+    }
+}
+
+// One instance of every algorithm, in menu order
+pub fn all_schedulers(time_quantum: u32) -> Vec<Box<dyn Scheduler>> {
+    vec![
+        Box::new(fcfs::FCFSScheduler),
+        Box::new(sjf::SJFNonPreemptiveScheduler),
+        Box::new(sjf::SJFPreemptiveScheduler),
+        Box::new(round_robin::RoundRobinScheduler::new(time_quantum)),
+        Box::new(priority::PriorityScheduler::new(false, None)),
+        Box::new(priority::PriorityScheduler::new(true, None)),
+        Box::new(hrrn::HRRNScheduler),
+        Box::new(lottery::LotteryScheduler::new(time_quantum, lottery::DEFAULT_SEED)),
+        Box::new(stride::StrideScheduler::new(time_quantum)),
+        Box::new(cfs::CfsScheduler::new(cfs::DEFAULT_TARGET_LATENCY, cfs::DEFAULT_MIN_GRANULARITY)),
+    ]
+}
@@ -1,159 +1,579 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cmp::Reverse, collections::{BTreeMap, BTreeSet, BinaryHeap}, mem, ops::Bound};
 
 use crate::{
-    models::cpu_process::PriorityProcess, 
-    utils::gantt_display::{GanttChart, GanttSegment}
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{
+        ScheduleError, Scheduler, SchedulerConfig,
+        engine::{ReadyQueue, peek_for_core, pop_for_core, simulate},
+        locks::LockProtocol,
+    },
 };
 
-pub trait PrioritySched {
-    fn schedule(&mut self);
-    fn display(&self);
+// (effective priority, ready time, pid, index) wrapped in Reverse, so the smallest value is popped first
+type ReadyHeap = BinaryHeap<Reverse<(u32, u32, usize, usize)>>;
+
+// (time the process reaches its base priority modulo the step, ready time, pid, index), see `PriorityQueue`
+type AgingEntry = (u64, u32, usize, usize);
+
+/*
+ *  Starvation protection for the Priority Scheduler.
+ *
+ *  * A process gains `step` priority levels (its value goes down by `step`) for every
+ *    `interval` ticks it spends waiting in the ready queue, down to 0.
+ *  * Aging is kept once gained, a preempted process continues from its aged priority.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct AgingPolicy {
+    pub interval: u32,
+    pub step: u32,
+}
+
+impl AgingPolicy {
+    // Priority of a process of `priority` once it has waited `waited` ticks in total
+    fn aged(&self, priority: u32, waited: u32) -> u32 {
+        priority.saturating_sub(self.step.saturating_mul(waited / self.interval))
+    }
 }
 
+#[derive(Debug)]
 pub struct PriorityScheduler {
-    pub processes: Vec<PriorityProcess>,
-    pub gantt_chart: GanttChart
+    pub preemptive: bool,
+    pub aging: Option<AgingPolicy>,
+    pub lock_protocol: LockProtocol,
 }
 
 impl PriorityScheduler {
-    pub fn new(processes: Vec<PriorityProcess>) -> Self {
-        PriorityScheduler { 
-            processes, 
-            gantt_chart: GanttChart::new() 
+    pub fn new(preemptive: bool, aging: Option<AgingPolicy>) -> Self {
+        PriorityScheduler { preemptive, aging, lock_protocol: LockProtocol::None }
+    }
+
+    pub fn with_lock_protocol(mut self, lock_protocol: LockProtocol) -> Self {
+        self.lock_protocol = lock_protocol;
+        self
+    }
+}
+
+// Where `PriorityQueue::best` found the process to pick
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Settled,
+    Aging(u32, AgingEntry),
+    Holder(usize),
+}
+
+/*
+ *  Used Revsrse since BinaryHeap uses max heap by default.
+ *  In Priority Scheduling, the smaller the value of priority, that's the being prioritize. Hence, min heap
+ *  The heap is keyed on the effective priority, a lock a process holds may raise it.
+ *
+ *  Aging is applied lazily, a queued process is only touched when it leaves the queue:
+ *
+ *  * `settled` holds the processes whose priority can't change while queued, there is no
+ *    aging or they have aged down to 0.
+ *  * The other aging processes are bucketed by base priority modulo the aging step. In a
+ *    bucket the priority of a process only depends on when it reaches the base priority
+ *    modulo the step, had it waited without a break: the earlier, the lower the value. The
+ *    processes tied with the front of a bucket are the ones reaching their level within the
+ *    same interval, a pick compares the first of every such time by the full key. Buckets
+ *    never tie with each other, their priorities are different modulo the step. A pick costs
+ *    O(log n) per bucket and per time tied with the front, there are at most `step` buckets.
+ *  * `phases` counts the bucketed processes by the time they started aging modulo the
+ *    interval, they gain their levels at those moments of every interval.
+ *  * `holders` are the queued processes in a critical section, a lock may lend them a priority
+ *    at any time. There are at most as many as locks.
+ *  * `waited` is the total time each process has spent in the ready queue up to `since`, the
+ *    time it was queued, that part is already applied to its priority.
+ *  * `popped` processes left the queue since the last `update`, which catches their priority up.
+ */
+#[derive(Debug, Default)]
+struct PriorityQueue {
+    settled: ReadyHeap,
+    buckets: BTreeMap<u32, BTreeSet<AgingEntry>>,
+    phases: BTreeMap<u32, usize>,
+    holders: Vec<usize>,
+    len: usize,
+    preemptive: bool,
+    aging: Option<AgingPolicy>,
+    waited: Vec<u32>,
+    since: Vec<Option<u32>>,
+    popped: Vec<(usize, u32)>,
+}
+
+impl PriorityQueue {
+    // Total time `idx` has waited at `time`, the current wait included
+    fn waited_at(&self, idx: usize, time: u32) -> u32 {
+        self.waited[idx] + self.since[idx].map_or(0, |since| time.saturating_sub(since))
+    }
+
+    // Own priority of `idx` at `time`, aged up to it
+    fn own_priority(&self, idx: usize, processes: &[ProcessState], time: u32) -> u32 {
+        match (self.aging, self.since[idx]) {
+            (Some(aging), Some(_)) => aging.aged(processes[idx].base.priority, self.waited_at(idx, time)),
+            _ => processes[idx].priority,
         }
     }
 
-    pub fn avg_turnaround_time(&self) -> f32 {
-        self.processes
-            .iter()
-            .map(|p| p.turnaround_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
+    fn effective_priority(&self, idx: usize, processes: &[ProcessState], time: u32) -> u32 {
+        let own = self.own_priority(idx, processes, time);
+        processes[idx].lock_priority.map_or(own, |lock_priority| lock_priority.min(own))
     }
 
-    pub fn avg_waiting_time(&self) -> f32 {
-        self.processes
+    // What `idx` is picked by, the earlier ready time and then the lower pid win a tie
+    fn key(&self, idx: usize, processes: &[ProcessState], time: u32) -> (u32, u32, usize) {
+        (self.effective_priority(idx, processes, time), processes[idx].ready_since, processes[idx].base.pid)
+    }
+
+    /*
+     *  Applies the aging from `since` up to `time` to `idx`, recording every level it gains
+     *  at the moment it crossed the aging boundary.
+     */
+    fn catch_up(&mut self, idx: usize, processes: &mut [ProcessState], time: u32) {
+        let (Some(aging), Some(since)) = (self.aging, self.since[idx]) else {
+            return;
+        };
+
+        let elapsed = time.saturating_sub(since);
+        self.since[idx] = Some(time);
+        if elapsed == 0 {
+            return;
+        }
+
+        let steps_before = self.waited[idx] / aging.interval;
+        self.waited[idx] += elapsed;
+        let steps_after = self.waited[idx] / aging.interval;
+
+        let process = &mut processes[idx];
+        for step in (steps_before + 1)..=steps_after {
+            let aged_priority = process.base.priority.saturating_sub(aging.step.saturating_mul(step));
+            if aged_priority == process.priority {
+                break;
+            }
+
+            // The moment the process crossed this aging boundary
+            let changed_at = time - (self.waited[idx] - step * aging.interval);
+            process.priority = aged_priority;
+            process.record_priority(changed_at);
+        }
+    }
+
+    // Best process `core` may run, the settled, aging and holder candidates compared at `time`
+    fn best(&self, processes: &[ProcessState], time: u32, core: usize) -> Option<((u32, u32, usize), Slot)> {
+        let settled = peek_for_core(&self.settled, processes, core, |Reverse((.., idx))| *idx)
+            .map(|&Reverse((priority, ready_since, pid, _))| ((priority, ready_since, pid), Slot::Settled));
+
+        let aging = self.buckets.iter().filter_map(|(&residue, bucket)| {
+            self.bucket_best(bucket, processes, time, core).map(|(key, entry)| (key, Slot::Aging(residue, entry)))
+        });
+
+        let holders = self.holders
             .iter()
-            .map(|p| p.waiting_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
+            .enumerate()
+            .filter(|&(_, &idx)| processes[idx].base.allows_core(core))
+            .map(|(position, &idx)| (self.key(idx, processes, time), Slot::Holder(position)));
+
+        settled.into_iter().chain(aging).chain(holders).min_by_key(|&(key, _)| key)
     }
 
-    /* 
-     *  Helper function to add all newly arrived processes to the ready queue
+    /*
+     *  Best process of a bucket `core` may run at `time`.
      *
-     *  * Used Revsrse since BinaryHeap uses max heap by default.
-     *  * In Priority Scheduling, the smaller the value of priority, that's the being prioritize. Hence, min heap
-    */
-    fn add_arrived_processes_to_heap(
+     *  Entries reaching their level at the same time share a priority and are in key order,
+     *  so only the first of every such time is compared, until the priority goes up.
+     */
+    fn bucket_best(
         &self,
-        heap: &mut BinaryHeap<Reverse<(u32, u32, usize, usize)>>,
-        next_arrival: &mut usize,
-        current_time: u32
-    ) {
-        while *next_arrival < self.processes.len()
-            && self.processes[*next_arrival].base.arrival_time <= current_time
-        {
-            let process = &self.processes[*next_arrival];
-            heap.push(Reverse((
-                process.priority,
-                process.base.arrival_time,
-                process.base.pid,
-                *next_arrival,
-            )));
-
-            *next_arrival += 1;
-        }
-    }
-
-    fn display_table(processes: &[PriorityProcess]) {
-        const HEADER: &str = "|---------|----|----|----------|----|-----|-----|";
-
-        println!("\n{}", HEADER);
-        println!(
-            "|{:^9}|{:^4}|{:^4}|{:^10}|{:^4}|{:^5}|{:^5}|",
-            "Process", "AT", "BT", "Priority", "CT", "TAT", "WT"
-        );
-        println!("{}", HEADER);
-
-        for p in processes {
-            println!(
-                "|{:^9}|{:^4}|{:^4}|{:^10}|{:^4}|{:^5}|{:^5}|",
-                p.base.pid,
-                p.base.arrival_time,
-                p.base.burst_time,
-                p.priority,
-                p.completion_time,
-                p.turnaround_time,
-                p.waiting_time,
-            );
-        }
-
-        println!("{}\n", HEADER);
+        bucket: &BTreeSet<AgingEntry>,
+        processes: &[ProcessState],
+        time: u32,
+        core: usize,
+    ) -> Option<((u32, u32, usize), AgingEntry)> {
+        let mut best: Option<((u32, u32, usize), AgingEntry)> = None;
+        let mut from = Bound::Unbounded;
+
+        while let Some(&entry) = bucket.range((from, Bound::Unbounded)).find(|&&(.., idx)| processes[idx].base.allows_core(core)) {
+            let key = self.key(entry.3, processes, time);
+            if best.is_some_and(|(best_key, _)| key.0 > best_key.0) {
+                break;
+            }
+            if best.is_none_or(|(best_key, _)| key < best_key) {
+                best = Some((key, entry));
+            }
+            from = Bound::Excluded((entry.0, u32::MAX, usize::MAX, usize::MAX));
+        }
+
+        best
+    }
+
+    // Time `idx` started aging, had it waited without a break, modulo the interval
+    fn phase(&self, idx: usize, aging: AgingPolicy) -> u32 {
+        self.since[idx].map_or(0, |since| since - self.waited[idx]) % aging.interval
+    }
+
+    fn remove_phase(&mut self, idx: usize, aging: AgingPolicy) {
+        let phase = self.phase(idx, aging);
+        if let Some(count) = self.phases.get_mut(&phase) {
+            *count -= 1;
+            if *count == 0 {
+                self.phases.remove(&phase);
+            }
+        }
+    }
+
+    // Next time `idx` gains a level, if it still can before the clock runs out
+    fn next_step(&self, idx: usize, aging: AgingPolicy, time: u32) -> Option<u32> {
+        let left = aging.interval - self.waited_at(idx, time) % aging.interval;
+        u32::try_from(time as u64 + left as u64).ok()
     }
 }
 
-impl PrioritySched for PriorityScheduler {
-    fn schedule(&mut self) {
-        self.processes.sort_by_key(|p| p.base.arrival_time);
+impl ReadyQueue for PriorityQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        if self.waited.len() < processes.len() {
+            self.waited.resize(processes.len(), 0);
+            self.since.resize(processes.len(), None);
+        }
+
+        let process = &processes[idx];
+        self.since[idx] = Some(time);
+        self.len += 1;
 
-        let num_process = self.processes.len();
-        let mut completed_process = 0;
-        let mut current_time = 0;
-        let mut next_arrival = 0;
+        if process.lock_priority.is_some() || process.in_critical_section() {
+            self.holders.push(idx);
+            return;
+        }
 
-        let mut heap = BinaryHeap::new();
+        match self.aging {
+            Some(aging) if process.priority > 0 => {
+                // Time it would reach its base priority modulo the step, had it waited without a break
+                let (base, step) = (process.base.priority, aging.step);
+                let started = time - self.waited[idx];
+                let reaches = started as u64 + (base / step) as u64 * aging.interval as u64;
+                *self.phases.entry(started % aging.interval).or_default() += 1;
+                self.buckets
+                    .entry(base % step)
+                    .or_default()
+                    .insert((reaches, process.ready_since, process.base.pid, idx));
+            }
+            _ => self.settled.push(Reverse((process.effective_priority(), process.ready_since, process.base.pid, idx))),
+        }
+    }
 
-        while completed_process < num_process {
-            self.add_arrived_processes_to_heap(
-                &mut heap,
-                &mut next_arrival, 
-                current_time
-            );
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<usize> {
+        let (_, slot) = self.best(processes, time, core)?;
 
-            if let Some(Reverse((
-                _priority,
-                _arrival,
-                _pid,
+        let idx = match slot {
+            Slot::Settled => pop_for_core(&mut self.settled, processes, core, |Reverse((.., idx))| *idx)
+                .map(|Reverse((.., idx))| idx)?,
+            Slot::Aging(residue, entry) => {
+                let bucket = self.buckets.get_mut(&residue)?;
+                bucket.remove(&entry);
+                if bucket.is_empty() {
+                    self.buckets.remove(&residue);
+                }
+                let idx = entry.3;
+                if let Some(aging) = self.aging {
+                    self.remove_phase(idx, aging);
+                }
                 idx
-            ))) = heap.pop() {
-                let process = &mut self.processes[idx];
-
-                let start_time = current_time;
-                let end_time = start_time + process.base.burst_time;
-
-                process.completion_time = end_time;
-                process.turnaround_time = end_time - process.base.arrival_time;
-                process.waiting_time = process.turnaround_time - process.base.burst_time;
-
-                self.gantt_chart.segments.push(GanttSegment {
-                    pid: process.base.pid,
-                    start_time,
-                    end_time
-                });
-
-                current_time = end_time;
-                completed_process += 1;
-
-                self.add_arrived_processes_to_heap(
-                    &mut heap,
-                    &mut next_arrival, 
-                    current_time
-                );
-            } else {
-                if heap.is_empty() && next_arrival < num_process { 
-                    current_time = self.processes[next_arrival].base.arrival_time; 
+            }
+            Slot::Holder(position) => self.holders.swap_remove(position),
+        };
+
+        self.len -= 1;
+        match self.aging {
+            Some(_) => self.popped.push((idx, time)),
+            None => self.since[idx] = None,
+        }
+        Some(idx)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    // In the order `pop` picks them, by key at `time`
+    fn ready(&self, processes: &[ProcessState], time: u32) -> Vec<usize> {
+        let settled = self.settled.iter().map(|&Reverse((.., idx))| idx);
+        let bucketed = self.buckets.values().flatten().map(|&(.., idx)| idx);
+
+        let mut ready: Vec<usize> = settled.chain(bucketed).chain(self.holders.iter().copied()).collect();
+        ready.sort_by_cached_key(|&idx| self.key(idx, processes, time));
+        ready
+    }
+
+    // Effective priority, aging and locks included
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], time: u32) -> Option<DecisionValue> {
+        Some(DecisionValue::new("priority", self.effective_priority(idx, processes, time) as f64))
+    }
+
+    // Only a strictly higher priority takes the CPU, equal priorities keep running
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], time: u32) -> bool {
+        self.preemptive
+            && self.best(processes, time, core)
+                .is_some_and(|((priority, ..), _)| priority < processes[running].effective_priority())
+    }
+
+    /*
+     *  Earliest time after `current_time` a waiting process will gain a priority level.
+     *
+     *  Bucketed processes gain their levels at their phase of every interval, the first phase
+     *  after the current one is the next of them. Holders are checked one by one.
+     */
+    fn next_event(&self, processes: &[ProcessState], current_time: u32) -> Option<u32> {
+        let aging = self.aging.filter(|_| self.preemptive)?;
+
+        let now = current_time % aging.interval;
+        let bucketed = match self.phases.range(now + 1..).next().or_else(|| self.phases.iter().next()) {
+            Some((&phase, _)) if phase > now => u32::try_from(current_time as u64 + (phase - now) as u64).ok(),
+            Some((&phase, _)) => u32::try_from(current_time as u64 + (aging.interval - now + phase) as u64).ok(),
+            None => None,
+        };
+
+        let holders = self.holders
+            .iter()
+            .filter(|&&idx| self.own_priority(idx, processes, current_time) > 0)
+            .filter_map(|&idx| self.next_step(idx, aging, current_time));
+
+        bucketed.into_iter().chain(holders).min()
+    }
+
+    /*
+     *  Catches up the processes that left the queue, and the holders whose own priority a lock
+     *  may be compared to, then settles the processes that aged down to 0.
+     */
+    fn update(&mut self, processes: &mut [ProcessState], time: u32) {
+        let Some(aging) = self.aging else {
+            return;
+        };
+
+        for (idx, popped_at) in mem::take(&mut self.popped) {
+            self.catch_up(idx, processes, popped_at);
+            self.since[idx] = None;
+        }
+
+        for position in 0..self.holders.len() {
+            self.catch_up(self.holders[position], processes, time);
+        }
+
+        // The front of a bucket is the first to reach 0
+        let mut settled = Vec::new();
+        for bucket in self.buckets.values_mut() {
+            while let Some(&(.., idx)) = bucket.first()
+                && aging.aged(processes[idx].base.priority, self.waited[idx] + self.since[idx].map_or(0, |since| time - since)) == 0
+            {
+                bucket.pop_first();
+                settled.push(idx);
+            }
+        }
+        for &idx in &settled {
+            self.remove_phase(idx, aging);
+        }
+        self.buckets.retain(|_, bucket| !bucket.is_empty());
+
+        for idx in settled {
+            self.catch_up(idx, processes, time);
+            let process = &processes[idx];
+            self.settled.push(Reverse((process.effective_priority(), process.ready_since, process.base.pid, idx)));
+        }
+    }
+
+    // A holder's priority is read live, anything else lent a priority becomes one
+    fn reprioritize(&mut self, idx: usize, _processes: &[ProcessState]) {
+        if self.since.get(idx).is_none_or(|since| since.is_none()) || self.holders.contains(&idx) {
+            return;
+        }
+
+        let bucketed = self.buckets.values().flatten().any(|&(.., queued)| queued == idx);
+        if let Some(aging) = self.aging.filter(|_| bucketed) {
+            self.remove_phase(idx, aging);
+        }
+
+        self.settled.retain(|&Reverse((.., queued))| queued != idx);
+        for bucket in self.buckets.values_mut() {
+            bucket.retain(|&(.., queued)| queued != idx);
+        }
+        self.buckets.retain(|_, bucket| !bucket.is_empty());
+        self.holders.push(idx);
+    }
+}
+
+impl Scheduler for PriorityScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        // A step of 0 never ages, an interval of 0 ages every tick
+        let aging = self.aging
+            .filter(|aging| aging.step > 0)
+            .map(|aging| AgingPolicy { interval: aging.interval.max(1), ..aging });
+        let new_queue = || PriorityQueue {
+            preemptive: self.preemptive,
+            aging,
+            ..Default::default()
+        };
+
+        simulate(self, processes, config, new_queue)
+    }
+
+    fn name(&self) -> String {
+        let mode = if self.preemptive { "Preemptive" } else { "Non-Preemptive" };
+
+        let mut settings = Vec::new();
+        if let Some(aging) = self.aging {
+            settings.push(format!("Aging: +{} every {}", aging.step, aging.interval));
+        }
+        match self.lock_protocol {
+            LockProtocol::None => {}
+            LockProtocol::Inheritance => settings.push("Priority Inheritance".to_string()),
+            LockProtocol::Ceiling => settings.push("Priority Ceiling".to_string()),
+        }
+
+        match settings.as_slice() {
+            [] => format!("{} Priority", mode),
+            settings => format!("{} Priority ({})", mode, settings.join(", ")),
+        }
+    }
+
+    fn preemptive(&self) -> bool {
+        self.preemptive
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        match self.aging {
+            Some(aging) => vec![
+                ("aging_interval", aging.interval.to_string()),
+                ("aging_step", aging.step.to_string()),
+            ],
+            None => Vec::new(),
+        }
+    }
+
+    fn lock_protocol(&self) -> LockProtocol {
+        self.lock_protocol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::generator::{ArrivalDistribution, BurstDistribution, PriorityDistribution, WorkloadGenerator};
+
+    // Completion time of every process, aging every waiting process and picking by a scan at every tick
+    fn naive_completions(preemptive: bool, aging: AgingPolicy, processes: &[BaseProcess]) -> Vec<u32> {
+        let mut remaining: Vec<u32> = processes.iter().map(|p| p.burst_time).collect();
+        let mut priority: Vec<u32> = processes.iter().map(|p| p.priority).collect();
+        let mut waited = vec![0; processes.len()];
+        let mut ready_since: Vec<u32> = processes.iter().map(|p| p.arrival_time).collect();
+        let mut completions = vec![0; processes.len()];
+        let mut running: Option<usize> = None;
+        let mut time = 0;
+
+        while remaining.iter().any(|&left| left > 0) {
+            let arrived: Vec<usize> = (0..processes.len())
+                .filter(|&i| remaining[i] > 0 && processes[i].arrival_time <= time)
+                .collect();
+            let best = arrived
+                .iter()
+                .copied()
+                .filter(|&i| running != Some(i))
+                .min_by_key(|&i| (priority[i], ready_since[i], processes[i].pid));
+
+            if let (true, Some(current), Some(best)) = (preemptive, running, best)
+                && priority[best] < priority[current]
+            {
+                ready_since[current] = time;
+                running = Some(best);
+            }
+            if running.is_none() {
+                running = best;
+            }
+
+            for &i in &arrived {
+                if running != Some(i) {
+                    waited[i] += 1;
+                    priority[i] = aging.aged(processes[i].priority, waited[i]);
+                }
+            }
+
+            time += 1;
+            if let Some(current) = running {
+                remaining[current] -= 1;
+                if remaining[current] == 0 {
+                    completions[current] = time;
+                    running = None;
                 }
             }
         }
+
+        completions
     }
 
-    fn display(&self) {
-        let mut process = self.processes.clone();
-        process.sort_by_key(|p| p.base.pid);
-        Self::display_table(&process);
+    fn run(preemptive: bool, aging: AgingPolicy, processes: &[BaseProcess]) -> ScheduleResult {
+        PriorityScheduler::new(preemptive, Some(aging)).schedule(processes, &SchedulerConfig::default()).unwrap()
+    }
 
-        println!("Average Turnaround Time: {:.2}", self.avg_turnaround_time());
-        println!("Average Waiting Time: {:.2}", self.avg_waiting_time());
+    #[test]
+    fn records_every_level_at_its_aging_boundary() {
+        let processes = vec![
+            BaseProcess::new(1, 0, 6).with_priority(0),
+            BaseProcess::new(2, 0, 1).with_priority(4),
+            BaseProcess::new(3, 1, 1).with_priority(3),
+        ];
 
-        self.gantt_chart.display_gantt_chart();
+        let result = run(false, AgingPolicy { interval: 2, step: 1 }, &processes);
+        assert_eq!(result.processes[1].priority_history, vec![(0, 4), (2, 3), (4, 2), (6, 1)]);
+        assert_eq!(result.processes[2].priority_history, vec![(1, 3), (3, 2), (5, 1), (7, 0)]);
+
+        // Both at 1 at time 6, P2 has been ready longer
+        let completions: Vec<u32> = result.processes.iter().map(|p| p.completion_time).collect();
+        assert_eq!(completions, vec![6, 7, 8]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn preempts_when_a_waiting_process_ages_past_the_running_one() {
+        let processes = vec![BaseProcess::new(1, 0, 10).with_priority(5), BaseProcess::new(2, 0, 2).with_priority(8)];
+
+        let result = run(true, AgingPolicy { interval: 2, step: 2 }, &processes);
+        assert_eq!(result.processes[1].priority_history, vec![(0, 8), (2, 6), (4, 4)]);
+        assert_eq!(result.processes[1].completion_time, 6);
+        assert_eq!(result.processes[0].priority_history, vec![(0, 5), (6, 3)]);
+        assert_eq!(result.processes[0].completion_time, 12);
+    }
+
+    #[test]
+    fn breaks_ties_between_aged_processes_by_ready_time() {
+        let processes = vec![
+            BaseProcess::new(1, 7, 6).with_priority(2),
+            BaseProcess::new(2, 5, 1).with_priority(3),
+            BaseProcess::new(3, 7, 4).with_priority(6),
+            BaseProcess::new(4, 9, 1).with_priority(3),
+            BaseProcess::new(5, 3, 5).with_priority(0),
+        ];
+
+        // At 14 P1 and P4 are both at 2, P4 has been ready since 9
+        let result = run(true, AgingPolicy { interval: 4, step: 1 }, &processes);
+        assert_eq!(result.processes[3].completion_time, 15);
+        assert_eq!(result.processes[0].completion_time, 16);
+    }
+
+    #[test]
+    fn matches_a_naive_tick_by_tick_aging_scan() {
+        for seed in 0..300 {
+            let processes = WorkloadGenerator::new(12, seed)
+                .with_arrival(ArrivalDistribution::Uniform { min: 0, max: 3 })
+                .with_burst(BurstDistribution::Uniform { min: 1, max: 6 })
+                .with_priority(PriorityDistribution::Uniform { min: 0, max: 7 })
+                .generate()
+                .unwrap();
+
+            for preemptive in [false, true] {
+                for aging in [AgingPolicy { interval: 1, step: 1 }, AgingPolicy { interval: 4, step: 1 }, AgingPolicy { interval: 3, step: 2 }] {
+                    let result = run(preemptive, aging, &processes);
+                    let completions: Vec<u32> = result.processes.iter().map(|p| p.completion_time).collect();
+                    assert_eq!(
+                        completions,
+                        naive_completions(preemptive, aging, &processes),
+                        "seed {}, preemptive {}, {:?}", seed, preemptive, aging
+                    );
+                }
+            }
+        }
+    }
+}
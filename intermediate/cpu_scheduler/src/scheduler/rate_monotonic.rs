@@ -0,0 +1,69 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, peek_for_core, pop_for_core, simulate}},
+};
+
+/*
+ *  Rate Monotonic, fixed priority real-time scheduling.
+ *
+ *  * Every job inherits the priority of its task: the shorter the period, the higher the priority.
+ *  * The ready queue is ordered by (period, pid, ready time, index), equal periods
+ *    fall back to the lower task id so the priority order stays fixed.
+ *  * Preemptive: a job of a strictly shorter period task takes the CPU.
+ *  * Processes without a period run only when no periodic job is ready.
+ */
+#[derive(Debug, Default)]
+struct RateMonotonicQueue {
+    heap: BinaryHeap<Reverse<(u32, usize, u32, usize)>>,
+}
+
+fn rank_of(process: &ProcessState) -> (u32, usize) {
+    (process.base.period.unwrap_or(u32::MAX), process.base.pid)
+}
+
+impl ReadyQueue for RateMonotonicQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let process = &processes[idx];
+        let (period, pid) = rank_of(process);
+        self.heap.push(Reverse((period, pid, process.ready_since, idx)));
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        pop_for_core(&mut self.heap, processes, core, |Reverse((_, _, _, idx))| *idx)
+            .map(|Reverse((_, _, _, idx))| idx)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        let mut entries: Vec<_> = self.heap.iter().map(|Reverse(entry)| *entry).collect();
+        entries.sort_unstable();
+        entries.into_iter().map(|(_, _, _, idx)| idx).collect()
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        processes[idx].base.period.map(|period| DecisionValue::new("period", period as f64))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], _time: u32) -> bool {
+        peek_for_core(&self.heap, processes, core, |Reverse((_, _, _, idx))| *idx)
+            .is_some_and(|Reverse((period, pid, _, _))| (*period, *pid) < rank_of(&processes[running]))
+    }
+}
+
+#[derive(Debug)]
+pub struct RateMonotonicScheduler;
+
+impl Scheduler for RateMonotonicScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        simulate(self, processes, config, RateMonotonicQueue::default)
+    }
+
+    fn name(&self) -> String {
+        "Rate Monotonic".to_string()
+    }
+}
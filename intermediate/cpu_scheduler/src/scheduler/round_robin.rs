@@ -1,154 +1,297 @@
-use std::{cmp::min, collections::VecDeque};
+use std::{cell::RefCell, collections::BTreeSet, fmt};
 
 use crate::{
-    models::cpu_process::RRProcess,
-    utils::gantt_display::{GanttChart, GanttSegment},
+    models::{cpu_process::{BaseProcess, ProcessState}, schedule_result::ScheduleResult, trace::DecisionValue},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}, fcfs::FifoQueue},
 };
 
-pub trait RRScheduler {
-    fn schedule(&mut self);
-    fn display(&self);
+/*
+ *  How long the time slice of every dispatch is.
+ *
+ *  * Fixed: always the scheduler's time quantum.
+ *  * Mean / Median: the queue runs in rounds, a round gives every process queued when it
+ *    starts one slice of the mean (rounded up) or median remaining burst of those processes.
+ *    Processes queued during a round wait for the next one.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuantumPolicy {
+    #[default]
+    Fixed,
+    Mean,
+    Median,
 }
 
+impl QuantumPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fixed" => Some(QuantumPolicy::Fixed),
+            "mean" | "average" => Some(QuantumPolicy::Mean),
+            "median" => Some(QuantumPolicy::Median),
+            _ => None,
+        }
+    }
+
+    // Quantum of a round over the `remaining` bursts of the queue, at least 1
+    fn quantum(&self, remaining: &RemainingBursts, time_quantum: u32) -> u32 {
+        let quantum = match self {
+            QuantumPolicy::Fixed => None,
+            QuantumPolicy::Mean => remaining.mean(),
+            QuantumPolicy::Median => remaining.median(),
+        };
+
+        quantum.unwrap_or(time_quantum).max(1)
+    }
+}
+
+/*
+ *  Remaining bursts of the queued processes, for the adaptive quanta.
+ *
+ *  * `sum` gives the mean in O(1).
+ *  * `lower` holds the smaller half and `upper` the rest, `lower` has the extra one when
+ *    the count is odd, so the median is at their edges. Entries are (remaining, index),
+ *    which makes every entry unique and any of them removable in O(log n).
+ */
+#[derive(Debug, Default)]
+struct RemainingBursts {
+    sum: u64,
+    lower: BTreeSet<(u32, usize)>,
+    upper: BTreeSet<(u32, usize)>,
+}
+
+impl RemainingBursts {
+    fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    fn insert(&mut self, entry: (u32, usize)) {
+        self.sum += entry.0 as u64;
+        match self.lower.last() {
+            Some(&last) if entry > last => self.upper.insert(entry),
+            _ => self.lower.insert(entry),
+        };
+        self.balance();
+    }
+
+    fn remove(&mut self, entry: (u32, usize)) {
+        if self.lower.remove(&entry) || self.upper.remove(&entry) {
+            self.sum -= entry.0 as u64;
+            self.balance();
+        }
+    }
+
+    fn balance(&mut self) {
+        if self.lower.len() > self.upper.len() + 1 {
+            let moved = self.lower.pop_last();
+            self.upper.extend(moved);
+        } else if self.upper.len() > self.lower.len() {
+            let moved = self.upper.pop_first();
+            self.lower.extend(moved);
+        }
+    }
+
+    // Rounded up, none when nothing is queued
+    fn mean(&self) -> Option<u32> {
+        let len = self.len() as u64;
+        (len > 0).then(|| self.sum.div_ceil(len) as u32)
+    }
+
+    // Mean of the two middle bursts (rounded up) for an even count, none when nothing is queued
+    fn median(&self) -> Option<u32> {
+        let &(lower, _) = self.lower.last()?;
+        match self.upper.first() {
+            Some(&(upper, _)) if self.lower.len() == self.upper.len() => {
+                Some((lower as u64 + upper as u64).div_ceil(2) as u32)
+            }
+            _ => Some(lower),
+        }
+    }
+}
+
+impl fmt::Display for QuantumPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantumPolicy::Fixed => write!(f, "Fixed"),
+            QuantumPolicy::Mean => write!(f, "Mean"),
+            QuantumPolicy::Median => write!(f, "Median"),
+        }
+    }
+}
+
+/*
+ *  FIFO ready queue where every process gives up the CPU after one time quantum.
+ *
+ *  * `slices` holds the quantum each process got at its last dispatch, shared by
+ *    the per-core queues so a stolen process keeps the quantum it was picked with.
+ *  * With an adaptive policy, `remaining` tracks the queued bursts and `round` is the
+ *    quantum of the current round and how many of its processes are still to be picked.
+ *    A process doesn't run while queued, so its remaining burst stays the one it was queued with.
+ */
+struct RoundRobinQueue<'a> {
+    fifo: FifoQueue,
+    time_quantum: u32,
+    policy: QuantumPolicy,
+    slices: &'a RefCell<Vec<u32>>,
+    remaining: RemainingBursts,
+    round: (u32, usize),
+}
+
+impl ReadyQueue for RoundRobinQueue<'_> {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], time: u32) {
+        if self.policy != QuantumPolicy::Fixed {
+            self.remaining.insert((processes[idx].remaining_time, idx));
+        }
+        self.fifo.push(idx, processes, time);
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], time: u32, core: usize) -> Option<usize> {
+        let idx = self.fifo.pop(processes, time, core)?;
+
+        // Only an adaptive policy tracks the queue, a fixed one stays O(1) per dispatch
+        let quantum = match self.policy {
+            QuantumPolicy::Fixed => self.time_quantum,
+            policy => {
+                // The picked process is still counted in the round it starts
+                let (quantum, left) = &mut self.round;
+                if *left == 0 {
+                    *quantum = policy.quantum(&self.remaining, self.time_quantum);
+                    *left = self.remaining.len();
+                }
+                *left -= 1;
+
+                self.remaining.remove((processes[idx].remaining_time, idx));
+                *quantum
+            }
+        };
+
+        self.slices.borrow_mut()[idx] = quantum;
+        Some(idx)
+    }
+
+    fn len(&self) -> usize {
+        self.fifo.len()
+    }
+
+    fn ready(&self, processes: &[ProcessState], time: u32) -> Vec<usize> {
+        self.fifo.ready(processes, time)
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], time: u32) -> Option<DecisionValue> {
+        self.fifo.decision_value(idx, processes, time)
+    }
+
+    fn time_slice(&self, running: usize, _processes: &[ProcessState]) -> Option<u32> {
+        match self.policy {
+            QuantumPolicy::Fixed => Some(self.time_quantum),
+            QuantumPolicy::Mean | QuantumPolicy::Median => self.slices.borrow().get(running).copied(),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct RoundRobinScheduler {
-    pub processes: Vec<RRProcess>,
-    pub gantt_chart: GanttChart,
     pub time_quantum: u32,
+    pub policy: QuantumPolicy,
 }
 
 impl RoundRobinScheduler {
-    pub fn new(time_quantum: u32, processes: Vec<RRProcess>) -> Self {
+    pub fn new(time_quantum: u32) -> Self {
         RoundRobinScheduler {
-            processes,
-            gantt_chart: GanttChart::new(),
             time_quantum,
+            policy: QuantumPolicy::Fixed,
         }
     }
 
-    pub fn avg_turnaround_time(&self) -> f32 {
-        self.processes
-            .iter()
-            .map(|t| t.turnaround_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
+    // The time quantum is only used by the fixed policy
+    pub fn with_policy(mut self, policy: QuantumPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl Scheduler for RoundRobinScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let slices = RefCell::new(vec![self.time_quantum.max(1); processes.len()]);
+        let new_queue = || RoundRobinQueue {
+            fifo: FifoQueue::default(),
+            time_quantum: self.time_quantum.max(1),
+            policy: self.policy,
+            slices: &slices,
+            remaining: RemainingBursts::default(),
+            round: (0, 0),
+        };
+
+        simulate(self, processes, config, new_queue)
     }
 
-    pub fn avg_waiting_time(&self) -> f32 {
-        self.processes
-            .iter()
-            .map(|t| t.waiting_time as f32)
-            .sum::<f32>() / self.processes.len() as f32
-    }
-
-    // Helper function to add all newly arrived processes to the ready queue
-    fn add_arrived_processes(
-        &self,
-        ready_queue: &mut VecDeque<usize>,
-        next_arrival_index: &mut usize,
-        current_time: u32,
-    ) {
-        while *next_arrival_index < self.processes.len()
-            && self.processes[*next_arrival_index].base.arrival_time <= current_time
-        {
-            ready_queue.push_back(*next_arrival_index);
-            *next_arrival_index += 1;
+    fn name(&self) -> String {
+        match self.policy {
+            QuantumPolicy::Fixed => format!("Round Robin (Time Quantum: {})", self.time_quantum),
+            policy => format!("Round Robin (Adaptive Quantum: {})", policy),
         }
     }
 
-    // Helper for Table display
-    fn display_table(processes: &[RRProcess]) {
-        const HEADER: &str = "|---------|----|----|----|----|-----|";
-
-        println!("{}", HEADER);
-        println!(
-            "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|",
-            "Process", "AT", "BT", "CT", "TAT", "WT",
-        );
-        println!("{}", HEADER);
-
-        for p in processes {
-            println!(
-                "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|",
-                p.base.pid,
-                p.base.arrival_time,
-                p.base.burst_time,
-                p.completion_time,
-                p.turnaround_time,
-                p.waiting_time,
-            );
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        match self.policy {
+            QuantumPolicy::Fixed => vec![("time_quantum", self.time_quantum.to_string())],
+            policy => vec![("quantum_policy", policy.to_string().to_lowercase())],
         }
-
-        println!("{}", HEADER);
     }
 }
 
-impl RRScheduler for RoundRobinScheduler {
-    fn schedule(&mut self) {
-        self.processes.sort_by_key(|p| p.base.arrival_time);
-
-        let num_processes = self.processes.len();
-        let mut ready_queue: VecDeque<usize> = VecDeque::new();
-        let mut current_time = 0;
-        let mut completed_process = 0;
-        let mut next_arrival_index = 0;
-
-        self.add_arrived_processes(
-            &mut ready_queue,
-            &mut next_arrival_index, 
-            current_time
-        );
-
-        while completed_process < num_processes {
-            if ready_queue.is_empty() {
-                if next_arrival_index < num_processes {
-                    current_time = self.processes[next_arrival_index].base.arrival_time;
-                    ready_queue.push_back(next_arrival_index);
-                    next_arrival_index += 1;
-                }
-                continue;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            if let Some(process_index) = ready_queue.pop_front() {
-                // Calculate execution time for this time quantum
-                let execution_time = min(
-                    self.time_quantum,
-                    self.processes[process_index].remaining_time,
-                );
-
-                let start_time = current_time;
-
-                self.processes[process_index].remaining_time -= execution_time;
-                current_time += execution_time;
-
-                self.gantt_chart.segments.push(GanttSegment {
-                    pid: self.processes[process_index].base.pid,
-                    start_time,
-                    end_time: current_time,
-                });
-
-                self.add_arrived_processes(
-                    &mut ready_queue,
-                    &mut next_arrival_index, 
-                    current_time
-                );
-
-                if self.processes[process_index].remaining_time == 0 {
-                    let process = &mut self.processes[process_index];
-                    process.completion_time = current_time;
-                    process.turnaround_time = process.completion_time - process.base.arrival_time;
-                    process.waiting_time = process.turnaround_time - process.base.burst_time;
-                    completed_process += 1;
-                } else {
-                    ready_queue.push_back(process_index);
-                }
+    fn completions(policy: QuantumPolicy, bursts: &[u32]) -> Vec<u32> {
+        let processes: Vec<BaseProcess> = bursts
+            .iter()
+            .enumerate()
+            .map(|(i, &burst)| BaseProcess::new(i + 1, 0, burst))
+            .collect();
+
+        RoundRobinScheduler::new(0)
+            .with_policy(policy)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap()
+            .processes
+            .iter()
+            .map(|p| p.completion_time)
+            .collect()
+    }
+
+    #[test]
+    fn tracks_the_mean_and_median_through_removals() {
+        let mut remaining = RemainingBursts::default();
+        let mut naive: Vec<(u32, usize)> = Vec::new();
+
+        for idx in 0..300 {
+            let entry = ((idx * 37 % 23) as u32, idx);
+            remaining.insert(entry);
+            naive.push(entry);
+
+            if idx % 3 == 2 {
+                let removed = naive.remove(idx * 7 % naive.len());
+                remaining.remove(removed);
             }
+
+            let mut values: Vec<u64> = naive.iter().map(|&(value, _)| value as u64).collect();
+            values.sort_unstable();
+            let middle = values.len() / 2;
+            let median = match values.len() % 2 {
+                0 => (values[middle - 1] + values[middle]).div_ceil(2),
+                _ => values[middle],
+            };
+
+            assert_eq!(remaining.median(), Some(median as u32), "after {}", idx);
+            assert_eq!(remaining.mean(), Some(values.iter().sum::<u64>().div_ceil(values.len() as u64) as u32));
         }
     }
 
-    fn display(&self) {
-        Self::display_table(&self.processes);
-        println!("\nAverage Turnaround Time: {:.2}", self.avg_turnaround_time());
-        println!("\nAverage Waiting Time: {:.2}", self.avg_waiting_time());
-
-        self.gantt_chart.display_gantt_chart();
+    #[test]
+    fn keeps_the_quantum_for_the_whole_round() {
+        // Mean 7 for the round, the 10s are left with 3 each for the next one
+        assert_eq!(completions(QuantumPolicy::Mean, &[1, 10, 10]), vec![1, 18, 21]);
+        // Median 6, then 4 over the two that are left
+        assert_eq!(completions(QuantumPolicy::Median, &[1, 2, 10, 10]), vec![1, 3, 19, 23]);
     }
 }
@@ -1,122 +1,328 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cell::RefCell, cmp::{Ordering, Reverse}, collections::BinaryHeap};
 
 use crate::{
-    models::cpu_process::SJFProcess,
-    utils::gantt_display::{GanttChart, GanttSegment},
+    models::{
+        cpu_process::{BaseProcess, Burst, ProcessState},
+        schedule_result::ScheduleResult,
+        trace::DecisionValue,
+    },
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, peek_for_core, pop_for_core, simulate}},
 };
 
-pub trait SJFScheduler {
-    fn schedule(&mut self);
-    fn display(&self);
+// τ(0), the guess for a first burst with no history
+pub const DEFAULT_INITIAL_ESTIMATE: f32 = 10.0;
+
+/*
+ *  Both SJF variants order the ready queue by (burst, ready time, index).
+ *
+ *  * Non-preemptive: the shortest job keeps the CPU until its burst completes.
+ *  * Preemptive (SRTF): a process that becomes ready with a strictly shorter
+ *    remaining time takes the CPU from the running one.
+ *  * Equal bursts fall back to FCFS, the process that became ready first runs first.
+ *  * With I/O, the burst is the current CPU burst, not the whole process.
+ */
+#[derive(Debug, Default)]
+struct ShortestJobQueue {
+    heap: BinaryHeap<Reverse<(u32, u32, usize)>>,
+    preemptive: bool,
+}
+
+impl ReadyQueue for ShortestJobQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let process = &processes[idx];
+        self.heap.push(Reverse((process.remaining_time, process.ready_since, idx)));
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        pop_for_core(&mut self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+            .map(|Reverse((_, _, idx))| idx)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        let mut entries: Vec<_> = self.heap.iter().map(|Reverse(entry)| *entry).collect();
+        entries.sort_unstable();
+        entries.into_iter().map(|(_, _, idx)| idx).collect()
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        Some(DecisionValue::new("remaining", processes[idx].remaining_time as f64))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], _time: u32) -> bool {
+        self.preemptive
+            && peek_for_core(&self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+                .is_some_and(|Reverse((remaining, _, _))| *remaining < processes[running].remaining_time)
+    }
+}
+
+#[derive(Debug)]
+pub struct SJFNonPreemptiveScheduler;
+
+#[derive(Debug)]
+pub struct SJFPreemptiveScheduler;
+
+impl Scheduler for SJFNonPreemptiveScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        simulate(self, processes, config, ShortestJobQueue::default)
+    }
+
+    fn name(&self) -> String {
+        "Shortest Job First (Non-Preemptive)".to_string()
+    }
+
+    fn preemptive(&self) -> bool {
+        false
+    }
+}
+
+impl Scheduler for SJFPreemptiveScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let new_queue = || ShortestJobQueue {
+            preemptive: true,
+            ..Default::default()
+        };
+
+        simulate(self, processes, config, new_queue)
+    }
+
+    fn name(&self) -> String {
+        "Shortest Job First (Preemptive / SRTF)".to_string()
+    }
+}
+
+/*
+ *  Exponential averaging of CPU bursts: τ(n+1) = α·t(n) + (1 − α)·τ(n).
+ *
+ *  * `alpha` (0 to 1) is the weight of the last burst, 0 ignores it and 1 only trusts it.
+ *  * `initial` is τ(0), the history of a process is averaged in before it arrives.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurstPrediction {
+    pub alpha: f32,
+    pub initial: f32,
+}
+
+impl BurstPrediction {
+    pub fn new(alpha: f32, initial: f32) -> Self {
+        BurstPrediction { alpha, initial }
+    }
+
+    pub fn next(&self, estimate: f32, actual: u32) -> f32 {
+        self.alpha * actual as f32 + (1.0 - self.alpha) * estimate
+    }
+
+    // Estimate of the first burst of a process after its earlier runs
+    pub fn seed(&self, history: &[u32]) -> f32 {
+        history.iter().fold(self.initial, |estimate, &actual| self.next(estimate, actual))
+    }
+}
+
+// Predicted remaining time as a heap key, ordered like `f32::total_cmp`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Predicted(f32);
+
+impl Eq for Predicted {}
+
+impl PartialOrd for Predicted {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Predicted {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Estimates of every process, shared by the per-core queues so they follow migrations
+#[derive(Debug)]
+struct Predictions {
+    // Estimate of the current (or next) CPU burst
+    estimate: Vec<f32>,
+    // (predicted, actual) of every completed CPU burst
+    history: Vec<Vec<(f32, u32)>>,
 }
 
-pub struct SJFPreemptiveScheduler {
-    pub processes: Vec<SJFProcess>,
-    pub gantt_chart: GanttChart,
-    pub avg_tat: f32,
-    pub avg_wt: f32,
+/*
+ *  SJF / SRTF ordered by the predicted burst instead of the real one.
+ *
+ *  * The predicted remaining time is the estimate minus the time the process already
+ *    ran in its current burst, never below 0.
+ *  * Ties fall back to FCFS like the exact version.
+ *  * The estimate is only updated once a burst completes and its length is known,
+ *    so the key of a queued process never changes and a heap keeps the order.
+ */
+struct PredictedJobQueue<'a> {
+    heap: BinaryHeap<Reverse<(Predicted, u32, usize)>>,
+    preemptive: bool,
+    prediction: BurstPrediction,
+    predictions: &'a RefCell<Predictions>,
 }
 
-impl SJFPreemptiveScheduler {
-    pub fn new(processes: Vec<SJFProcess>) -> Self {
-        Self {
-            processes,
-            avg_tat: 0.0,
-            avg_wt: 0.0,
-            gantt_chart: GanttChart::new(),
+impl PredictedJobQueue<'_> {
+    fn predicted_remaining(&self, idx: usize, processes: &[ProcessState]) -> f32 {
+        let process = &processes[idx];
+        let burst = match process.base.bursts.get(process.burst_index) {
+            Some(Burst::Cpu(time)) => *time,
+            _ => process.remaining_time,
+        };
+        let elapsed = burst.saturating_sub(process.remaining_time);
+
+        (self.predictions.borrow().estimate[idx] - elapsed as f32).max(0.0)
+    }
+}
+
+impl ReadyQueue for PredictedJobQueue<'_> {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let predicted = Predicted(self.predicted_remaining(idx, processes));
+        self.heap.push(Reverse((predicted, processes[idx].ready_since, idx)));
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        pop_for_core(&mut self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+            .map(|Reverse((_, _, idx))| idx)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        let mut entries: Vec<_> = self.heap.iter().map(|Reverse(entry)| *entry).collect();
+        entries.sort_unstable();
+        entries.into_iter().map(|(_, _, idx)| idx).collect()
+    }
+
+    fn decision_value(&self, idx: usize, processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        Some(DecisionValue::new("predicted", self.predicted_remaining(idx, processes) as f64))
+    }
+
+    fn should_preempt(&self, running: usize, core: usize, processes: &[ProcessState], _time: u32) -> bool {
+        self.preemptive
+            && peek_for_core(&self.heap, processes, core, |Reverse((_, _, idx))| *idx)
+                .is_some_and(|Reverse((Predicted(predicted), _, _))| *predicted < self.predicted_remaining(running, processes))
+    }
+
+    // A burst that ran to completion is the next t(n)
+    fn stop(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        let process = &processes[idx];
+        let Some(Burst::Cpu(actual)) = process.base.bursts.get(process.burst_index).copied() else {
+            return;
+        };
+        if process.remaining_time > 0 {
+            return;
         }
+
+        let mut predictions = self.predictions.borrow_mut();
+        let estimate = predictions.estimate[idx];
+        predictions.history[idx].push((estimate, actual));
+        predictions.estimate[idx] = self.prediction.next(estimate, actual);
     }
 }
 
-impl SJFScheduler for SJFPreemptiveScheduler {
-    fn schedule(&mut self) {
-        self.processes.sort_by_key(|p| p.base.arrival_time);
+#[derive(Debug)]
+pub struct PredictiveSJFScheduler {
+    pub preemptive: bool,
+    pub prediction: BurstPrediction,
+}
 
-        let num_of_processes = self.processes.len();
-        let mut time = 0;
-        let mut completed_process = 0;
+impl PredictiveSJFScheduler {
+    pub fn new(preemptive: bool, prediction: BurstPrediction) -> Self {
+        PredictiveSJFScheduler { preemptive, prediction }
+    }
+}
 
-        let mut heap = BinaryHeap::new();
-        let mut next_proc = 0;
+impl Scheduler for PredictiveSJFScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let predictions = RefCell::new(Predictions {
+            estimate: processes.iter().map(|p| self.prediction.seed(&p.history)).collect(),
+            history: vec![Vec::new(); processes.len()],
+        });
+        let new_queue = || PredictedJobQueue {
+            heap: BinaryHeap::new(),
+            preemptive: self.preemptive,
+            prediction: self.prediction,
+            predictions: &predictions,
+        };
 
-        while completed_process < num_of_processes {
-            while next_proc < num_of_processes
-                && self.processes[next_proc].base.arrival_time <= time
-            {
-                heap.push(Reverse((
-                    self.processes[next_proc].remaining_time,
-                    self.processes[next_proc].base.arrival_time,
-                    next_proc,
-                )));
-                next_proc += 1;
-            }
+        let mut result = simulate(self, processes, config, new_queue)?;
 
-            if let Some(Reverse((_, _, idx))) = heap.pop() {
-                let process = &mut self.processes[idx];
-
-                // Execute for one unit of time
-                process.remaining_time -= 1;
-                self.gantt_chart.segments.push(GanttSegment {
-                    pid: process.base.pid,
-                    start_time: time,
-                    end_time: time + 1,
-                });
-
-                time += 1;
-
-                if process.remaining_time == 0 {
-                    process.completion_time = time;
-                    process.turnaround_time = time - process.base.arrival_time;
-                    process.waiting_time = process.turnaround_time - process.base.burst_time;
-                    completed_process += 1;
-                } else {
-                    // Push it back into heap with updated remaining time
-                    heap.push(Reverse((
-                        process.remaining_time,
-                        process.base.arrival_time,
-                        idx,
-                    )));
-                }
-            } else {
-                // No process is ready; jump to the next arrival
-                if next_proc < num_of_processes {
-                    time = self.processes[next_proc].base.arrival_time;
-                }
+        for (idx, history) in predictions.into_inner().history.into_iter().enumerate() {
+            if let Some(p) = result.process_mut(processes[idx].pid) {
+                p.burst_predictions = history;
             }
         }
 
-        let total_tat: u32 = self.processes.iter().map(|p| p.turnaround_time).sum();
-        let total_wt: u32 = self.processes.iter().map(|p| p.waiting_time).sum();
-
-        self.avg_tat = total_tat as f32 / num_of_processes as f32;
-        self.avg_wt = total_wt as f32 / num_of_processes as f32;
-    }
-
-    fn display(&self) {
-        println!("|---------|----|----|----|----|-----|----|");
-        println!(
-            "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|",
-            "Process", "AT", "BT", "CT", "TAT", "WT", ""
-        );
-        println!("|---------|----|----|----|----|-----|----|");
-
-        for p in &self.processes {
-            println!(
-                "|{:^9}|{:^4}|{:^4}|{:^4}|{:^4}|{:^5}|{:^4}|",
-                p.base.pid,
-                p.base.arrival_time,
-                p.base.burst_time,
-                p.completion_time,
-                p.turnaround_time,
-                p.waiting_time,
-                "",
-            )
+        Ok(result)
+    }
+
+    fn name(&self) -> String {
+        let mode = if self.preemptive { "Preemptive / SRTF" } else { "Non-Preemptive" };
+        format!(
+            "Shortest Job First ({}, Predicted: Alpha {}, Initial {})",
+            mode, self.prediction.alpha, self.prediction.initial
+        )
+    }
+
+    fn oracle(&self) -> Option<Box<dyn Scheduler>> {
+        match self.preemptive {
+            true => Some(Box::new(SJFPreemptiveScheduler)),
+            false => Some(Box::new(SJFNonPreemptiveScheduler)),
         }
+    }
+
+    fn preemptive(&self) -> bool {
+        self.preemptive
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("alpha", self.prediction.alpha.to_string()),
+            ("initial_estimate", self.prediction.initial.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicts_each_burst_from_the_exponential_average() {
+        let process = BaseProcess::new(1, 0, 0)
+            .with_bursts(vec![Burst::Cpu(4), Burst::Io(2), Burst::Cpu(8), Burst::Io(2), Burst::Cpu(2)]);
+        let result = PredictiveSJFScheduler::new(false, BurstPrediction::new(0.5, 10.0))
+            .schedule(&[process], &SchedulerConfig::default())
+            .unwrap();
+
+        // 10, then (4 + 10) / 2, then (8 + 7) / 2
+        assert_eq!(result.processes[0].burst_predictions, vec![(10.0, 4), (7.0, 8), (7.5, 2)]);
+    }
+
+    #[test]
+    fn runs_the_shortest_predicted_burst_first() {
+        // P1 is actually shorter, but its history says it runs long
+        let processes = vec![
+            BaseProcess::new(1, 0, 2).with_history(vec![20, 20]),
+            BaseProcess::new(2, 0, 9).with_history(vec![1]),
+        ];
+        let prediction = BurstPrediction::new(0.5, 10.0);
+        assert_eq!(prediction.seed(&processes[0].history), 17.5);
+        assert_eq!(prediction.seed(&processes[1].history), 5.5);
 
-        println!("|---------|----|----|----|----|-----|----|");
-        println!("\nAverage Turnaround Time: {:.2}", self.avg_tat);
-        println!("\nAverage Waiting Time: {:.2}", self.avg_wt);
+        let result = PredictiveSJFScheduler::new(false, prediction)
+            .schedule(&processes, &SchedulerConfig::default())
+            .unwrap();
+        let completions: Vec<u32> = result.processes.iter().map(|p| p.completion_time).collect();
+        assert_eq!(completions, vec![11, 9]);
 
-        self.gantt_chart.display_gantt_chart();
+        let oracle = SJFNonPreemptiveScheduler.schedule(&processes, &SchedulerConfig::default()).unwrap();
+        let completions: Vec<u32> = oracle.processes.iter().map(|p| p.completion_time).collect();
+        assert_eq!(completions, vec![2, 11]);
     }
 }
@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    models::{
+        cpu_process::{BaseProcess, ProcessState},
+        schedule_result::{ScheduleResult, ShareBasis},
+        trace::DecisionValue,
+    },
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, engine::{ReadyQueue, simulate}},
+};
+
+// Pass advanced per time unit by a process holding a single ticket
+const STRIDE1: u64 = 1 << 20;
+
+/*
+ *  Deterministic counterpart of lottery scheduling.
+ *
+ *  * Every process has a stride of STRIDE1 / tickets and a pass, the ready process
+ *    with the lowest pass runs for one time quantum.
+ *  * The pass grows by the stride for every time unit the process ran, so over
+ *    time each process gets CPU in proportion to its tickets.
+ *  * A process arriving or coming back from I/O starts at the pass of the last
+ *    dispatched process, it can't claim the time it wasn't ready.
+ *  * Equal passes run in the order the processes became ready.
+ */
+#[derive(Debug, Default)]
+struct StrideQueue {
+    // (pass, insertion order, index)
+    ready: BTreeSet<(u64, u64, usize)>,
+    inserted: u64,
+    pass: Vec<u64>,
+    global_pass: u64,
+    time_quantum: u32,
+}
+
+impl ReadyQueue for StrideQueue {
+    fn push(&mut self, idx: usize, processes: &[ProcessState], _time: u32) {
+        if self.pass.len() < processes.len() {
+            self.pass.resize(processes.len(), 0);
+        }
+
+        let process = &processes[idx];
+        let stride = STRIDE1 / process.base.tickets.max(1) as u64;
+        self.pass[idx] += stride * process.last_run as u64;
+
+        let joining = process.start_time.is_none() || process.blocked_until.is_some();
+        if joining {
+            self.pass[idx] = self.pass[idx].max(self.global_pass);
+        }
+
+        self.inserted += 1;
+        self.ready.insert((self.pass[idx], self.inserted, idx));
+    }
+
+    fn pop(&mut self, processes: &[ProcessState], _time: u32, core: usize) -> Option<usize> {
+        let entry = *self.ready
+            .iter()
+            .find(|&&(_, _, idx)| processes[idx].base.allows_core(core))?;
+
+        self.ready.remove(&entry);
+        self.global_pass = self.global_pass.max(entry.0);
+        Some(entry.2)
+    }
+
+    fn len(&self) -> usize {
+        self.ready.len()
+    }
+
+    fn ready(&self, _processes: &[ProcessState], _time: u32) -> Vec<usize> {
+        self.ready.iter().map(|&(_, _, idx)| idx).collect()
+    }
+
+    // Shown in time units per ticket, the pass a running process gained is added when it stops
+    fn decision_value(&self, idx: usize, _processes: &[ProcessState], _time: u32) -> Option<DecisionValue> {
+        let pass = self.pass.get(idx)?;
+        Some(DecisionValue::new("pass", *pass as f64 / STRIDE1 as f64))
+    }
+
+    fn time_slice(&self, _running: usize, _processes: &[ProcessState]) -> Option<u32> {
+        Some(self.time_quantum)
+    }
+}
+
+#[derive(Debug)]
+pub struct StrideScheduler {
+    pub time_quantum: u32,
+}
+
+impl StrideScheduler {
+    pub fn new(time_quantum: u32) -> Self {
+        StrideScheduler { time_quantum }
+    }
+}
+
+impl Scheduler for StrideScheduler {
+    fn schedule(&self, processes: &[BaseProcess], config: &SchedulerConfig) -> Result<ScheduleResult, ScheduleError> {
+        let new_queue = || StrideQueue {
+            time_quantum: self.time_quantum.max(1),
+            ..Default::default()
+        };
+
+        let mut result = simulate(self, processes, config, new_queue)?;
+        result.share_basis = Some(ShareBasis::Tickets);
+        Ok(result)
+    }
+
+    fn name(&self) -> String {
+        format!("Stride (Time Quantum: {})", self.time_quantum)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("time_quantum", self.time_quantum.to_string())]
+    }
+}
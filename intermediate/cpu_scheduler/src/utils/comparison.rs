@@ -0,0 +1,212 @@
+use crate::{
+    models::{cpu_process::BaseProcess, metrics::Metrics, schedule_result::ScheduleResult},
+    scheduler::{ScheduleError, Scheduler, SchedulerConfig, round_robin::{QuantumPolicy, RoundRobinScheduler}},
+    utils::export::escape,
+};
+
+struct Metric {
+    name: &'static str,
+    value: fn(&Metrics) -> f64,
+    lower_is_better: bool,
+    precision: usize,
+}
+
+const METRICS: [Metric; 9] = [
+    Metric { name: "Avg TAT", value: |m| m.avg_turnaround_time, lower_is_better: true, precision: 2 },
+    Metric { name: "Avg WT", value: |m| m.avg_waiting_time, lower_is_better: true, precision: 2 },
+    Metric { name: "Avg RT", value: |m| m.avg_response_time, lower_is_better: true, precision: 2 },
+    Metric { name: "Avg NTAT", value: |m| m.avg_normalized_turnaround, lower_is_better: true, precision: 2 },
+    Metric { name: "Max WT", value: |m| m.max_waiting_time as f64, lower_is_better: true, precision: 0 },
+    Metric { name: "Throughput", value: |m| m.throughput, lower_is_better: false, precision: 2 },
+    Metric { name: "CS", value: |m| m.context_switches as f64, lower_is_better: true, precision: 0 },
+    Metric { name: "CPU %", value: |m| m.cpu_utilization, lower_is_better: false, precision: 2 },
+    Metric { name: "Fairness", value: |m| m.fairness_index, lower_is_better: false, precision: 3 },
+];
+
+// Metrics a time quantum changes the most, see `display_quantum_sweep`
+const SWEEP_METRICS: [Metric; 4] = [
+    Metric { name: "Avg TAT", value: |m| m.avg_turnaround_time, lower_is_better: true, precision: 2 },
+    Metric { name: "Avg WT", value: |m| m.avg_waiting_time, lower_is_better: true, precision: 2 },
+    Metric { name: "Avg RT", value: |m| m.avg_response_time, lower_is_better: true, precision: 2 },
+    Metric { name: "CS", value: |m| m.context_switches as f64, lower_is_better: true, precision: 0 },
+];
+
+// Runs the same workload through every scheduler, stops at the first one that fails
+pub fn run_comparison(
+    processes: &[BaseProcess],
+    schedulers: &[Box<dyn Scheduler>],
+    config: &SchedulerConfig
+) -> Result<Vec<ScheduleResult>, ScheduleError> {
+    schedulers
+        .iter()
+        .map(|scheduler| scheduler.schedule(processes, config))
+        .collect()
+}
+
+fn best_value(metrics: &[Metrics], metric: &Metric) -> f64 {
+    let values = metrics.iter().map(|m| (metric.value)(m));
+
+    if metric.lower_is_better {
+        values.fold(f64::MAX, f64::min)
+    } else {
+        values.fold(f64::MIN, f64::max)
+    }
+}
+
+/*
+ *  Prints one row per algorithm and one column per metric.
+ *
+ *  * The best value of each column is marked with `*` (ties are all marked).
+ *  * NTAT is the normalized turnaround (TAT / BT), Max WT the longest wait of any process.
+ *  * Throughput is in processes per time unit, CS is the context-switch count.
+ *  * Fairness is Jain's index of the CPU share by tickets for lottery and stride and by nice
+ *    weight otherwise, see `ScheduleResult::fairness_basis`.
+ */
+pub fn display_comparison(results: &[ScheduleResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    const HEADER: &str = "|----------------------------------------|------------|------------|------------|------------|------------|------------|------------|------------|------------|";
+
+    let metrics: Vec<Metrics> = results.iter().map(ScheduleResult::metrics).collect();
+    let best: Vec<f64> = METRICS.iter().map(|m| best_value(&metrics, m)).collect();
+
+    println!("\n{:=^159}", " ALGORITHM COMPARISON ");
+    println!("\n{}", HEADER);
+    print!("|{:^40}|", "Algorithm");
+    for metric in &METRICS {
+        print!("{:^12}|", metric.name);
+    }
+    println!();
+    println!("{}", HEADER);
+
+    for (result, result_metrics) in results.iter().zip(&metrics) {
+        print!("|{:^40}|", result.algorithm_name);
+        for (metric, best) in METRICS.iter().zip(&best) {
+            let value = (metric.value)(result_metrics);
+            let marker = if (value - best).abs() < f64::EPSILON { "*" } else { " " };
+            print!("{:^12}|", format!("{:.*}{}", metric.precision, value, marker));
+        }
+        println!();
+    }
+
+    println!("{}", HEADER);
+
+    println!("\nBest per metric (*):");
+    for (metric, best) in METRICS.iter().zip(&best) {
+        let winners: Vec<&str> = results
+            .iter()
+            .zip(&metrics)
+            .filter(|(_, m)| ((metric.value)(m) - best).abs() < f64::EPSILON)
+            .map(|(r, _)| r.algorithm_name.as_str())
+            .collect();
+
+        println!("  {:<12}{}", metric.name, winners.join(", "));
+    }
+    println!();
+}
+
+// Same table as `display_comparison` as HTML, the best value of each column in bold
+pub fn comparison_html(results: &[ScheduleResult]) -> String {
+    let metrics: Vec<Metrics> = results.iter().map(ScheduleResult::metrics).collect();
+    let best: Vec<f64> = METRICS.iter().map(|m| best_value(&metrics, m)).collect();
+
+    let mut html = String::from("<h2>Algorithm Comparison</h2>\n<table>\n<tr><th>Algorithm</th>");
+    for metric in &METRICS {
+        html.push_str(&format!("<th>{}</th>", metric.name));
+    }
+    html.push_str("</tr>\n");
+
+    for (result, result_metrics) in results.iter().zip(&metrics) {
+        html.push_str(&format!("<tr><td>{}</td>", escape(&result.algorithm_name)));
+        for (metric, best) in METRICS.iter().zip(&best) {
+            let value = (metric.value)(result_metrics);
+            let cell = format!("{:.*}", metric.precision, value);
+            if (value - best).abs() < f64::EPSILON {
+                html.push_str(&format!("<td><b>{}</b></td>", cell));
+            } else {
+                html.push_str(&format!("<td>{}</td>", cell));
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n");
+    html
+}
+
+// Round Robin with every quantum of `quanta`, then both adaptive quanta, labelled by quantum
+pub fn run_quantum_sweep(
+    processes: &[BaseProcess],
+    quanta: &[u32],
+    config: &SchedulerConfig
+) -> Result<Vec<(String, ScheduleResult)>, ScheduleError> {
+    let mut labels: Vec<String> = quanta.iter().map(|q| q.to_string()).collect();
+    let mut schedulers: Vec<Box<dyn Scheduler>> = quanta
+        .iter()
+        .map(|&q| Box::new(RoundRobinScheduler::new(q)) as Box<dyn Scheduler>)
+        .collect();
+
+    for policy in [QuantumPolicy::Mean, QuantumPolicy::Median] {
+        labels.push(policy.to_string().to_lowercase());
+        schedulers.push(Box::new(RoundRobinScheduler::new(0).with_policy(policy)));
+    }
+
+    Ok(labels
+        .into_iter()
+        .zip(run_comparison(processes, &schedulers, config)?)
+        .collect())
+}
+
+/*
+ *  Prints one row per quantum, the adaptive quanta last.
+ *
+ *  * The best value of each column is marked with `*` (ties are all marked).
+ *  * CS is the context-switch count, it falls as the quantum grows while the
+ *    response time rises, Round Robin turns into FCFS past the longest burst.
+ */
+pub fn display_quantum_sweep(results: &[(String, ScheduleResult)]) {
+    if results.is_empty() {
+        return;
+    }
+
+    const HEADER: &str = "|------------|------------|------------|------------|------------|";
+
+    let metrics: Vec<Metrics> = results.iter().map(|(_, result)| result.metrics()).collect();
+    let best: Vec<f64> = SWEEP_METRICS.iter().map(|m| best_value(&metrics, m)).collect();
+
+    println!("\n{:=^66}", " TIME QUANTUM SWEEP ");
+    println!("\n{}", HEADER);
+    print!("|{:^12}|", "Quantum");
+    for metric in &SWEEP_METRICS {
+        print!("{:^12}|", metric.name);
+    }
+    println!();
+    println!("{}", HEADER);
+
+    for ((label, _), result_metrics) in results.iter().zip(&metrics) {
+        print!("|{:^12}|", label);
+        for (metric, best) in SWEEP_METRICS.iter().zip(&best) {
+            let value = (metric.value)(result_metrics);
+            let marker = if (value - best).abs() < f64::EPSILON { "*" } else { " " };
+            print!("{:^12}|", format!("{:.*}{}", metric.precision, value, marker));
+        }
+        println!();
+    }
+
+    println!("{}", HEADER);
+
+    println!("\nBest quantum per metric (*):");
+    for (metric, best) in SWEEP_METRICS.iter().zip(&best) {
+        let winners: Vec<&str> = results
+            .iter()
+            .zip(&metrics)
+            .filter(|(_, m)| ((metric.value)(m) - best).abs() < f64::EPSILON)
+            .map(|((label, _), _)| label.as_str())
+            .collect();
+
+        println!("  {:<12}{}", metric.name, winners.join(", "));
+    }
+    println!();
+}
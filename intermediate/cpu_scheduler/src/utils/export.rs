@@ -0,0 +1,398 @@
+use std::{fmt::Write, fs::{self, File}, io, path::Path};
+
+use thiserror::Error;
+
+use crate::{
+    models::{cpu_process::BaseProcess, schedule_result::ScheduleResult},
+    utils::{comparison::comparison_html, gantt_display::{GanttChart, SegmentKind}, report::Report},
+};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("could not write export file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("could not write JSON export: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("could not write CSV export: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("unsupported export format '{0}' (expected svg, html, json or csv)")]
+    UnsupportedFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Svg,
+    Html,
+    // The runs as data, see `report::Report`
+    Json,
+    // One row per process of every run
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, ExportError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "svg" => Ok(ExportFormat::Svg),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(ExportError::UnsupportedFormat(extension)),
+        }
+    }
+}
+
+// Fill of every process bar, picked by pid so a process keeps its color across charts
+const PALETTE: [&str; 10] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2",
+    "#edc948", "#b07aa1", "#ff9da7", "#9c755f", "#bab0ac",
+];
+
+const COLUMNS: [&str; 10] = ["Process", "AT", "BT", "IO", "Priority", "ST", "CT", "TAT", "WT", "RT"];
+
+// Gantt chart layout, in pixels
+const CHART_WIDTH: f64 = 960.0;
+const MAX_UNIT_WIDTH: f64 = 40.0;
+const MARGIN: f64 = 20.0;
+const ROW_LABEL_WIDTH: f64 = 60.0;
+const ROW_HEIGHT: f64 = 36.0;
+const ROW_GAP: f64 = 12.0;
+const AXIS_HEIGHT: f64 = 30.0;
+const MIN_TICK_SPACING: f64 = 32.0;
+
+// Table layout, in pixels
+const CELL_WIDTH: f64 = 72.0;
+const CELL_HEIGHT: f64 = 24.0;
+const LINE_HEIGHT: f64 = 20.0;
+
+// Shared by every SVG: the hatching of idle segments
+const SVG_DEFS: &str = r##"<defs><pattern id="idle" width="6" height="6" patternUnits="userSpaceOnUse" patternTransform="rotate(45)"><rect width="6" height="6" fill="#f4f4f4"/><line x1="0" y1="0" x2="0" y2="6" stroke="#cccccc" stroke-width="2"/></pattern></defs>"##;
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: center; }
+th { background: #333; color: #fff; }
+tr:nth-child(even) td { background: #f4f4f4; }
+tr.missed td { background: #fdd; }
+ul.metrics { list-style: none; padding: 0; }
+section { margin-bottom: 3em; }";
+
+// SVG fragment and the size it takes, so fragments can be stacked into one document
+struct SvgPart {
+    body: String,
+    width: f64,
+    height: f64,
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn process_color(pid: usize) -> &'static str {
+    PALETTE[pid % PALETTE.len()]
+}
+
+fn svg_document(part: &SvgPart) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">{}<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>{}</svg>\n",
+        SVG_DEFS,
+        part.body,
+        w = part.width,
+        h = part.height,
+    )
+}
+
+// Smallest 1, 2, 5 x 10^n step that keeps the axis labels apart
+fn tick_step(unit_width: f64) -> u32 {
+    let mut magnitude = 1;
+    loop {
+        for step in [magnitude, magnitude * 2, magnitude * 5] {
+            if step as f64 * unit_width >= MIN_TICK_SPACING {
+                return step;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+fn gantt_part(chart: &GanttChart) -> SvgPart {
+    let end_time = chart.end_time().max(1);
+    let unit_width = (CHART_WIDTH / end_time as f64).min(MAX_UNIT_WIDTH);
+    let left = MARGIN + ROW_LABEL_WIDTH;
+    let x_of = |time: u32| left + time as f64 * unit_width;
+
+    // A row under the cores for every process that blocked on a lock
+    let mut waiting: Vec<usize> = chart.lock_waits.iter().map(|w| w.pid).collect();
+    waiting.sort_unstable();
+    waiting.dedup();
+
+    let rows_height = (chart.cores + waiting.len()) as f64 * (ROW_HEIGHT + ROW_GAP) - ROW_GAP;
+    let width = x_of(end_time) + MARGIN;
+    let height = MARGIN + rows_height + AXIS_HEIGHT + MARGIN;
+    let mut body = String::new();
+
+    for core in 0..chart.cores {
+        let y = MARGIN + core as f64 * (ROW_HEIGHT + ROW_GAP);
+        let row_label = if chart.cores == 1 { "CPU".to_string() } else { format!("Core {}", core) };
+        let _ = write!(
+            body,
+            "<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\">{}</text>",
+            MARGIN, y + ROW_HEIGHT / 2.0, row_label
+        );
+
+        for segment in chart.timeline(core) {
+            let x = x_of(segment.start_time);
+            let w = (segment.end_time - segment.start_time) as f64 * unit_width;
+            let (fill, text_color) = match segment.kind {
+                SegmentKind::Process => (process_color(segment.pid), "#ffffff"),
+                SegmentKind::ContextSwitch => ("#444444", "#ffffff"),
+                SegmentKind::Idle => ("url(#idle)", "#888888"),
+            };
+
+            let _ = write!(
+                body,
+                "<rect x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\" stroke=\"#ffffff\"><title>{} {}-{}</title></rect>",
+                x, y, w, ROW_HEIGHT, fill, segment.label(), segment.start_time, segment.end_time
+            );
+
+            // Roughly 7px per character at 12px
+            let label = segment.label();
+            if w >= label.len() as f64 * 7.0 + 4.0 {
+                let _ = write!(
+                    body,
+                    "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"{}\">{}</text>",
+                    x + w / 2.0, y + ROW_HEIGHT / 2.0, text_color, label
+                );
+            }
+        }
+    }
+
+    for (row, &pid) in waiting.iter().enumerate() {
+        let y = MARGIN + (chart.cores + row) as f64 * (ROW_HEIGHT + ROW_GAP);
+        let _ = write!(
+            body,
+            "<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\">P{} wait</text>",
+            MARGIN, y + ROW_HEIGHT / 2.0, pid
+        );
+
+        for wait in chart.lock_waits.iter().filter(|w| w.pid == pid) {
+            let x = x_of(wait.start_time);
+            let w = (wait.end_time - wait.start_time) as f64 * unit_width;
+            let _ = write!(
+                body,
+                "<rect x=\"{:.1}\" y=\"{}\" width=\"{:.1}\" height=\"{}\" fill=\"#f4cccc\" stroke=\"#d62728\"><title>P{} blocked on {} held by P{} {}-{}</title></rect>",
+                x, y, w, ROW_HEIGHT, wait.pid, escape(&wait.lock), wait.holder, wait.start_time, wait.end_time
+            );
+
+            if w >= wait.lock.len() as f64 * 7.0 + 4.0 {
+                let _ = write!(
+                    body,
+                    "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"#d62728\">{}</text>",
+                    x + w / 2.0, y + ROW_HEIGHT / 2.0, escape(&wait.lock)
+                );
+            }
+        }
+    }
+
+    // Time axis under the last row
+    let axis_y = MARGIN + rows_height + 4.0;
+    let _ = write!(
+        body,
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{:.1}\" y2=\"{}\" stroke=\"#222222\"/>",
+        left, axis_y, x_of(end_time), axis_y
+    );
+
+    let step = tick_step(unit_width);
+    let mut ticks: Vec<u32> = (0..end_time).step_by(step as usize).collect();
+    // The end is always labelled, the last regular tick makes room for it
+    if ticks.last().is_some_and(|&last| (end_time - last) as f64 * unit_width < MIN_TICK_SPACING) && ticks.len() > 1 {
+        ticks.pop();
+    }
+    ticks.push(end_time);
+
+    for time in ticks {
+        let x = x_of(time);
+        let _ = write!(
+            body,
+            "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#222222\"/><text x=\"{x:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            axis_y, axis_y + 5.0, axis_y + 18.0, time
+        );
+    }
+
+    // Missed deadlines as red lines across every row
+    for &(pid, deadline) in &chart.deadline_misses {
+        let x = x_of(deadline);
+        let _ = write!(
+            body,
+            "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#d62728\" stroke-width=\"2\" stroke-dasharray=\"4 2\"><title>P{} missed its deadline at {}</title></line>",
+            MARGIN - 6.0, MARGIN + rows_height + 2.0, pid, deadline
+        );
+    }
+
+    SvgPart { body, width, height }
+}
+
+fn table_rows(result: &ScheduleResult) -> Vec<(bool, [String; 10])> {
+    result.processes
+        .iter()
+        .map(|p| (
+            p.missed_deadline(),
+            [
+                p.pid.to_string(),
+                p.arrival_time.to_string(),
+                p.burst_time.to_string(),
+                p.io_time.to_string(),
+                p.priority.to_string(),
+                p.start_time.to_string(),
+                p.completion_time.to_string(),
+                p.turnaround_time.to_string(),
+                p.waiting_time.to_string(),
+                p.response_time.to_string(),
+            ],
+        ))
+        .collect()
+}
+
+fn table_part(result: &ScheduleResult) -> SvgPart {
+    let rows = table_rows(result);
+    let width = MARGIN * 2.0 + CELL_WIDTH * COLUMNS.len() as f64;
+    let height = MARGIN * 2.0 + CELL_HEIGHT * (rows.len() + 1) as f64;
+    let mut body = String::new();
+
+    let mut draw_row = |y: f64, cells: &[&str], fill: &str, color: &str| {
+        for (i, cell) in cells.iter().enumerate() {
+            let x = MARGIN + i as f64 * CELL_WIDTH;
+            let _ = write!(
+                body,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#cccccc\"/><text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"{}\">{}</text>",
+                x, y, CELL_WIDTH, CELL_HEIGHT, fill, x + CELL_WIDTH / 2.0, y + CELL_HEIGHT / 2.0, color, cell
+            );
+        }
+    };
+
+    draw_row(MARGIN, &COLUMNS, "#333333", "#ffffff");
+    for (i, (missed, cells)) in rows.iter().enumerate() {
+        let fill = match (missed, i % 2) {
+            (true, _) => "#ffdddd",
+            (false, 1) => "#f4f4f4",
+            _ => "#ffffff",
+        };
+        let cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+        draw_row(MARGIN + CELL_HEIGHT * (i + 1) as f64, &cells, fill, "#222222");
+    }
+
+    SvgPart { body, width, height }
+}
+
+pub fn gantt_svg(chart: &GanttChart) -> String {
+    svg_document(&gantt_part(chart))
+}
+
+/*
+ *  One standalone SVG with, for every result: the algorithm name, the per-process
+ *  table, the averages and the Gantt chart, stacked top to bottom.
+ */
+pub fn results_svg(results: &[ScheduleResult]) -> String {
+    let mut body = String::new();
+    let mut width: f64 = 0.0;
+    let mut y = 0.0;
+
+    for result in results {
+        let _ = write!(
+            body,
+            "<text x=\"{}\" y=\"{}\" font-size=\"16\" font-weight=\"bold\">{}</text>",
+            MARGIN, y + MARGIN + 12.0, escape(&result.algorithm_name)
+        );
+        y += MARGIN + 12.0;
+
+        let table = table_part(result);
+        let _ = write!(body, "<g transform=\"translate(0,{})\">{}</g>", y, table.body);
+        y += table.height;
+        width = width.max(table.width);
+
+        for line in result.summary_lines() {
+            let _ = write!(body, "<text x=\"{}\" y=\"{}\">{}</text>", MARGIN, y, escape(&line));
+            y += LINE_HEIGHT;
+        }
+
+        let chart = gantt_part(&result.gantt_chart);
+        let _ = write!(body, "<g transform=\"translate(0,{})\">{}</g>", y, chart.body);
+        y += chart.height;
+        width = width.max(chart.width);
+    }
+
+    svg_document(&SvgPart { body, width, height: y })
+}
+
+/*
+ *  Self-contained HTML report, no external stylesheet or script.
+ *
+ *  * One section per result with its table, averages and Gantt chart as inline SVG.
+ *  * More than one result (compare mode) adds the comparison table at the top.
+ */
+pub fn html_report(results: &[ScheduleResult]) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>CPU Scheduling Report</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>CPU Scheduling Report</h1>\n",
+        HTML_STYLE
+    );
+
+    if results.len() > 1 {
+        html.push_str(&comparison_html(results));
+    }
+
+    for result in results {
+        let _ = writeln!(html, "<section>\n<h2>{}</h2>\n<table>", escape(&result.algorithm_name));
+
+        html.push_str("<tr>");
+        for column in COLUMNS {
+            let _ = write!(html, "<th>{}</th>", column);
+        }
+        html.push_str("</tr>\n");
+
+        for (missed, cells) in table_rows(result) {
+            html.push_str(if missed { "<tr class=\"missed\">" } else { "<tr>" });
+            for cell in cells {
+                let _ = write!(html, "<td>{}</td>", cell);
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n<ul class=\"metrics\">\n");
+
+        for line in result.summary_lines() {
+            let _ = writeln!(html, "<li>{}</li>", escape(&line));
+        }
+        html.push_str("</ul>\n");
+
+        html.push_str(&gantt_svg(&result.gantt_chart));
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// Writes `results` to `path`, as SVG or HTML depending on its extension
+// `workload` is only written by the data formats, the charts are drawn from the results alone
+pub fn export(path: &Path, workload: &[BaseProcess], results: &[ScheduleResult]) -> Result<(), ExportError> {
+    match ExportFormat::from_path(path)? {
+        ExportFormat::Svg => fs::write(path, results_svg(results))?,
+        ExportFormat::Html => fs::write(path, html_report(results))?,
+        ExportFormat::Json => Report::new(workload, results).write_json(File::create(path)?)?,
+        ExportFormat::Csv => Report::new(workload, results).write_csv(File::create(path)?)?,
+    }
+
+    Ok(())
+}
@@ -1,22 +1,280 @@
-#[derive(Debug)]
+use std::env;
+
+use serde::Serialize;
+
+// Characters per time unit when the whole chart fits the terminal
+const UNIT_WIDTH: usize = 4;
+const DEFAULT_TERMINAL_WIDTH: usize = 90;
+const MIN_TERMINAL_WIDTH: usize = 20;
+// Longest chart drawn in the terminal, every segment takes at least a column so there are too many past it
+const MAX_CHART_LINES: usize = 1000;
+// Keys standing in for labels too wide for their segment, '*' once they run out
+const LEGEND_KEYS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/*
+ *  How time maps to characters in the terminal.
+ *
+ *  * `unit_width` characters per time unit while the chart is short enough, up to `UNIT_WIDTH`.
+ *  * `units_per_column` time units per character once one character per unit is too
+ *    much, a segment is then rounded to the nearest column but never narrower than one.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Scale {
+    unit_width: usize,
+    units_per_column: u32,
+}
+
+impl Scale {
+    // Characters `length` time units take
+    fn width(&self, length: u32) -> usize {
+        match self.units_per_column {
+            1 => length as usize * self.unit_width,
+            units => ((length as u64 + units as u64 / 2) / units as u64).max(1) as usize,
+        }
+    }
+
+    // Time units that fit in `columns` characters
+    fn units(&self, columns: usize) -> u32 {
+        match self.units_per_column {
+            1 => (columns / self.unit_width) as u32,
+            units => (columns as u64 * units as u64).min(u32::MAX as u64) as u32,
+        }
+    }
+}
+
+// Segments of every core, rendered as one row per core
+#[derive(Debug, Clone, Serialize)]
 pub struct GanttChart {
     pub segments: Vec<GanttSegment>,
+    pub cores: usize,
+    // (pid, deadline) of every job that completed after its deadline, marked under each row
+    pub deadline_misses: Vec<(usize, u32)>,
+    // Time every process spent blocked on a lock, marked under each row
+    pub lock_waits: Vec<LockWait>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    #[default]
+    Process,
+    // Dispatch latency, `pid` is the process being switched in
+    ContextSwitch,
+    // CPU has nothing to run, `pid` is unused
+    Idle,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct GanttSegment {
     pub pid: usize,
     pub start_time: u32,
     pub end_time: u32,
+    pub kind: SegmentKind,
+    pub core: usize,
+}
+
+// `pid` was blocked on `lock`, held by `holder`, from `start_time` to `end_time`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockWait {
+    pub pid: usize,
+    pub lock: String,
+    pub holder: usize,
+    pub start_time: u32,
+    pub end_time: u32,
+}
+
+impl GanttSegment {
+    pub fn label(&self) -> String {
+        match self.kind {
+            SegmentKind::Process => format!("P{}", self.pid),
+            SegmentKind::ContextSwitch => "CS".to_string(),
+            SegmentKind::Idle => "IDLE".to_string(),
+        }
+    }
+}
+
+impl Default for GanttChart {
+    fn default() -> Self {
+        GanttChart::new()
+    }
 }
 
 impl GanttChart {
     pub fn new() -> Self {
+        GanttChart::with_cores(1)
+    }
+
+    pub fn with_cores(cores: usize) -> Self {
         GanttChart {
             segments: Vec::new(),
+            cores: cores.max(1),
+            deadline_misses: Vec::new(),
+            lock_waits: Vec::new(),
         }
     }
 
+    pub fn core_segments(&self, core: usize) -> impl Iterator<Item = &GanttSegment> {
+        self.segments.iter().filter(move |s| s.core == core)
+    }
+
+    // Pid of the last process that ran on `core`, context-switch and idle segments are skipped
+    pub fn last_pid(&self, core: usize) -> Option<usize> {
+        // Searched from the end, the chart is in time order and only its tail matters
+        self.segments
+            .iter()
+            .rev()
+            .find(|s| s.core == core && s.kind == SegmentKind::Process)
+            .map(|s| s.pid)
+    }
+
+    /*
+     *  Charges the dispatch latency before `pid` runs on `core` at `current_time`.
+     *
+     *  * Only charged when the core switches to a different process than the last one,
+     *    the first dispatch and a process continuing on the core are free.
+     *  * Returns the time the process actually starts running, `Err` with that time
+     *    and nothing charged if it is past the last u32 time unit.
+     */
+    pub fn dispatch(&mut self, core: usize, pid: usize, current_time: u32, context_switch_time: u32) -> Result<u32, u64> {
+        match self.last_pid(core) {
+            Some(last) if last != pid && context_switch_time > 0 => {
+                let end_time = current_time
+                    .checked_add(context_switch_time)
+                    .ok_or(current_time as u64 + context_switch_time as u64)?;
+                self.segments.push(GanttSegment {
+                    pid,
+                    start_time: current_time,
+                    end_time,
+                    kind: SegmentKind::ContextSwitch,
+                    core,
+                });
+                Ok(end_time)
+            }
+            _ => Ok(current_time),
+        }
+    }
+
+    // Adds a segment to `core`, extending its previous one if it is the same kind and pid
+    fn push_segment(&mut self, core: usize, pid: usize, start_time: u32, end_time: u32, kind: SegmentKind) {
+        if end_time == start_time {
+            return;
+        }
+
+        match self.segments.iter_mut().rev().find(|s| s.core == core) {
+            Some(last) if last.kind == kind && last.pid == pid && last.end_time == start_time => {
+                last.end_time = end_time;
+            }
+            _ => self.segments.push(GanttSegment { pid, start_time, end_time, kind, core }),
+        }
+    }
+
+    pub fn add_idle(&mut self, core: usize, start_time: u32, end_time: u32) {
+        self.push_segment(core, 0, start_time, end_time, SegmentKind::Idle);
+    }
+
+    // Adds a running segment, extending the previous one if the same process just kept running
+    pub fn add_process(&mut self, core: usize, pid: usize, start_time: u32, end_time: u32) {
+        self.push_segment(core, pid, start_time, end_time, SegmentKind::Process);
+    }
+
+    pub fn process_segments(&self, core: usize) -> impl Iterator<Item = &GanttSegment> {
+        self.core_segments(core).filter(|s| s.kind == SegmentKind::Process)
+    }
+
+    pub fn context_switch_time(&self) -> u32 {
+        self.segments
+            .iter()
+            .filter(|s| s.kind == SegmentKind::ContextSwitch)
+            .map(|s| s.end_time - s.start_time)
+            .sum()
+    }
+
+    // Time the last segment of any core ends
+    pub fn end_time(&self) -> u32 {
+        self.segments.iter().map(|s| s.end_time).max().unwrap_or(0)
+    }
+
+    // The chart as far as it got by `time`, segments still running are cut off there
+    pub fn until(&self, time: u32) -> GanttChart {
+        GanttChart {
+            segments: self.segments
+                .iter()
+                .filter(|s| s.start_time < time)
+                .map(|s| GanttSegment { end_time: s.end_time.min(time), ..s.clone() })
+                .collect(),
+            cores: self.cores,
+            deadline_misses: self.deadline_misses
+                .iter()
+                .copied()
+                .filter(|&(_, deadline)| deadline <= time)
+                .collect(),
+            lock_waits: self.lock_waits
+                .iter()
+                .filter(|wait| wait.start_time < time)
+                .map(|wait| LockWait { end_time: wait.end_time.min(time), ..wait.clone() })
+                .collect(),
+        }
+    }
+
+    /*
+     *  Segments of `core` from time 0 until the end of the chart, every gap
+     *  (before the first arrival, between bursts, after the core's last process)
+     *  filled with an idle segment, merged with any idle segment next to it.
+     */
+    pub fn timeline(&self, core: usize) -> Vec<GanttSegment> {
+        let mut timeline = GanttChart::with_cores(self.cores);
+        let mut time = 0;
+
+        for segment in self.core_segments(core) {
+            timeline.add_idle(core, time, segment.start_time.max(time));
+            timeline.push_segment(core, segment.pid, segment.start_time, segment.end_time, segment.kind);
+            time = time.max(segment.end_time);
+        }
+
+        timeline.add_idle(core, time, self.end_time().max(time));
+        timeline.segments
+    }
+
+    /*
+     *  Widest scale that fits every core on one line: up to `UNIT_WIDTH` characters per
+     *  time unit, then as few time units per character as needed.
+     *
+     *  A row with more segments than the terminal has columns never fits, it is drawn
+     *  at the scale that would fit its time span and wraps.
+     */
+    fn scale(&self, max_width: usize) -> Scale {
+        let timelines: Vec<Vec<GanttSegment>> = (0..self.cores).map(|core| self.timeline(core)).collect();
+        let fits = |scale: Scale| {
+            timelines.iter().all(|timeline| {
+                let width = timeline.iter().map(|s| scale.width(s.end_time - s.start_time)).sum::<usize>();
+                // A '+' before every segment and one closing the row
+                width + timeline.len() < max_width
+            })
+        };
+
+        if let Some(unit_width) = (1..=UNIT_WIDTH).rev().find(|&unit_width| fits(Scale { unit_width, units_per_column: 1 })) {
+            return Scale { unit_width, units_per_column: 1 };
+        }
+
+        // Rows only get narrower as a column holds more time units
+        let end_time = self.end_time().max(1);
+        let (mut low, mut high) = (2, end_time.max(2));
+        if !fits(Scale { unit_width: 1, units_per_column: high }) {
+            return Scale { unit_width: 1, units_per_column: end_time.div_ceil(max_width as u32).max(2) };
+        }
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if fits(Scale { unit_width: 1, units_per_column: middle }) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        Scale { unit_width: 1, units_per_column: low }
+    }
+
     pub fn display_gantt_chart(&self) {
         if self.segments.is_empty() {
             return;
@@ -24,44 +282,267 @@ impl GanttChart {
 
         println!("\n{:=^90}", " GANTT CHART ");
 
-        // Print top border
-        print!("\n");
-        for segment in &self.segments {
-            let width = (segment.end_time - segment.start_time) as usize * 4;
-            print!("+{}", "-".repeat(width));
-        }
-        println!("+");
-
-        // Print process IDs
-        print!("|");
-        for segment in &self.segments {
-            let width = (segment.end_time - segment.start_time) as usize * 4;
-            let label = format!("P{}", segment.pid);
-            let padding = (width - label.len()) / 2;
-            print!(
-                "{}{}{}",
-                " ".repeat(padding),
-                label,
-                " ".repeat(width - padding - label.len())
+        let max_width = terminal_width();
+
+        if self.segments.len() / max_width > MAX_CHART_LINES {
+            println!(
+                "The chart has {} segments, too many to draw here, export it with --export <report.svg> instead\n",
+                self.segments.len()
             );
-            print!("|");
+            return;
+        }
+
+        let scale = self.scale(max_width);
+        if scale.units_per_column > 1 {
+            println!("Scale: 1 column = {} time units, every segment is at least 1 column wide", scale.units_per_column);
+        }
+
+        if self.cores == 1 {
+            self.display_row(&self.timeline(0), scale, max_width);
+        } else {
+            for core in 0..self.cores {
+                println!("\nCore {}", core);
+                if self.core_segments(core).next().is_none() {
+                    println!("(never used)");
+                    continue;
+                }
+                self.display_row(&self.timeline(core), scale, max_width);
+            }
+        }
+
+        if !self.deadline_misses.is_empty() {
+            let misses: Vec<String> = self.deadline_misses
+                .iter()
+                .map(|(pid, deadline)| format!("P{} @ {}", pid, deadline))
+                .collect();
+            println!("^ Deadline missed: {}\n", misses.join(", "));
+        }
+
+        if !self.lock_waits.is_empty() {
+            let waits: Vec<String> = self.lock_waits
+                .iter()
+                .map(|w| format!("P{} on {} {}-{} (held by P{})", w.pid, w.lock, w.start_time, w.end_time, w.holder))
+                .collect();
+            println!("~ Blocked on a lock: {}\n", waits.join(", "));
+        }
+    }
+
+    // Column of `time` in a line, a time outside of the line is drawn at its nearest end
+    fn column_of(segments: &[GanttSegment], time: u32, scale: Scale) -> usize {
+        let mut column = 0;
+
+        for segment in segments {
+            let (length, width) = (segment.end_time - segment.start_time, scale.width(segment.end_time - segment.start_time));
+            if time <= segment.start_time {
+                return column;
+            }
+            if time < segment.end_time {
+                return column + ((time - segment.start_time) as u64 * width as u64 / length as u64) as usize;
+            }
+            column += width + 1;
+        }
+
+        column
+    }
+
+    // Splits a row into lines of at most `max_width` characters, a segment crossing the end of a line continues on the next
+    fn wrap(segments: &[GanttSegment], scale: Scale, max_width: usize) -> Vec<Vec<GanttSegment>> {
+        let mut lines = Vec::new();
+        let mut line: Vec<GanttSegment> = Vec::new();
+        // Every line starts with its opening '+'
+        let mut width = 1;
+
+        for segment in segments {
+            let mut segment = segment.clone();
+
+            loop {
+                let segment_width = scale.width(segment.end_time - segment.start_time);
+                if width + segment_width < max_width {
+                    width += segment_width + 1;
+                    line.push(segment);
+                    break;
+                }
+
+                // At least one time unit per line, however narrow the terminal
+                let units = scale.units((max_width - width).saturating_sub(1)).max(u32::from(line.is_empty()));
+                if units > 0 {
+                    line.push(GanttSegment { end_time: segment.start_time + units, ..segment.clone() });
+                    segment.start_time += units;
+                }
+
+                lines.push(std::mem::take(&mut line));
+                width = 1;
+
+                if segment.start_time == segment.end_time {
+                    break;
+                }
+            }
+        }
+
+        if !line.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    fn display_row(&self, segments: &[GanttSegment], scale: Scale, max_width: usize) {
+        for line in Self::wrap(segments, scale, max_width) {
+            self.display_line(&line, scale);
+        }
+    }
+
+    fn display_line(&self, segments: &[GanttSegment], scale: Scale) {
+        println!();
+        for row in self.render_line(segments, scale) {
+            println!("{}", row);
         }
         println!();
+    }
+
+    /*
+     *  Rows a line of the chart is drawn as.
+     *
+     *  * A label wider than its segment is replaced by a one letter key, listed in a legend under the line.
+     *  * Every segment boundary gets its time, ending under its '+'. A time that would run
+     *    into the previous one goes on the next row of times instead.
+     */
+    fn render_line(&self, segments: &[GanttSegment], scale: Scale) -> Vec<String> {
+        let widths: Vec<usize> = segments
+            .iter()
+            .map(|s| scale.width(s.end_time - s.start_time))
+            .collect();
+
+        let border: String = widths.iter().map(|&width| format!("+{}", "-".repeat(width))).collect::<String>() + "+";
+
+        let mut legend: Vec<(char, String)> = Vec::new();
+        let mut labels = String::from("|");
+        for (segment, &width) in segments.iter().zip(&widths) {
+            let mut label = segment.label();
+            if label.len() > width {
+                let key = match legend.iter().find(|(_, name)| *name == label) {
+                    Some(&(key, _)) => key,
+                    None => {
+                        let key = LEGEND_KEYS.chars().nth(legend.len()).unwrap_or('*');
+                        legend.push((key, label));
+                        key
+                    }
+                };
+                label = key.to_string();
+            }
+            labels.push_str(&format!("{:^width$}|", label, width = width));
+        }
+
+        let mut times: Vec<Vec<(usize, String)>> = Vec::new();
+        let boundaries = std::iter::once((0, segments[0].start_time)).chain(
+            segments.iter().zip(&widths).scan(0, |column, (segment, &width)| {
+                *column += width + 1;
+                Some((*column, segment.end_time))
+            }),
+        );
+        for (column, time) in boundaries {
+            let time = time.to_string();
+            let start = (column + 1).saturating_sub(time.len());
+            match times.iter_mut().find(|row| row.last().is_none_or(|(from, label)| from + label.len() < start)) {
+                Some(row) => row.push((start, time)),
+                None => times.push(vec![(start, time)]),
+            }
+        }
+
+        let mut rows = vec![border.clone(), labels, border];
+        for placed in times {
+            let mut row = String::new();
+            for (from, label) in placed {
+                row.push_str(&" ".repeat(from - row.len()));
+                row.push_str(&label);
+            }
+            rows.push(row);
+        }
+
+        // Mark every missed deadline of this line with a ^ under its time
+        let (start_time, end_time) = (segments[0].start_time, segments[segments.len() - 1].end_time);
+        let mut columns: Vec<usize> = self.deadline_misses
+            .iter()
+            .filter(|&&(_, deadline)| deadline > start_time && deadline <= end_time)
+            .map(|&(_, deadline)| Self::column_of(segments, deadline, scale))
+            .collect();
+
+        if !columns.is_empty() {
+            columns.sort_unstable();
+            columns.dedup();
 
-        // Print bottom border
-        for segment in &self.segments {
-            let width = (segment.end_time - segment.start_time) as usize * 4;
-            print!("+{}", "-".repeat(width));
+            let mut marker = String::new();
+            for column in columns {
+                marker.push_str(&" ".repeat(column - marker.len()));
+                marker.push('^');
+            }
+            rows.push(marker);
         }
-        println!("+");
 
-        // Print time labels
-        print!("{}", self.segments[0].start_time);
-        for segment in &self.segments {
-            let width = (segment.end_time - segment.start_time) as usize * 4;
-            let time_label = segment.end_time.to_string();
-            print!("{}{}", " ".repeat(width - time_label.len() + 1), time_label);
+        // Underline the time every process spent blocked on a lock during this line
+        for wait in self.lock_waits.iter().filter(|w| w.start_time < end_time && w.end_time > start_time) {
+            let from = Self::column_of(segments, wait.start_time, scale);
+            let to = Self::column_of(segments, wait.end_time, scale);
+            rows.push(format!("{}{} P{} waits for {}", " ".repeat(from), "~".repeat(to - from + 1), wait.pid, wait.lock));
         }
-        println!("\n");
+
+        if !legend.is_empty() {
+            let keys: Vec<String> = legend.iter().map(|(key, label)| format!("{} = {}", key, label)).collect();
+            rows.push(format!("Legend: {}", keys.join(", ")));
+        }
+
+        rows
+    }
+}
+
+// Width the chart wraps at, from `COLUMNS` when the shell exports it
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+        .max(MIN_TERMINAL_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_chart_keeps_the_full_unit_width() {
+        let mut chart = GanttChart::new();
+        chart.add_process(0, 1, 0, 5);
+
+        assert_eq!(chart.scale(90), Scale { unit_width: UNIT_WIDTH, units_per_column: 1 });
+    }
+
+    #[test]
+    fn long_bursts_are_scaled_to_one_line() {
+        let mut chart = GanttChart::new();
+        chart.add_process(0, 1, 0, 500);
+        chart.add_idle(0, 500, 2000);
+        chart.add_process(0, 2, 2000, 2300);
+
+        let scale = chart.scale(90);
+        assert!(scale.units_per_column > 1);
+        assert_eq!(GanttChart::wrap(&chart.timeline(0), scale, 90).len(), 1);
+    }
+
+    #[test]
+    fn narrow_segments_keep_their_label_and_every_time() {
+        let mut chart = GanttChart::new();
+        chart.add_idle(0, 0, 100);
+        chart.add_process(0, 1, 100, 103);
+        chart.add_process(0, 2, 103, 105);
+
+        let rows = chart.render_line(&chart.timeline(0), Scale { unit_width: 1, units_per_column: 10 });
+        assert_eq!(rows, vec![
+            "+----------+-+-+",
+            "|   IDLE   |a|b|",
+            "+----------+-+-+",
+            "0        100 105",
+            "           103",
+            "Legend: a = P1, b = P2",
+        ]);
     }
 }
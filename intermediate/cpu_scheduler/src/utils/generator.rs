@@ -0,0 +1,335 @@
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::models::cpu_process::{BaseProcess, Burst};
+
+// Bimodal defaults: CPU-bound burst and the short CPU bursts of an I/O-bound process
+const DEFAULT_CPU_BOUND: (u32, u32) = (8, 20);
+const DEFAULT_SHORT_BURST: (u32, u32) = (1, 3);
+// Number of CPU bursts of an I/O-bound process in the bimodal mix
+const IO_BOUND_CPU_BURSTS: (u32, u32) = (2, 4);
+// Length of every I/O burst of an I/O-bound process
+const IO_BOUND_IO_TIME: (u32, u32) = (2, 8);
+
+// Time between two consecutive arrivals, the first process always arrives at 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrivalDistribution {
+    Fixed(u32),
+    Uniform { min: u32, max: u32 },
+    // Poisson arrivals: exponential inter-arrival times around `mean`
+    Exponential { mean: f64 },
+}
+
+// Total CPU time of a process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BurstDistribution {
+    Uniform { min: u32, max: u32 },
+    Exponential { mean: f64 },
+    /*
+     *  Mix of CPU-bound and I/O-bound processes.
+     *
+     *  * `io_bound` is the fraction (0 to 1) of I/O-bound processes.
+     *  * A CPU-bound process is one CPU burst drawn from `cpu_bound`.
+     *  * An I/O-bound process alternates 2-4 short CPU bursts drawn from
+     *    `short` with 2-8 time units of I/O.
+     */
+    Bimodal { io_bound: f64, cpu_bound: (u32, u32), short: (u32, u32) },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriorityDistribution {
+    Fixed(u32),
+    Uniform { min: u32, max: u32 },
+}
+
+fn parse_number<T: FromStr>(value: &str, spec: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number '{}' in '{}'", value, spec))
+}
+
+fn parse_range(min: &str, max: &str, spec: &str) -> Result<(u32, u32), String> {
+    let (min, max) = (parse_number(min, spec)?, parse_number(max, spec)?);
+    if min > max {
+        return Err(format!("Invalid range in '{}', min is larger than max", spec));
+    }
+    Ok((min, max))
+}
+
+// `fixed:<interval>`, `uniform:<min>:<max>` or `exp:<mean>` (also `poisson:<mean>`)
+impl FromStr for ArrivalDistribution {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        match parts.as_slice() {
+            ["fixed", interval] => Ok(ArrivalDistribution::Fixed(parse_number(interval, spec)?)),
+            ["uniform", min, max] => {
+                let (min, max) = parse_range(min, max, spec)?;
+                Ok(ArrivalDistribution::Uniform { min, max })
+            }
+            ["exp" | "exponential" | "poisson", mean] => match parse_number::<f64>(mean, spec)? {
+                mean if mean > 0.0 => Ok(ArrivalDistribution::Exponential { mean }),
+                _ => Err(format!("The mean in '{}' must be greater than 0", spec)),
+            },
+            _ => Err(format!("Unknown arrival distribution '{}', expected fixed:<n>, uniform:<min>:<max> or exp:<mean>", spec)),
+        }
+    }
+}
+
+// `uniform:<min>:<max>`, `exp:<mean>` or `bimodal:<io-bound fraction>[:<cpu min>:<cpu max>:<short min>:<short max>]`
+impl FromStr for BurstDistribution {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let distribution = match parts.as_slice() {
+            ["uniform", min, max] => {
+                let (min, max) = parse_range(min, max, spec)?;
+                BurstDistribution::Uniform { min, max }
+            }
+            ["exp" | "exponential", mean] => BurstDistribution::Exponential { mean: parse_number(mean, spec)? },
+            ["bimodal", io_bound] => BurstDistribution::Bimodal {
+                io_bound: parse_number(io_bound, spec)?,
+                cpu_bound: DEFAULT_CPU_BOUND,
+                short: DEFAULT_SHORT_BURST,
+            },
+            ["bimodal", io_bound, cpu_min, cpu_max, short_min, short_max] => BurstDistribution::Bimodal {
+                io_bound: parse_number(io_bound, spec)?,
+                cpu_bound: parse_range(cpu_min, cpu_max, spec)?,
+                short: parse_range(short_min, short_max, spec)?,
+            },
+            _ => return Err(format!(
+                "Unknown burst distribution '{}', expected uniform:<min>:<max>, exp:<mean> or bimodal:<io-bound fraction>",
+                spec
+            )),
+        };
+
+        match distribution {
+            BurstDistribution::Uniform { min: 0, .. } => Err(format!("Bursts in '{}' must be at least 1", spec)),
+            BurstDistribution::Exponential { mean } if mean <= 0.0 => {
+                Err(format!("The mean in '{}' must be greater than 0", spec))
+            }
+            BurstDistribution::Bimodal { io_bound, cpu_bound, short }
+                if !(0.0..=1.0).contains(&io_bound) || cpu_bound.0 == 0 || short.0 == 0 =>
+            {
+                Err(format!("Invalid bimodal mix '{}', the fraction is 0 to 1 and bursts are at least 1", spec))
+            }
+            _ => Ok(distribution),
+        }
+    }
+}
+
+// `fixed:<priority>` or `uniform:<min>:<max>`
+impl FromStr for PriorityDistribution {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        match parts.as_slice() {
+            ["fixed", priority] => Ok(PriorityDistribution::Fixed(parse_number(priority, spec)?)),
+            ["uniform", min, max] => {
+                let (min, max) = parse_range(min, max, spec)?;
+                Ok(PriorityDistribution::Uniform { min, max })
+            }
+            _ => Err(format!("Unknown priority distribution '{}', expected fixed:<n> or uniform:<min>:<max>", spec)),
+        }
+    }
+}
+
+/*
+ *  Synthetic workload from an explicit seed, the same settings and seed always
+ *  produce the same processes.
+ *
+ *  * Draws come from ChaCha8, seeded from `seed`, its output is the same on every
+ *    platform and release.
+ *  * Pids are 1 to `count` in arrival order.
+ *  * Arrival times and the CPU time of a process must fit in u32, a workload that
+ *    goes past the last time unit is an error.
+ *  * Defaults: uniform 0-4 inter-arrival, uniform 1-10 bursts, priority 0.
+ */
+#[derive(Debug, Clone)]
+pub struct WorkloadGenerator {
+    pub count: usize,
+    pub seed: u64,
+    pub arrival: ArrivalDistribution,
+    pub burst: BurstDistribution,
+    pub priority: PriorityDistribution,
+}
+
+impl WorkloadGenerator {
+    pub fn new(count: usize, seed: u64) -> Self {
+        WorkloadGenerator {
+            count,
+            seed,
+            arrival: ArrivalDistribution::Uniform { min: 0, max: 4 },
+            burst: BurstDistribution::Uniform { min: 1, max: 10 },
+            priority: PriorityDistribution::Fixed(0),
+        }
+    }
+
+    pub fn with_arrival(mut self, arrival: ArrivalDistribution) -> Self {
+        self.arrival = arrival;
+        self
+    }
+
+    pub fn with_burst(mut self, burst: BurstDistribution) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: PriorityDistribution) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    // Inverse transform sampling, rounded to whole time units, None past the last time unit
+    fn exponential(rng: &mut ChaCha8Rng, mean: f64) -> Option<u32> {
+        let u: f64 = rng.random();
+        let time = (-mean * (1.0 - u).ln()).round();
+        (time <= u32::MAX as f64).then_some(time as u32)
+    }
+
+    fn bursts(&self, rng: &mut ChaCha8Rng, pid: usize) -> Result<Vec<Burst>, String> {
+        let too_long = || format!("Process {} has more than {} units of CPU time", pid, u32::MAX);
+
+        match self.burst {
+            BurstDistribution::Uniform { min, max } => Ok(vec![Burst::Cpu(rng.random_range(min..=max))]),
+            BurstDistribution::Exponential { mean } => {
+                let burst = Self::exponential(rng, mean).ok_or_else(too_long)?;
+                Ok(vec![Burst::Cpu(burst.max(1))])
+            }
+            BurstDistribution::Bimodal { io_bound, cpu_bound, short } => {
+                if !rng.random_bool(io_bound) {
+                    return Ok(vec![Burst::Cpu(rng.random_range(cpu_bound.0..=cpu_bound.1))]);
+                }
+
+                let cpu_bursts = rng.random_range(IO_BOUND_CPU_BURSTS.0..=IO_BOUND_CPU_BURSTS.1);
+                let mut bursts = Vec::new();
+                for i in 0..cpu_bursts {
+                    if i > 0 {
+                        bursts.push(Burst::Io(rng.random_range(IO_BOUND_IO_TIME.0..=IO_BOUND_IO_TIME.1)));
+                    }
+                    bursts.push(Burst::Cpu(rng.random_range(short.0..=short.1)));
+                }
+
+                // The CPU bursts add up into `burst_time`, the I/O ones are at most a few units each
+                let cpu_time = bursts.iter().try_fold(0u32, |sum, burst| match burst {
+                    Burst::Cpu(time) => sum.checked_add(*time),
+                    Burst::Io(_) => Some(sum),
+                });
+                cpu_time.ok_or_else(too_long)?;
+                Ok(bursts)
+            }
+        }
+    }
+
+    pub fn generate(&self) -> Result<Vec<BaseProcess>, String> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut arrival_time: u32 = 0;
+
+        (1..=self.count)
+            .map(|pid| {
+                if pid > 1 {
+                    let gap = match self.arrival {
+                        ArrivalDistribution::Fixed(interval) => Some(interval),
+                        ArrivalDistribution::Uniform { min, max } => Some(rng.random_range(min..=max)),
+                        ArrivalDistribution::Exponential { mean } => Self::exponential(&mut rng, mean),
+                    };
+                    arrival_time = gap
+                        .and_then(|gap| arrival_time.checked_add(gap))
+                        .ok_or_else(|| format!("Process {} arrives after {}, the last time unit", pid, u32::MAX))?;
+                }
+
+                let bursts = self.bursts(&mut rng, pid)?;
+                let priority = match self.priority {
+                    PriorityDistribution::Fixed(priority) => priority,
+                    PriorityDistribution::Uniform { min, max } => rng.random_range(min..=max),
+                };
+
+                Ok(BaseProcess::new(pid, arrival_time, 0)
+                    .with_bursts(bursts)
+                    .with_priority(priority))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What a generated process is made of
+    fn summary(processes: &[BaseProcess]) -> Vec<(usize, u32, u32, u32, Vec<Burst>)> {
+        processes.iter().map(|p| (p.pid, p.arrival_time, p.burst_time, p.priority, p.bursts.clone())).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_workload() {
+        let generator = WorkloadGenerator::new(200, 7)
+            .with_arrival(ArrivalDistribution::Exponential { mean: 3.0 })
+            .with_burst(BurstDistribution::Bimodal { io_bound: 0.5, cpu_bound: (8, 20), short: (1, 3) })
+            .with_priority(PriorityDistribution::Uniform { min: 0, max: 9 });
+
+        assert_eq!(summary(&generator.generate().unwrap()), summary(&generator.generate().unwrap()));
+        let other = WorkloadGenerator { seed: 8, ..generator.clone() };
+        assert_ne!(summary(&generator.generate().unwrap()), summary(&other.generate().unwrap()));
+
+        // ChaCha8 draws the same numbers everywhere, a change here breaks every saved seed
+        let processes = WorkloadGenerator::new(4, 42)
+            .with_priority(PriorityDistribution::Uniform { min: 0, max: 9 })
+            .generate()
+            .unwrap();
+        let drawn: Vec<(u32, u32, u32)> = processes.iter().map(|p| (p.arrival_time, p.burst_time, p.priority)).collect();
+        assert_eq!(drawn, vec![(0, 3, 6), (0, 10, 7), (2, 4, 6), (5, 3, 6)]);
+    }
+
+    #[test]
+    fn draws_stay_within_the_ranges() {
+        let processes = WorkloadGenerator::new(500, 3)
+            .with_arrival(ArrivalDistribution::Uniform { min: 2, max: 5 })
+            .with_burst(BurstDistribution::Bimodal { io_bound: 0.5, cpu_bound: (8, 20), short: (1, 3) })
+            .with_priority(PriorityDistribution::Uniform { min: 1, max: 4 })
+            .generate()
+            .unwrap();
+
+        assert_eq!(processes[0].arrival_time, 0);
+        for (pid, pair) in (2..).zip(processes.windows(2)) {
+            assert_eq!(pair[1].pid, pid);
+            assert!((2..=5).contains(&(pair[1].arrival_time - pair[0].arrival_time)));
+        }
+
+        for process in &processes {
+            assert!((1..=4).contains(&process.priority));
+            match process.bursts.as_slice() {
+                [Burst::Cpu(burst)] => assert!((8..=20).contains(burst)),
+                bursts => {
+                    assert!((3..=7).contains(&bursts.len()));
+                    for (i, burst) in bursts.iter().enumerate() {
+                        match (i % 2, burst) {
+                            (0, Burst::Cpu(time)) => assert!((1..=3).contains(time)),
+                            (1, Burst::Io(time)) => assert!((2..=8).contains(time)),
+                            _ => panic!("pid {} doesn't alternate CPU and I/O: {:?}", process.pid, bursts),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reports_a_workload_past_the_last_time_unit() {
+        let fixed = |count| WorkloadGenerator::new(count, 1).with_arrival(ArrivalDistribution::Fixed(u32::MAX / 2)).generate();
+        assert_eq!(fixed(3).unwrap()[2].arrival_time, u32::MAX - 1);
+        assert!(fixed(4).is_err());
+
+        let exponential = WorkloadGenerator::new(2, 1).with_arrival(ArrivalDistribution::Exponential { mean: 1e12 });
+        assert!(exponential.generate().is_err());
+
+        let long_bursts = BurstDistribution::Bimodal { io_bound: 1.0, cpu_bound: (1, 1), short: (4_000_000_000, 4_000_000_000) };
+        assert!(WorkloadGenerator::new(1, 1).with_burst(long_bursts).generate().is_err());
+
+        let long_burst = BurstDistribution::Exponential { mean: 1e12 };
+        assert!(WorkloadGenerator::new(1, 1).with_burst(long_burst).generate().is_err());
+    }
+}
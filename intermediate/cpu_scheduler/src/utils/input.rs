@@ -1,6 +1,6 @@
 use std::io::{self, Write};
 
-use crate::models::cpu_process::{BaseProcess, PriorityProcess};
+use crate::models::{cpu_process::BaseProcess, periodic_task::PeriodicTask};
 
 pub fn user_input(message: &str) -> Result<String, io::Error> {
     print!("{}", message);
@@ -10,6 +10,16 @@ pub fn user_input(message: &str) -> Result<String, io::Error> {
     Ok(input.trim().to_string())
 }
 
+pub fn confirm(message: &str) -> Result<bool, io::Error> {
+    loop {
+        match user_input(message)?.to_lowercase().as_str() {
+            "yes" | "y" => return Ok(true),
+            "no" | "n" => return Ok(false),
+            _ => println!("Invalid input. Please enter 'y' for yes or 'n' for no."),
+        }
+    }
+}
+
 pub fn valid_input(input: &str) -> Result<u32, &'static str> {
     input.trim().parse::<u32>().map_err(|_| "Invalid input. Please enter a positive integer.")
 }
@@ -48,7 +58,7 @@ pub fn get_processes_from_user() -> Result<Vec<BaseProcess>, Box<dyn std::error:
     Ok(processes)
 }
 
-pub fn get_priority_processes_from_user() -> Result<Vec<PriorityProcess>, Box<dyn std::error::Error>> {
+pub fn get_priority_processes_from_user() -> Result<Vec<BaseProcess>, Box<dyn std::error::Error>> {
     let num_of_processes = loop {
         let proc_input = user_input("Enter the number of processes (3-5): ")?;
         match proc_input.trim().parse() {
@@ -84,10 +94,140 @@ pub fn get_priority_processes_from_user() -> Result<Vec<PriorityProcess>, Box<dy
             }
         };
 
-        let bp = BaseProcess::new(i + 1, arrival_time, burst_time);
+        processes.push(BaseProcess::new(i + 1, arrival_time, burst_time).with_priority(priority));
+    }
+    
+    Ok(processes)
+}
+
+pub fn get_ticket_processes_from_user() -> Result<Vec<BaseProcess>, Box<dyn std::error::Error>> {
+    let num_of_processes = loop {
+        let proc_input = user_input("Enter the number of processes (3-5): ")?;
+        match proc_input.trim().parse() {
+            Ok(num) if (3..=5).contains(&num) => break num,
+            _ => println!("Please choose between 3 to 5 only.")
+        };
+    };
+
+    let mut processes = Vec::new();
+    for i in 0..num_of_processes {
+        println!("Process {}", i + 1);
 
-        processes.push(PriorityProcess::new(bp, priority));
+        let arrival_time = loop {
+            let input = user_input("Enter Arrival Time: ")?;
+            match valid_input(&input) {
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+        let burst_time = loop {
+            let input = user_input("Enter Burst Time: ")?;
+            match valid_input(&input) {
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+
+        let tickets: u32 = loop {
+            let input = user_input("Enter Tickets: ")?;
+            match valid_input(&input) {
+                Ok(0) => println!("A process needs at least 1 ticket."),
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+
+        processes.push(BaseProcess::new(i + 1, arrival_time, burst_time).with_tickets(tickets));
     }
     
     Ok(processes)
-}
\ No newline at end of file
+}
+
+pub fn get_nice_processes_from_user() -> Result<Vec<BaseProcess>, Box<dyn std::error::Error>> {
+    let num_of_processes = loop {
+        let proc_input = user_input("Enter the number of processes (3-5): ")?;
+        match proc_input.trim().parse() {
+            Ok(num) if (3..=5).contains(&num) => break num,
+            _ => println!("Please choose between 3 to 5 only.")
+        };
+    };
+
+    let mut processes = Vec::new();
+    for i in 0..num_of_processes {
+        println!("Process {}", i + 1);
+
+        let arrival_time = loop {
+            let input = user_input("Enter Arrival Time: ")?;
+            match valid_input(&input) {
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+        let burst_time = loop {
+            let input = user_input("Enter Burst Time: ")?;
+            match valid_input(&input) {
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+
+        let nice: i32 = loop {
+            let input = user_input("Enter Nice Value (-20 to 19): ")?;
+            match input.trim().parse::<i32>() {
+                Ok(value) if (-20..=19).contains(&value) => break value,
+                _ => println!("Invalid input. Please enter a number between -20 and 19.")
+            }
+        };
+
+        processes.push(BaseProcess::new(i + 1, arrival_time, burst_time).with_nice(nice));
+    }
+    
+    Ok(processes)
+}
+pub fn get_periodic_tasks_from_user() -> Result<Vec<PeriodicTask>, Box<dyn std::error::Error>> {
+    let num_of_tasks = loop {
+        let task_input = user_input("Enter the number of periodic tasks (2-5): ")?;
+        match task_input.trim().parse() {
+            Ok(num) if (2..=5).contains(&num) => break num,
+            _ => println!("Please choose between 2 to 5 only.")
+        };
+    };
+
+    let mut tasks = Vec::new();
+    for i in 0..num_of_tasks {
+        println!("Task {}", i + 1);
+
+        let period = loop {
+            let input = user_input("Enter Period: ")?;
+            match valid_input(&input) {
+                Ok(0) => println!("Value must be greater than 0."),
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+        let wcet = loop {
+            let input = user_input("Enter Worst-Case Execution Time: ")?;
+            match valid_input(&input) {
+                Ok(0) => println!("Value must be greater than 0."),
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+
+        let deadline = loop {
+            let input = user_input("Enter Relative Deadline (empty = period): ")?;
+            if input.is_empty() {
+                break period;
+            }
+            match valid_input(&input) {
+                Ok(0) => println!("Value must be greater than 0."),
+                Ok(value) => break value,
+                Err(e) => println!("{}", e)
+            }
+        };
+
+        tasks.push(PeriodicTask::new(i + 1, period, wcet).with_deadline(deadline));
+    }
+
+    Ok(tasks)
+}
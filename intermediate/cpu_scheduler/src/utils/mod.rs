@@ -1,3 +1,12 @@
 pub mod input;
 pub mod gantt_display;
 pub mod try_again;
+pub mod workload;
+pub mod comparison;
+pub mod schedulability;
+pub mod export;
+pub mod generator;
+pub mod replay;
+pub mod prediction;
+pub mod validation;
+pub mod report;
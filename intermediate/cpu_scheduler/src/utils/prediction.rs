@@ -0,0 +1,85 @@
+use crate::models::schedule_result::ScheduleResult;
+
+// Difference to the oracle, relative when the oracle value isn't 0
+fn versus(value: f64, oracle: f64) -> String {
+    let difference = value - oracle;
+    if oracle == 0.0 {
+        return format!("{:.2} vs {:.2} ({:+.2})", value, oracle, difference);
+    }
+
+    format!("{:.2} vs {:.2} ({:+.2}, {:+.1}%)", value, oracle, difference, difference / oracle * 100.0)
+}
+
+/*
+ *  How well the predicted bursts matched the real ones, and what the errors cost
+ *  compared to the same algorithm with exact burst times (the oracle).
+ *
+ *  * MAE is the mean absolute error of the predictions of a process.
+ *  * Error % is the total absolute error over the total actual CPU time.
+ */
+pub fn display_prediction(result: &ScheduleResult, oracle: &ScheduleResult) {
+    const HEADER: &str = "|---------|--------|---------------|------------|-------|---------|";
+
+    println!("\n{:=^90}", " BURST PREDICTION ");
+    println!("\n{}", HEADER);
+    println!(
+        "|{:^9}|{:^8}|{:^15}|{:^12}|{:^7}|{:^9}|",
+        "Process", "Bursts", "Avg Predicted", "Avg Actual", "MAE", "Error %"
+    );
+    println!("{}", HEADER);
+
+    let mut total_error = 0.0;
+    let mut total_actual = 0;
+    let mut total_bursts = 0;
+
+    for p in &result.processes {
+        let bursts = p.burst_predictions.len();
+        if bursts == 0 {
+            continue;
+        }
+
+        let predicted: f32 = p.burst_predictions.iter().map(|(predicted, _)| predicted).sum();
+        let actual: u32 = p.burst_predictions.iter().map(|(_, actual)| actual).sum();
+        let error: f32 = p.burst_predictions
+            .iter()
+            .map(|&(predicted, actual)| (predicted - actual as f32).abs())
+            .sum();
+
+        total_error += error;
+        total_actual += actual;
+        total_bursts += bursts;
+
+        println!(
+            "|{:^9}|{:^8}|{:^15.2}|{:^12.2}|{:^7.2}|{:^9.1}|",
+            p.pid,
+            bursts,
+            predicted / bursts as f32,
+            actual as f32 / bursts as f32,
+            error / bursts as f32,
+            error / actual.max(1) as f32 * 100.0
+        );
+    }
+
+    println!("{}\n", HEADER);
+
+    if total_bursts > 0 {
+        println!(
+            "Overall: MAE {:.2} over {} bursts, error {:.1}% of the CPU time",
+            total_error / total_bursts as f32,
+            total_bursts,
+            total_error / total_actual.max(1) as f32 * 100.0
+        );
+    }
+
+    let (metrics, exact) = (result.metrics(), oracle.metrics());
+    println!("\nCompared to {} with exact burst times:", oracle.algorithm_name);
+    println!("Average Waiting Time: {}", versus(metrics.avg_waiting_time, exact.avg_waiting_time));
+    println!("Average Turnaround Time: {}", versus(metrics.avg_turnaround_time, exact.avg_turnaround_time));
+    println!("Average Response Time: {}", versus(metrics.avg_response_time, exact.avg_response_time));
+    println!(
+        "Context Switches: {} vs {} ({:+})",
+        metrics.context_switches,
+        exact.context_switches,
+        metrics.context_switches as i64 - exact.context_switches as i64
+    );
+}
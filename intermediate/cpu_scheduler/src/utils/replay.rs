@@ -0,0 +1,223 @@
+use std::io::{self, Write};
+
+use crate::{
+    models::{schedule_result::ScheduleResult, trace::{Trace, TraceEvent, TraceEventKind}},
+    utils::gantt_display::SegmentKind,
+};
+
+const COMMANDS: &str = "[Enter/n] next event  [b] previous event  [+] next tick  [-] previous tick  [j <time>] jump  [q] quit";
+
+/*
+ *  Where the replay stands.
+ *
+ *  * `time` is the clock, the Gantt chart and the remaining times are drawn up to it.
+ *  * `event` is the last trace event that already happened, None before the first one.
+ *    Several events share a time, so stepping by event can stay on the same tick.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    time: u32,
+    event: Option<usize>,
+}
+
+impl Position {
+    // Every event up to and including `time` has happened
+    fn at_time(trace: &Trace, time: u32) -> Self {
+        Position {
+            time,
+            event: trace.events.iter().rposition(|e| e.time <= time),
+        }
+    }
+
+    fn at_event(trace: &Trace, event: Option<usize>) -> Self {
+        Position {
+            time: event.map_or(0, |event| trace.events[event].time),
+            event,
+        }
+    }
+}
+
+enum Command {
+    NextEvent,
+    PreviousEvent,
+    NextTick,
+    PreviousTick,
+    Jump(u32),
+    Quit,
+}
+
+impl Command {
+    fn parse(input: &str) -> Option<Self> {
+        match input.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] | ["n"] => Some(Command::NextEvent),
+            ["b"] => Some(Command::PreviousEvent),
+            ["+"] => Some(Command::NextTick),
+            ["-"] => Some(Command::PreviousTick),
+            ["j", time] => time.parse().ok().map(Command::Jump),
+            ["q"] => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+// Running process of every core after `events`, with the time it gets the core
+fn running(events: &[TraceEvent], cores: usize) -> Vec<Option<(usize, u32)>> {
+    let mut running = vec![None; cores];
+
+    for event in events {
+        let Some(core) = event.core.filter(|&core| core < cores) else {
+            continue;
+        };
+
+        running[core] = match event.kind {
+            TraceEventKind::Dispatch { pid, starts_at, .. } => Some((pid, starts_at)),
+            TraceEventKind::Preemption { .. }
+            | TraceEventKind::QuantumExpiry { .. }
+            | TraceEventKind::IoStart { .. }
+            | TraceEventKind::Completion { .. }
+            | TraceEventKind::LockBlocked { .. } => None,
+            _ => running[core],
+        };
+    }
+
+    running
+}
+
+fn display_frame(result: &ScheduleResult, trace: &Trace, position: Position) {
+    const HEADER: &str = "|---------|------------------|-----------|";
+
+    let happened = position.event.map_or(&trace.events[..0], |event| &trace.events[..=event]);
+    let current = happened.last();
+
+    println!("\n{:=^90}", format!(" REPLAY: t = {} ", position.time));
+    println!("Event {} of {}", happened.len(), trace.events.len());
+
+    // Everything that happened at the current tick so far
+    let tick_start = happened.iter().rposition(|e| e.time != position.time).map_or(0, |i| i + 1);
+    for event in &happened[tick_start..] {
+        let core = event.core.map_or(String::new(), |core| format!("core {}", core));
+        println!("  {:<7} {}", core, event.describe());
+    }
+
+    let running = running(happened, result.gantt_chart.cores);
+    let cores: Vec<String> = running
+        .iter()
+        .enumerate()
+        .map(|(core, running)| {
+            let process = match running {
+                Some((pid, starts_at)) if *starts_at > position.time => format!("P{} (context switch)", pid),
+                Some((pid, _)) => format!("P{}", pid),
+                None => "idle".to_string(),
+            };
+            format!("core {}: {}", core, process)
+        })
+        .collect();
+    println!("\nRunning: {}", cores.join(" | "));
+    match current {
+        Some(event @ TraceEvent { kind: TraceEventKind::Dispatch { .. }, .. }) => {
+            println!("{} (before the dispatch)", event.describe_ready_queues())
+        }
+        Some(event) => println!("{}", event.describe_ready_queues()),
+        None => println!("ready: empty"),
+    }
+
+    let mut pids: Vec<usize> = result.processes.iter().map(|p| p.pid).collect();
+    pids.dedup();
+
+    println!("\n{}", HEADER);
+    println!("|{:^9}|{:^18}|{:^11}|", "Process", "State", "Remaining");
+    println!("{}", HEADER);
+
+    for pid in pids {
+        // Jobs of a periodic task share its pid, they count together
+        let jobs: Vec<_> = result.processes.iter().filter(|p| p.pid == pid).collect();
+        let released: u32 = match jobs.iter().filter(|p| p.arrival_time <= position.time).map(|p| p.burst_time).sum() {
+            0 => jobs.first().map_or(0, |p| p.burst_time),
+            released => released,
+        };
+        let executed: u32 = result.gantt_chart
+            .segments
+            .iter()
+            .filter(|s| s.pid == pid && s.kind == SegmentKind::Process && s.start_time < position.time)
+            .map(|s| s.end_time.min(position.time) - s.start_time)
+            .sum();
+
+        let state = match happened.iter().rev().find(|e| e.pid() == pid).map(|e| (&e.kind, e.core)) {
+            None => "not arrived".to_string(),
+            Some((TraceEventKind::Arrival { .. }, _)) => "arrived".to_string(),
+            Some((TraceEventKind::IoCompletion { .. }, _)) => "I/O done".to_string(),
+            Some((TraceEventKind::Enqueue { .. }, _)) => "ready".to_string(),
+            Some((
+                TraceEventKind::Dispatch { .. } | TraceEventKind::LockAcquire { .. } | TraceEventKind::LockRelease { .. },
+                core,
+            )) => format!("running on core {}", core.unwrap_or(0)),
+            Some((TraceEventKind::Preemption { .. } | TraceEventKind::QuantumExpiry { .. }, _)) => "preempted".to_string(),
+            Some((TraceEventKind::IoStart { .. }, _)) => "blocked on I/O".to_string(),
+            Some((TraceEventKind::LockBlocked { lock, .. }, _)) => format!("blocked on lock {}", lock),
+            Some((TraceEventKind::Completion { .. }, _)) => "done".to_string(),
+        };
+
+        println!("|{:^9}|{:^18}|{:^11}|", pid, state, released.saturating_sub(executed));
+    }
+    println!("{}", HEADER);
+
+    result.gantt_chart.until(position.time).display_gantt_chart();
+}
+
+/*
+ *  Steps through a schedule one event or one tick at a time, showing the state
+ *  of every core, the ready queue and the Gantt chart drawn so far.
+ *
+ *  * Needs the trace, so the schedule has to be run with `SchedulerConfig::trace`.
+ *  * Reads one command per line, an empty line steps to the next event.
+ *  * Stops at `q` or the end of input.
+ */
+pub fn replay(result: &ScheduleResult) -> Result<(), io::Error> {
+    let Some(trace) = &result.trace else {
+        println!("Nothing to replay, the schedule was run without a trace.");
+        return Ok(());
+    };
+
+    let end_time = result.gantt_chart.end_time().max(trace.events.last().map_or(0, |e| e.time));
+    let last_event = trace.events.len().checked_sub(1);
+    let mut position = Position::at_event(trace, None);
+
+    loop {
+        display_frame(result, trace, position);
+        if position.event == last_event {
+            println!("\nEnd of the schedule.");
+        }
+
+        print!("\n{}\n> ", COMMANDS);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(());
+        }
+
+        position = match Command::parse(&input) {
+            Some(Command::NextEvent) => match position.event {
+                Some(event) if Some(event) == last_event => position,
+                Some(event) => Position::at_event(trace, Some(event + 1)),
+                None if trace.events.is_empty() => position,
+                None => Position::at_event(trace, Some(0)),
+            },
+            Some(Command::PreviousEvent) => match position.event {
+                Some(0) | None => Position::at_event(trace, None),
+                Some(event) => Position::at_event(trace, Some(event - 1)),
+            },
+            Some(Command::NextTick) => Position::at_time(trace, (position.time + 1).min(end_time)),
+            Some(Command::PreviousTick) => match position.time {
+                0 => Position::at_event(trace, None),
+                time => Position::at_time(trace, time - 1),
+            },
+            Some(Command::Jump(time)) => Position::at_time(trace, time.min(end_time)),
+            Some(Command::Quit) => return Ok(()),
+            None => {
+                println!("Unknown command.");
+                position
+            }
+        };
+    }
+}
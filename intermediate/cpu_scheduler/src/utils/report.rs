@@ -0,0 +1,203 @@
+use std::{collections::BTreeMap, io::{BufWriter, Write}};
+
+use serde::Serialize;
+
+use crate::{
+    models::{
+        cpu_process::BaseProcess,
+        metrics::Metrics,
+        schedule_result::{GroupShare, ProcessResult, ScheduleResult},
+    },
+    utils::gantt_display::{GanttSegment, LockWait},
+};
+
+// Bumped whenever a field of the JSON or CSV export changes meaning, is renamed or removed
+pub const REPORT_VERSION: u32 = 1;
+
+// One process of one run, the same fields in the JSON and in the CSV
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessRow {
+    pub pid: usize,
+    pub arrival_time: u32,
+    pub burst_time: u32,
+    pub io_time: u32,
+    pub priority: u32,
+    pub start_time: u32,
+    pub completion_time: u32,
+    pub turnaround_time: u32,
+    pub waiting_time: u32,
+    pub response_time: u32,
+    pub migrations: u32,
+    pub deadline: Option<u32>,
+    pub missed_deadline: bool,
+    pub lock_wait_time: u32,
+    pub inversion_time: u32,
+    pub group: String,
+}
+
+impl From<&ProcessResult> for ProcessRow {
+    fn from(p: &ProcessResult) -> Self {
+        ProcessRow {
+            pid: p.pid,
+            arrival_time: p.arrival_time,
+            burst_time: p.burst_time,
+            io_time: p.io_time,
+            priority: p.priority,
+            start_time: p.start_time,
+            completion_time: p.completion_time,
+            turnaround_time: p.turnaround_time,
+            waiting_time: p.waiting_time,
+            response_time: p.response_time,
+            migrations: p.migrations,
+            deadline: p.deadline,
+            missed_deadline: p.missed_deadline(),
+            lock_wait_time: p.lock_wait_time,
+            inversion_time: p.inversion_time,
+            group: p.group.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport<'a> {
+    pub algorithm: &'a str,
+    pub parameters: BTreeMap<&'static str, &'a str>,
+    pub processes: Vec<ProcessRow>,
+    pub metrics: Metrics,
+    pub gantt: Vec<&'a GanttSegment>,
+    pub lock_waits: &'a [LockWait],
+    // Only filled by the Fair-Share Scheduler
+    pub group_shares: Vec<GroupShare>,
+}
+
+impl<'a> From<&'a ScheduleResult> for RunReport<'a> {
+    fn from(result: &'a ScheduleResult) -> Self {
+        let mut gantt: Vec<&GanttSegment> = result.gantt_chart.segments.iter().collect();
+        gantt.sort_by_key(|s| (s.core, s.start_time));
+
+        RunReport {
+            algorithm: &result.algorithm_name,
+            parameters: result.parameters.iter().map(|(name, value)| (*name, value.as_str())).collect(),
+            processes: result.processes.iter().map(ProcessRow::from).collect(),
+            metrics: result.metrics(),
+            gantt,
+            lock_waits: &result.gantt_chart.lock_waits,
+            group_shares: result.group_shares(),
+        }
+    }
+}
+
+/*
+ *  Every run of one workload, for the JSON and CSV exports.
+ *
+ *  * The layout only changes along with `REPORT_VERSION`, so outputs of two versions
+ *    can be diffed. Nothing in it depends on the time or the machine of the run.
+ *  * Runs keep the order they were made in, processes are sorted by pid and Gantt
+ *    segments by core then start time. Parameters are sorted by name.
+ *  * The CSV is flat: one row per process of every run, led by the algorithm.
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct Report<'a> {
+    pub format_version: u32,
+    pub workload: &'a [BaseProcess],
+    pub runs: Vec<RunReport<'a>>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(workload: &'a [BaseProcess], results: &'a [ScheduleResult]) -> Self {
+        Report {
+            format_version: REPORT_VERSION,
+            workload,
+            runs: results.iter().map(RunReport::from).collect(),
+        }
+    }
+
+    pub fn write_json(&self, writer: impl Write) -> Result<(), serde_json::Error> {
+        // serde_json writes a few bytes at a time, unbuffered stdout would flush every line
+        let mut writer = BufWriter::new(writer);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer).and_then(|_| writer.flush()).map_err(serde_json::Error::io)
+    }
+
+    pub fn write_csv(&self, writer: impl Write) -> Result<(), csv::Error> {
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(writer);
+
+        writer.write_record([
+            "algorithm", "pid", "arrival_time", "burst_time", "io_time", "priority", "start_time",
+            "completion_time", "turnaround_time", "waiting_time", "response_time", "migrations",
+            "deadline", "missed_deadline", "lock_wait_time", "inversion_time", "group",
+        ])?;
+        for run in &self.runs {
+            for row in &run.processes {
+                writer.serialize((run.algorithm, row))?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::scheduler::{Scheduler, SchedulerConfig, fcfs::FCFSScheduler, round_robin::RoundRobinScheduler};
+
+    fn runs(workload: &[BaseProcess]) -> Vec<ScheduleResult> {
+        let config = SchedulerConfig::default();
+        vec![
+            FCFSScheduler.schedule(workload, &config).unwrap(),
+            RoundRobinScheduler::new(2).schedule(workload, &config).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn json_keeps_the_order_and_the_fields_of_every_run() {
+        let workload = vec![BaseProcess::new(2, 1, 3), BaseProcess::new(1, 0, 4)];
+        let results = runs(&workload);
+
+        let mut json = Vec::new();
+        Report::new(&workload, &results).write_json(&mut json).unwrap();
+        // Nothing in the export changes between two runs
+        let mut again = Vec::new();
+        Report::new(&workload, &results).write_json(&mut again).unwrap();
+        assert_eq!(json, again);
+
+        let report: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(report["format_version"], REPORT_VERSION);
+        assert_eq!(report["workload"].as_array().unwrap().len(), 2);
+
+        let runs = report["runs"].as_array().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0]["algorithm"], results[0].algorithm_name.as_str());
+        assert_eq!(runs[1]["parameters"]["time_quantum"], "2");
+
+        let pids: Vec<&Value> = runs[1]["processes"].as_array().unwrap().iter().map(|p| &p["pid"]).collect();
+        assert_eq!(pids, [1, 2]);
+        let completions: Vec<&Value> = runs[1]["processes"].as_array().unwrap().iter().map(|p| &p["completion_time"]).collect();
+        assert_eq!(completions, [6, 7]);
+
+        let starts: Vec<u64> = runs[1]["gantt"].as_array().unwrap().iter().map(|s| s["start_time"].as_u64().unwrap()).collect();
+        assert!(starts.is_sorted());
+        assert_eq!(runs[1]["metrics"]["processes"], 2);
+    }
+
+    #[test]
+    fn csv_has_one_row_per_process_of_every_run() {
+        let workload = vec![BaseProcess::new(1, 0, 4), BaseProcess::new(2, 1, 3).with_group("ops")];
+        let results = runs(&workload);
+
+        let mut csv = Vec::new();
+        Report::new(&workload, &results).write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 1 + 2 * 2);
+        assert!(lines[0].starts_with("algorithm,pid,arrival_time,burst_time,"));
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+        assert_eq!(lines[1], format!("{},1,0,4,0,0,0,4,4,0,0,0,,false,0,0,default", results[0].algorithm_name));
+        assert!(lines[4].ends_with(",2,1,3,0,0,2,7,6,3,1,0,,false,0,0,ops"), "{}", lines[4]);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::models::{periodic_task::PeriodicTask, schedule_result::ScheduleResult};
+
+// Which real-time scheduler the analysis is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RealTimePolicy {
+    EarliestDeadlineFirst,
+    RateMonotonic,
+}
+
+pub fn total_utilization(tasks: &[PeriodicTask]) -> f64 {
+    tasks.iter().map(|t| t.utilization()).sum()
+}
+
+// Liu & Layland: n tasks with implicit deadlines always meet them under RM while U <= n(2^(1/n) - 1)
+pub fn liu_layland_bound(n: usize) -> f64 {
+    if n == 0 {
+        return 1.0;
+    }
+
+    n as f64 * (2f64.powf(1.0 / n as f64) - 1.0)
+}
+
+// Tasks from highest to lowest Rate Monotonic priority, same order as the scheduler
+pub fn rate_monotonic_order(tasks: &[PeriodicTask]) -> Vec<&PeriodicTask> {
+    let mut ordered: Vec<&PeriodicTask> = tasks.iter().collect();
+    ordered.sort_by_key(|t| (t.period, t.id));
+    ordered
+}
+
+/*
+ *  Exact response-time analysis for fixed priorities (Joseph & Pandya).
+ *
+ *  * R = C + sum over every higher priority task j of ceil(R / T_j) * C_j,
+ *    iterated from R = C until it stops changing.
+ *  * Returns the worst-case response time of every task in Rate Monotonic order,
+ *    None once it grows past the task's deadline.
+ *  * Exact for synchronous releases with deadlines up to the period, only sufficient with offsets.
+ */
+pub fn response_time_analysis(tasks: &[PeriodicTask]) -> Vec<(usize, Option<u32>)> {
+    let ordered = rate_monotonic_order(tasks);
+
+    ordered
+        .iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let higher = &ordered[..i];
+            let mut response = task.wcet as u64 + higher.iter().map(|t| t.wcet as u64).sum::<u64>();
+
+            loop {
+                if response > task.deadline as u64 {
+                    return (task.id, None);
+                }
+
+                let next = task.wcet as u64 + higher
+                    .iter()
+                    .map(|t| response.div_ceil(t.period as u64) * t.wcet as u64)
+                    .sum::<u64>();
+
+                if next == response {
+                    return (task.id, Some(response as u32));
+                }
+                response = next;
+            }
+        })
+        .collect()
+}
+
+/*
+ *  Processor demand test for EDF: the CPU time of every job with both its release
+ *  and deadline inside [0, t] must fit in t.
+ *
+ *  * Checked at every absolute deadline up to one hyperperiod past the longest deadline.
+ *  * Returns the first t where the demand is larger than t, None when every deadline is met.
+ *  * Exact for synchronous releases with U <= 1, only sufficient with offsets.
+ */
+pub fn edf_demand_test(tasks: &[PeriodicTask], hyperperiod: u32) -> Option<u32> {
+    let limit = hyperperiod as u64 + tasks.iter().map(|t| t.deadline as u64).max().unwrap_or(0);
+
+    let mut deadlines: Vec<u64> = tasks
+        .iter()
+        .flat_map(|t| (0..).map(move |k| k * t.period as u64 + t.deadline as u64).take_while(|&d| d <= limit))
+        .collect();
+    deadlines.sort_unstable();
+    deadlines.dedup();
+
+    deadlines
+        .into_iter()
+        .find(|&t| {
+            let demand: u64 = tasks
+                .iter()
+                .filter(|task| t >= task.deadline as u64)
+                .map(|task| ((t - task.deadline as u64) / task.period as u64 + 1) * task.wcet as u64)
+                .sum();
+            demand > t
+        })
+        .map(|t| t as u32)
+}
+
+/*
+ *  Analytical schedulability of the task set next to what the simulation of its
+ *  jobs over the hyperperiod actually did.
+ *
+ *  * Rate Monotonic: Liu & Layland utilization bound and response-time analysis.
+ *  * EDF: utilization test and processor demand test.
+ *  * The analysis assumes a single core and no context-switch cost, the
+ *    simulation doesn't, so the two can disagree.
+ */
+pub fn display_schedulability(
+    tasks: &[PeriodicTask],
+    hyperperiod: u32,
+    result: &ScheduleResult,
+    policy: RealTimePolicy,
+) {
+    const HEADER: &str = "|------|--------|------|----------|-------|--------------|------------|--------|";
+
+    let utilization = total_utilization(tasks);
+    let rta = response_time_analysis(tasks);
+
+    println!("\n{:=^90}", " SCHEDULABILITY ANALYSIS ");
+    println!("\n{}", HEADER);
+    println!(
+        "|{:^6}|{:^8}|{:^6}|{:^10}|{:^7}|{:^14}|{:^12}|{:^8}|",
+        "Task", "Period", "WCET", "Deadline", "U", "RTA Response", "Observed R", "Misses"
+    );
+    println!("{}", HEADER);
+
+    for task in tasks {
+        let jobs: Vec<_> = result.processes.iter().filter(|p| p.pid == task.id).collect();
+        let observed = jobs.iter().map(|p| p.turnaround_time).max().unwrap_or(0);
+        let misses = jobs.iter().filter(|p| p.missed_deadline()).count();
+
+        let response = match policy {
+            RealTimePolicy::EarliestDeadlineFirst => "-".to_string(),
+            RealTimePolicy::RateMonotonic => match rta.iter().find(|(id, _)| *id == task.id) {
+                Some((_, Some(response))) => response.to_string(),
+                _ => format!("> {}", task.deadline),
+            },
+        };
+
+        println!(
+            "|{:^6}|{:^8}|{:^6}|{:^10}|{:^7.3}|{:^14}|{:^12}|{:^8}|",
+            task.id, task.period, task.wcet, task.deadline, task.utilization(), response, observed, misses
+        );
+    }
+
+    println!("{}\n", HEADER);
+
+    println!("Total Utilization: {:.3}", utilization);
+    println!("Hyperperiod: {} ({} jobs released)", hyperperiod, result.processes.len());
+
+    let implicit_deadlines = tasks.iter().all(|t| t.deadline == t.period);
+    let schedulable = match policy {
+        RealTimePolicy::RateMonotonic => {
+            let bound = liu_layland_bound(tasks.len());
+            let verdict = if !implicit_deadlines {
+                "not applicable, deadlines differ from periods"
+            } else if utilization <= bound {
+                "schedulable"
+            } else {
+                "inconclusive"
+            };
+            println!("Liu & Layland Bound (n = {}): {:.3} -> {}", tasks.len(), bound, verdict);
+
+            let failing: Vec<String> = rta
+                .iter()
+                .filter(|(_, response)| response.is_none())
+                .map(|(id, _)| format!("P{}", id))
+                .collect();
+            if failing.is_empty() {
+                println!("Response-Time Analysis: schedulable");
+            } else {
+                println!("Response-Time Analysis: not schedulable ({} can miss)", failing.join(", "));
+            }
+            failing.is_empty()
+        }
+        RealTimePolicy::EarliestDeadlineFirst => {
+            let fits = utilization <= 1.0;
+            println!(
+                "Utilization Test (U <= 1): {}",
+                match (fits, implicit_deadlines) {
+                    (true, true) => "schedulable",
+                    (true, false) => "necessary condition met",
+                    (false, _) => "not schedulable",
+                }
+            );
+
+            let demand = if fits { edf_demand_test(tasks, hyperperiod) } else { None };
+            match (fits, demand) {
+                (false, _) => println!("Processor Demand Test: skipped, the CPU is overloaded"),
+                (true, Some(t)) => println!("Processor Demand Test: not schedulable, demand exceeds {}", t),
+                (true, None) => println!("Processor Demand Test: schedulable"),
+            }
+            fits && demand.is_none()
+        }
+    };
+
+    let misses: Vec<_> = result.processes.iter().filter(|p| p.missed_deadline()).collect();
+    println!(
+        "Simulation: {} of {} jobs missed their deadline",
+        misses.len(),
+        result.processes.len()
+    );
+    for p in &misses {
+        let deadline = p.deadline.unwrap_or_default();
+        println!(
+            "  P{} released at {}: deadline {}, completed {} ({} late)",
+            p.pid, p.arrival_time, deadline, p.completion_time, p.completion_time - deadline
+        );
+    }
+
+    if schedulable == misses.is_empty() {
+        println!("Analysis and simulation agree");
+    } else {
+        println!(
+            "Analysis and simulation disagree: the analysis assumes a single core, \
+             synchronous releases and no context-switch cost"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liu_layland_bound_falls_towards_ln_2() {
+        assert_eq!(liu_layland_bound(0), 1.0);
+        assert_eq!(liu_layland_bound(1), 1.0);
+        assert!((liu_layland_bound(2) - 0.8284).abs() < 1e-4);
+        assert!((liu_layland_bound(3) - 0.7798).abs() < 1e-4);
+        assert!((liu_layland_bound(1000) - 2f64.ln()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn response_time_analysis_adds_up_the_preemptions() {
+        let tasks = vec![PeriodicTask::new(3, 12, 3), PeriodicTask::new(1, 4, 1), PeriodicTask::new(2, 6, 2)];
+        assert_eq!(response_time_analysis(&tasks), vec![(1, Some(1)), (2, Some(3)), (3, Some(10))]);
+
+        // Task 3 only gets 1 unit in every 4, it needs more than its deadline
+        let tasks = vec![PeriodicTask::new(1, 4, 2), PeriodicTask::new(2, 4, 1), PeriodicTask::new(3, 8, 3)];
+        assert_eq!(response_time_analysis(&tasks), vec![(1, Some(2)), (2, Some(3)), (3, None)]);
+    }
+
+    #[test]
+    fn edf_demand_test_finds_the_first_overloaded_deadline() {
+        let tasks = vec![PeriodicTask::new(1, 4, 1), PeriodicTask::new(2, 6, 2), PeriodicTask::new(3, 12, 3)];
+        assert_eq!(edf_demand_test(&tasks, 12), None);
+
+        // U = 5/6, but both first jobs are due by 3 and need 4 units
+        let tasks = vec![PeriodicTask::new(1, 4, 2).with_deadline(2), PeriodicTask::new(2, 6, 2).with_deadline(3)];
+        assert!(total_utilization(&tasks) <= 1.0);
+        assert_eq!(edf_demand_test(&tasks, 12), Some(3));
+    }
+}
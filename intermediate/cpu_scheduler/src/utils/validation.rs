@@ -0,0 +1,351 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    models::{cpu_process::{BaseProcess, Burst}, schedule_result::{ProcessResult, ScheduleResult}},
+    utils::gantt_display::{GanttSegment, LockWait, SegmentKind},
+};
+
+// An invariant a schedule breaks, found by `validate`
+#[derive(Debug, Clone)]
+pub enum Violation {
+    // Two segments of the same core cover the same time
+    Overlap { core: usize, first: GanttSegment, second: GanttSegment },
+    // The same process runs on two cores at once
+    ParallelRun { pid: usize, time: u32, cores: (usize, usize) },
+    RunsBeforeArrival { pid: usize, start: u32, arrival: u32 },
+    // CPU time in the Gantt chart differs from the CPU bursts of the workload
+    CpuTime { pid: usize, expected: u32, received: u32 },
+    // A process of the workload that has no result
+    Missing { pid: usize },
+    // A reported value that doesn't follow from the Gantt chart or the other values
+    Inconsistent { pid: usize, metric: &'static str, expected: u32, actual: u32 },
+    // A non-preemptive algorithm split a CPU burst other than by blocking on a lock
+    Preempted { pid: usize, segments: usize, bursts: usize },
+    // A process runs while it is blocked on a lock
+    RunsWhileBlocked { pid: usize, lock: String, time: u32 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let segment = |s: &GanttSegment| format!("{} ({}-{})", s.label(), s.start_time, s.end_time);
+
+        match self {
+            Violation::Overlap { core, first, second } => {
+                write!(f, "core {}: {} overlaps {}", core, segment(first), segment(second))
+            }
+            Violation::ParallelRun { pid, time, cores } => {
+                write!(f, "P{} runs on cores {} and {} at the same time at {}", pid, cores.0, cores.1, time)
+            }
+            Violation::RunsBeforeArrival { pid, start, arrival } => {
+                write!(f, "P{} runs at {}, before it arrives at {}", pid, start, arrival)
+            }
+            Violation::CpuTime { pid, expected, received } => {
+                write!(f, "P{} received {} units of CPU time, its bursts add up to {}", pid, received, expected)
+            }
+            Violation::Missing { pid } => write!(f, "P{} has no result", pid),
+            Violation::Inconsistent { pid, metric, expected, actual } => {
+                write!(f, "P{} reports a {} of {}, expected {}", pid, metric, actual, expected)
+            }
+            Violation::Preempted { pid, segments, bursts } => {
+                write!(f, "P{} runs in {} segments for {} CPU burst(s) in a non-preemptive schedule", pid, segments, bursts)
+            }
+            Violation::RunsWhileBlocked { pid, lock, time } => {
+                write!(f, "P{} runs at {} while it is blocked on lock {}", pid, time, lock)
+            }
+        }
+    }
+}
+
+// Segments on the same core must not share any time, whatever their kind
+fn check_overlaps(result: &ScheduleResult, violations: &mut Vec<Violation>) {
+    for core in 0..result.gantt_chart.cores {
+        let mut segments: Vec<&GanttSegment> = result.gantt_chart.core_segments(core).collect();
+        segments.sort_by_key(|s| (s.start_time, s.end_time));
+
+        for pair in segments.windows(2) {
+            if pair[1].start_time < pair[0].end_time {
+                violations.push(Violation::Overlap { core, first: pair[0].clone(), second: pair[1].clone() });
+            }
+        }
+    }
+}
+
+// The first time two segments of one process overlap on different cores
+fn parallel_run(segments: &[&GanttSegment]) -> Option<(u32, (usize, usize))> {
+    segments.iter().enumerate().find_map(|(i, a)| {
+        segments[i + 1..]
+            .iter()
+            .find(|b| b.core != a.core && b.start_time < a.end_time && a.start_time < b.end_time)
+            .map(|b| (a.start_time.max(b.start_time), (a.core, b.core)))
+    })
+}
+
+/*
+ *  Checks the invariants every schedule of `processes` has to hold, whatever the
+ *  algorithm, and returns every one `result` breaks. An empty list is a valid schedule.
+ *
+ *  * No two segments of a core overlap, and a process never runs on two cores at once.
+ *  * No process runs before it arrives, and each receives exactly its CPU bursts.
+ *  * Start and completion times match the Gantt chart, turnaround, waiting and
+ *    response times match the start and completion times.
+ *  * With `preemptive` false every CPU burst runs in exactly one segment, or one
+ *    more for every time the process blocked on a lock.
+ *  * No process runs while it is blocked on a lock.
+ *  * Jobs of a periodic task share a pid, they are checked together against the
+ *    Gantt chart and one by one against their own values.
+ */
+pub fn validate(processes: &[BaseProcess], result: &ScheduleResult, preemptive: bool) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_overlaps(result, &mut violations);
+
+    // Jobs, segments and results of every pid, grouped in one pass each
+    let mut jobs: BTreeMap<usize, Vec<&BaseProcess>> = BTreeMap::new();
+    for process in processes {
+        jobs.entry(process.pid).or_default().push(process);
+    }
+    let mut segments_of: BTreeMap<usize, Vec<&GanttSegment>> = BTreeMap::new();
+    for segment in &result.gantt_chart.segments {
+        if segment.kind == SegmentKind::Process {
+            jobs.entry(segment.pid).or_default();
+            segments_of.entry(segment.pid).or_default().push(segment);
+        }
+    }
+    let mut waits_of: BTreeMap<usize, Vec<&LockWait>> = BTreeMap::new();
+    for wait in &result.gantt_chart.lock_waits {
+        waits_of.entry(wait.pid).or_default().push(wait);
+    }
+    let mut results_of: BTreeMap<usize, Vec<&ProcessResult>> = BTreeMap::new();
+    for p in &result.processes {
+        results_of.entry(p.pid).or_default().push(p);
+    }
+
+    for (&pid, jobs) in &jobs {
+        let segments = segments_of.remove(&pid).unwrap_or_default();
+        let first = segments.iter().map(|s| s.start_time).min();
+        let last = segments.iter().map(|s| s.end_time).max();
+
+        if let Some(start) = first
+            && let Some(arrival) = jobs.iter().map(|p| p.arrival_time).min()
+            && start < arrival
+        {
+            violations.push(Violation::RunsBeforeArrival { pid, start, arrival });
+        }
+
+        let expected = jobs.iter().map(|p| p.burst_time).sum();
+        let received = segments.iter().map(|s| s.end_time - s.start_time).sum();
+        if received != expected {
+            violations.push(Violation::CpuTime { pid, expected, received });
+        }
+
+        let results = results_of.remove(&pid).unwrap_or_default();
+        if results.is_empty() && !jobs.is_empty() {
+            violations.push(Violation::Missing { pid });
+        }
+
+        // A single process owns its pid, its values can be checked against the Gantt chart
+        if let ([job], [p]) = (jobs.as_slice(), results.as_slice()) {
+            if let Some((time, cores)) = parallel_run(&segments) {
+                violations.push(Violation::ParallelRun { pid, time, cores });
+            }
+
+            let reported = [
+                ("burst time", job.burst_time, p.burst_time),
+                ("start time", first.unwrap_or(p.start_time), p.start_time),
+                ("completion time", last.unwrap_or(p.completion_time), p.completion_time),
+            ];
+            for (metric, expected, actual) in reported {
+                if expected != actual {
+                    violations.push(Violation::Inconsistent { pid, metric, expected, actual });
+                }
+            }
+
+            // A wait may split a burst, or come before the burst ran at all
+            let bursts = job.bursts.iter().filter(|b| matches!(b, Burst::Cpu(time) if *time > 0)).count();
+            let waits = waits_of.get(&pid).map_or(0, Vec::len);
+            if !preemptive && (segments.len() < bursts || segments.len() > bursts + waits) {
+                violations.push(Violation::Preempted { pid, segments: segments.len(), bursts });
+            }
+        }
+
+        for wait in waits_of.remove(&pid).unwrap_or_default() {
+            if let Some(segment) = segments.iter().find(|s| s.start_time < wait.end_time && wait.start_time < s.end_time) {
+                let time = segment.start_time.max(wait.start_time);
+                violations.push(Violation::RunsWhileBlocked { pid, lock: wait.lock.clone(), time });
+            }
+        }
+
+        for p in results {
+            let turnaround_time = p.completion_time.saturating_sub(p.arrival_time);
+            let derived = [
+                ("turnaround time", turnaround_time, p.turnaround_time),
+                ("waiting time", turnaround_time.saturating_sub(p.burst_time + p.io_time), p.waiting_time),
+                ("response time", p.start_time.saturating_sub(p.arrival_time), p.response_time),
+            ];
+            for (metric, expected, actual) in derived {
+                if expected != actual {
+                    violations.push(Violation::Inconsistent { pid, metric, expected, actual });
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+pub fn display_validation(result: &ScheduleResult, violations: &[Violation]) {
+    println!("\n{:=^90}", " SCHEDULE VALIDATION ");
+    println!("{}", result.algorithm_name);
+
+    if violations.is_empty() {
+        println!("No violations, every invariant holds.");
+        return;
+    }
+
+    println!("{} violation(s):", violations.len());
+    for violation in violations {
+        println!("  - {}", violation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scheduler::{SchedulerConfig, all_schedulers}, utils::gantt_display::GanttChart};
+
+    // A result whose Gantt chart is exactly `segments`, (core, pid, start, end), and whose values follow from it
+    fn result_of(processes: &[BaseProcess], cores: usize, segments: &[(usize, usize, u32, u32)]) -> ScheduleResult {
+        let mut chart = GanttChart::with_cores(cores);
+        for &(core, pid, start, end) in segments {
+            chart.add_process(core, pid, start, end);
+        }
+
+        let results = processes
+            .iter()
+            .map(|p| {
+                let own = || segments.iter().filter(|s| s.1 == p.pid);
+                let start = own().map(|s| s.2).min().unwrap_or(p.arrival_time);
+                let completion = own().map(|s| s.3).max().unwrap_or(p.arrival_time);
+                ProcessResult::new(p, start, completion)
+            })
+            .collect();
+
+        ScheduleResult::new("test".to_string(), results, chart)
+    }
+
+    fn two_processes() -> Vec<BaseProcess> {
+        vec![BaseProcess::new(1, 0, 3), BaseProcess::new(2, 1, 2)]
+    }
+
+    #[test]
+    fn valid_schedule_has_no_violations() {
+        let processes = two_processes();
+        let result = result_of(&processes, 1, &[(0, 1, 0, 3), (0, 2, 3, 5)]);
+
+        assert!(validate(&processes, &result, false).is_empty());
+    }
+
+    #[test]
+    fn every_scheduler_produces_a_valid_schedule() {
+        let processes = vec![
+            BaseProcess::new(1, 0, 0).with_bursts(vec![Burst::Cpu(4), Burst::Io(3), Burst::Cpu(2)]).with_priority(2),
+            BaseProcess::new(2, 1, 6).with_priority(1),
+            BaseProcess::new(3, 2, 3).with_priority(3).with_nice(-5),
+            BaseProcess::new(4, 9, 1),
+        ];
+
+        for cores in [1, 2] {
+            let config = SchedulerConfig { context_switch_time: 1, num_cores: cores, ..Default::default() };
+            for scheduler in all_schedulers(2) {
+                let result = scheduler.schedule(&processes, &config).unwrap();
+                let violations = validate(&processes, &result, scheduler.preemptive());
+                assert!(violations.is_empty(), "{} on {} core(s): {:?}", scheduler.name(), cores, violations);
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_segments_are_reported() {
+        let processes = two_processes();
+        let result = result_of(&processes, 1, &[(0, 1, 0, 3), (0, 2, 2, 4)]);
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::Overlap { core: 0, .. })));
+    }
+
+    #[test]
+    fn process_on_two_cores_at_once_is_reported() {
+        let processes = vec![BaseProcess::new(1, 0, 4)];
+        let result = result_of(&processes, 2, &[(0, 1, 0, 2), (1, 1, 1, 3)]);
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::ParallelRun { pid: 1, time: 1, cores: (0, 1) })));
+    }
+
+    #[test]
+    fn run_before_arrival_is_reported() {
+        let processes = vec![BaseProcess::new(1, 2, 3)];
+        let result = result_of(&processes, 1, &[(0, 1, 0, 3)]);
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::RunsBeforeArrival { pid: 1, start: 0, arrival: 2 })));
+    }
+
+    #[test]
+    fn wrong_cpu_time_is_reported() {
+        let processes = two_processes();
+        let result = result_of(&processes, 1, &[(0, 1, 0, 2), (0, 2, 2, 4)]);
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::CpuTime { pid: 1, expected: 3, received: 2 })));
+    }
+
+    #[test]
+    fn missing_result_is_reported() {
+        let processes = two_processes();
+        let mut result = result_of(&processes, 1, &[(0, 1, 0, 3), (0, 2, 3, 5)]);
+        result.processes.retain(|p| p.pid != 2);
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::Missing { pid: 2 })));
+    }
+
+    #[test]
+    fn inconsistent_value_is_reported() {
+        let processes = two_processes();
+        let mut result = result_of(&processes, 1, &[(0, 1, 0, 3), (0, 2, 3, 5)]);
+        result.processes[1].waiting_time += 1;
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            Violation::Inconsistent { pid: 2, metric: "waiting time", expected: 2, actual: 3 }
+        )));
+    }
+
+    #[test]
+    fn split_burst_in_a_non_preemptive_schedule_is_reported() {
+        let processes = two_processes();
+        let segments = [(0, 1, 0, 1), (0, 2, 1, 3), (0, 1, 3, 5)];
+
+        assert!(validate(&processes, &result_of(&processes, 1, &segments), true).is_empty());
+
+        let violations = validate(&processes, &result_of(&processes, 1, &segments), false);
+        assert!(violations.iter().any(|v| matches!(v, Violation::Preempted { pid: 1, segments: 2, bursts: 1 })));
+    }
+
+    #[test]
+    fn run_while_blocked_on_a_lock_is_reported() {
+        let processes = two_processes();
+        let mut result = result_of(&processes, 1, &[(0, 1, 0, 3), (0, 2, 3, 5)]);
+        result.gantt_chart.lock_waits.push(LockWait {
+            pid: 2,
+            lock: "A".to_string(),
+            holder: 1,
+            start_time: 2,
+            end_time: 4,
+        });
+
+        let violations = validate(&processes, &result, true);
+        assert!(violations.iter().any(|v| matches!(v, Violation::RunsWhileBlocked { pid: 2, time: 3, .. })));
+    }
+}
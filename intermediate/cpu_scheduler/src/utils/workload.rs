@@ -0,0 +1,546 @@
+use std::{collections::HashSet, fs, io::{self, Write}, path::Path};
+
+use serde::{Deserialize, Deserializer, de::{self, DeserializeOwned}};
+use thiserror::Error;
+
+use crate::{
+    models::{cpu_process::{BaseProcess, Burst, CriticalSection, DEFAULT_GROUP, DEFAULT_TICKETS}, periodic_task::PeriodicTask},
+};
+
+#[derive(Debug, Error)]
+pub enum WorkloadError {
+    #[error("could not read workload file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("unsupported workload format '{0}' (expected csv, json or toml)")]
+    UnsupportedFormat(String),
+
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("process entry {entry}: {message}")]
+    Invalid { entry: usize, message: String },
+
+    #[error("workload does not contain any process")]
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadFormat {
+    Csv,
+    Json,
+    Toml,
+}
+
+impl WorkloadFormat {
+    pub fn from_path(path: &Path) -> Result<Self, WorkloadError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "csv" => Ok(WorkloadFormat::Csv),
+            "json" => Ok(WorkloadFormat::Json),
+            "toml" => Ok(WorkloadFormat::Toml),
+            _ => Err(WorkloadError::UnsupportedFormat(extension)),
+        }
+    }
+}
+
+/*
+ *  One row of a workload file.
+ *
+ *  * CSV:  header `pid,arrival,burst,priority` (priority column is optional)
+ *  * JSON: an array of `{ "pid": 1, "arrival": 0, "burst": 5, "priority": 2 }`
+ *  * TOML: a `[[processes]]` table per process with the same keys
+ *
+ *  A process doing I/O replaces `burst` with `bursts`: alternating CPU and I/O times
+ *  starting and ending with a CPU burst, e.g. `"5 3 2"` in CSV or `[5, 3, 2]` in JSON / TOML.
+ *  An optional `affinity` lists the cores the process may run on in the same way,
+ *  an optional `tickets` sets its share for the lottery / stride schedulers and an
+ *  optional `nice` (-20 to 19) its weight for the CFS Scheduler. An optional `history`
+ *  lists CPU bursts of earlier runs, oldest first, to seed burst prediction.
+ *  An optional `sections` lists critical sections as `<lock>:<start>:<length>` in CPU
+ *  time, e.g. `"A:1:3 B:6:2"` in CSV or `["A:1:3", "B:6:2"]` in JSON / TOML.
+ *  An optional `group` (or `owner`) names the user or team the process belongs to
+ *  for the Fair-Share Scheduler, processes without one share the `default` group.
+ */
+#[derive(Debug, Deserialize)]
+struct WorkloadRow {
+    pid: usize,
+    #[serde(alias = "arrival_time")]
+    arrival: u32,
+    #[serde(default, alias = "burst_time")]
+    burst: Option<u32>,
+    #[serde(default)]
+    priority: u32,
+    #[serde(default)]
+    tickets: Option<u32>,
+    #[serde(default)]
+    nice: i32,
+    #[serde(default, deserialize_with = "deserialize_numbers")]
+    bursts: Option<Vec<u32>>,
+    #[serde(default, deserialize_with = "deserialize_numbers")]
+    affinity: Option<Vec<u32>>,
+    #[serde(default, deserialize_with = "deserialize_numbers")]
+    history: Option<Vec<u32>>,
+    #[serde(default, deserialize_with = "deserialize_words")]
+    sections: Option<Vec<String>>,
+    #[serde(default, alias = "owner")]
+    group: Option<String>,
+}
+
+/*
+ *  One row of a periodic task set file, same formats as a workload.
+ *
+ *  * CSV:  header `id,period,wcet,deadline,offset` (deadline and offset are optional)
+ *  * JSON: an array of `{ "id": 1, "period": 10, "wcet": 3 }`
+ *  * TOML: a `[[tasks]]` table per task with the same keys
+ *
+ *  The deadline is relative to each release and defaults to the period.
+ */
+#[derive(Debug, Deserialize)]
+struct TaskRow {
+    #[serde(alias = "pid")]
+    id: usize,
+    period: u32,
+    #[serde(alias = "burst")]
+    wcet: u32,
+    #[serde(default)]
+    deadline: Option<u32>,
+    #[serde(default, alias = "arrival")]
+    offset: u32,
+}
+
+// Accepts a list of numbers, a single number or a string of numbers separated by spaces
+fn deserialize_numbers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u32>>, D::Error> {
+    struct NumbersVisitor;
+
+    impl<'de> de::Visitor<'de> for NumbersVisitor {
+        type Value = Option<Vec<u32>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a list of numbers")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            u32::try_from(value)
+                .map(|number| Some(vec![number]))
+                .map_err(|_| E::custom(format!("{} is too large", value)))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            u32::try_from(value)
+                .map(|number| Some(vec![number]))
+                .map_err(|_| E::custom(format!("invalid number {}", value)))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            if value.trim().is_empty() {
+                return Ok(None);
+            }
+
+            value
+                .split_whitespace()
+                .map(|number| number.parse::<u32>().map_err(|_| E::custom(format!("invalid number '{}'", number))))
+                .collect::<Result<Vec<u32>, E>>()
+                .map(Some)
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut numbers = Vec::new();
+            while let Some(number) = seq.next_element::<u32>()? {
+                numbers.push(number);
+            }
+            Ok(Some(numbers))
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    deserializer.deserialize_any(NumbersVisitor)
+}
+
+// Accepts a list of strings or a single string of words separated by spaces
+fn deserialize_words<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<String>>, D::Error> {
+    struct WordsVisitor;
+
+    impl<'de> de::Visitor<'de> for WordsVisitor {
+        type Value = Option<Vec<String>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a list of strings")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            if value.trim().is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some(value.split_whitespace().map(str::to_string).collect()))
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut words = Vec::new();
+            while let Some(word) = seq.next_element::<String>()? {
+                words.push(word);
+            }
+            Ok(Some(words))
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    deserializer.deserialize_any(WordsVisitor)
+}
+
+// Sorted by start, each within the CPU time of the process and none overlapping the next
+fn to_sections(specs: &[String], burst_time: u32) -> Result<Vec<CriticalSection>, String> {
+    let mut sections = specs
+        .iter()
+        .map(|spec| spec.parse::<CriticalSection>())
+        .collect::<Result<Vec<_>, _>>()?;
+    sections.sort_by_key(|section| section.start);
+
+    for section in &sections {
+        if section.length == 0 {
+            return Err(format!("critical section on {} has a length of 0", section.lock));
+        }
+        let Some(end) = section.start.checked_add(section.length) else {
+            return Err(format!("critical section on {} ends past {}", section.lock, u32::MAX));
+        };
+        if end > burst_time {
+            return Err(format!(
+                "critical section on {} ends at {}, after the {} units of CPU time of the process",
+                section.lock, end, burst_time
+            ));
+        }
+    }
+
+    // A process holds at most one lock at a time, so waiting on a lock can't deadlock
+    if let Some(pair) = sections.windows(2).find(|pair| pair[1].start < pair[0].end()) {
+        return Err(format!("critical sections on {} and {} overlap", pair[0].lock, pair[1].lock));
+    }
+
+    Ok(sections)
+}
+
+// Even positions are CPU bursts and odd positions are I/O bursts
+fn to_bursts(times: &[u32]) -> Result<Vec<Burst>, String> {
+    if times.len().is_multiple_of(2) {
+        return Err("bursts must start and end with a CPU burst (odd number of values)".to_string());
+    }
+
+    if times.contains(&0) {
+        return Err("bursts must all be greater than 0".to_string());
+    }
+
+    // The CPU and the I/O bursts are added up into `burst_time` and `io_time`
+    for (kind, first) in [("CPU", 0), ("I/O", 1)] {
+        if times.iter().skip(first).step_by(2).try_fold(0u32, |sum, &time| sum.checked_add(time)).is_none() {
+            return Err(format!("{} bursts add up to more than {}", kind, u32::MAX));
+        }
+    }
+
+    Ok(times
+        .iter()
+        .enumerate()
+        .map(|(i, &time)| if i % 2 == 0 { Burst::Cpu(time) } else { Burst::Io(time) })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct TomlWorkload<T> {
+    #[serde(alias = "tasks")]
+    processes: Vec<T>,
+}
+
+pub fn load_workload(path: &Path) -> Result<Vec<BaseProcess>, WorkloadError> {
+    let format = WorkloadFormat::from_path(path)?;
+    let content = fs::read_to_string(path)?;
+
+    parse_workload(&content, format)
+}
+
+// (line, row) pairs, so that validation errors can point at the offending CSV line
+fn parse_rows<T: DeserializeOwned>(content: &str, format: WorkloadFormat) -> Result<Vec<(Option<usize>, T)>, WorkloadError> {
+    let rows = match format {
+        WorkloadFormat::Csv => parse_csv(content)?,
+        WorkloadFormat::Json => serde_json::from_str::<Vec<T>>(content)
+            .map_err(|e| WorkloadError::Parse { line: e.line(), message: e.to_string() })?
+            .into_iter()
+            .map(|row| (None, row))
+            .collect(),
+        WorkloadFormat::Toml => toml::from_str::<TomlWorkload<T>>(content)
+            .map_err(|e| WorkloadError::Parse {
+                line: e.span().map_or(0, |span| line_of_offset(content, span.start)),
+                message: e.message().to_string(),
+            })?
+            .processes
+            .into_iter()
+            .map(|row| (None, row))
+            .collect(),
+    };
+
+    if rows.is_empty() {
+        return Err(WorkloadError::Empty);
+    }
+
+    Ok(rows)
+}
+
+pub fn parse_workload(content: &str, format: WorkloadFormat) -> Result<Vec<BaseProcess>, WorkloadError> {
+    let rows: Vec<(Option<usize>, WorkloadRow)> = parse_rows(content, format)?;
+
+    let mut seen_pids = HashSet::new();
+    let mut processes = Vec::with_capacity(rows.len());
+
+    for (index, (line, row)) in rows.into_iter().enumerate() {
+        // JSON and TOML rows don't carry their own line, report the entry number instead
+        let invalid = |message: String| match line {
+            Some(line) => WorkloadError::Parse { line, message },
+            None => WorkloadError::Invalid { entry: index + 1, message },
+        };
+
+        if !seen_pids.insert(row.pid) {
+            return Err(invalid(format!("duplicate pid {}", row.pid)));
+        }
+
+        let process = match (row.bursts, row.burst) {
+            (Some(times), _) => {
+                let bursts = to_bursts(&times).map_err(|e| invalid(format!("pid {}: {}", row.pid, e)))?;
+                BaseProcess::new(row.pid, row.arrival, 0).with_bursts(bursts)
+            }
+            (None, Some(0)) => return Err(invalid(format!("pid {} has a burst of 0", row.pid))),
+            (None, Some(burst)) => BaseProcess::new(row.pid, row.arrival, burst),
+            (None, None) => return Err(invalid(format!("pid {} has neither a burst nor bursts", row.pid))),
+        };
+
+        if !(-20..=19).contains(&row.nice) {
+            return Err(invalid(format!("pid {} has nice {}, expected -20 to 19", row.pid, row.nice)));
+        }
+
+        if row.tickets == Some(0) {
+            return Err(invalid(format!("pid {} has 0 tickets", row.pid)));
+        }
+
+        let history = row.history.unwrap_or_default();
+        if history.contains(&0) {
+            return Err(invalid(format!("pid {} has a burst of 0 in its history", row.pid)));
+        }
+
+        let sections = to_sections(&row.sections.unwrap_or_default(), process.burst_time)
+            .map_err(|e| invalid(format!("pid {}: {}", row.pid, e)))?;
+
+        let group = row.group.as_deref().map(str::trim).filter(|group| !group.is_empty()).unwrap_or(DEFAULT_GROUP);
+        if group.contains([',', ':']) {
+            return Err(invalid(format!("pid {} has group '{}', a group name can't contain ',' or ':'", row.pid, group)));
+        }
+
+        let affinity = row.affinity.unwrap_or_default().into_iter().map(|core| core as usize).collect();
+        processes.push(
+            process
+                .with_priority(row.priority)
+                .with_affinity(affinity)
+                .with_tickets(row.tickets.unwrap_or(DEFAULT_TICKETS))
+                .with_nice(row.nice)
+                .with_history(history)
+                .with_sections(sections)
+                .with_group(group)
+        );
+    }
+
+    Ok(processes)
+}
+
+pub fn load_task_set(path: &Path) -> Result<Vec<PeriodicTask>, WorkloadError> {
+    let format = WorkloadFormat::from_path(path)?;
+    let content = fs::read_to_string(path)?;
+
+    parse_task_set(&content, format)
+}
+
+pub fn parse_task_set(content: &str, format: WorkloadFormat) -> Result<Vec<PeriodicTask>, WorkloadError> {
+    let rows: Vec<(Option<usize>, TaskRow)> = parse_rows(content, format)?;
+
+    let mut seen_ids = HashSet::new();
+    let mut tasks = Vec::with_capacity(rows.len());
+
+    for (index, (line, row)) in rows.into_iter().enumerate() {
+        let invalid = |message: String| match line {
+            Some(line) => WorkloadError::Parse { line, message },
+            None => WorkloadError::Invalid { entry: index + 1, message },
+        };
+
+        if !seen_ids.insert(row.id) {
+            return Err(invalid(format!("duplicate task id {}", row.id)));
+        }
+
+        if row.period == 0 || row.wcet == 0 || row.deadline == Some(0) {
+            return Err(invalid(format!("task {}: period, wcet and deadline must be greater than 0", row.id)));
+        }
+
+        tasks.push(
+            PeriodicTask::new(row.id, row.period, row.wcet)
+                .with_deadline(row.deadline.unwrap_or(row.period))
+                .with_offset(row.offset)
+        );
+    }
+
+    Ok(tasks)
+}
+
+// Writes `processes` as a workload CSV that `load_workload` reads back, with every column it accepts
+pub fn write_workload_csv(processes: &[BaseProcess], writer: impl Write) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(["pid", "arrival", "bursts", "priority", "tickets", "nice", "affinity", "history", "sections", "group"])?;
+
+    // Lists are written as words separated by spaces
+    fn words<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+        values.into_iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" ")
+    }
+
+    for process in processes {
+        let bursts = process.bursts.iter().map(|b| match b {
+            Burst::Cpu(time) | Burst::Io(time) => *time,
+        });
+
+        writer.write_record([
+            process.pid.to_string(),
+            process.arrival_time.to_string(),
+            words(bursts),
+            process.priority.to_string(),
+            process.tickets.to_string(),
+            process.nice.to_string(),
+            words(&process.affinity),
+            words(&process.history),
+            words(&process.sections),
+            process.group.clone(),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn parse_csv<T: DeserializeOwned>(content: &str) -> Result<Vec<(Option<usize>, T)>, WorkloadError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(content.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| WorkloadError::Parse { line: 1, message: e.to_string() })?
+        .clone();
+
+    let mut rows = Vec::new();
+
+    for record in reader.records() {
+        let record = record.map_err(|e| WorkloadError::Parse {
+            line: e.position().map_or(0, |pos| pos.line() as usize),
+            message: e.to_string(),
+        })?;
+
+        let line = record.position().map_or(0, |pos| pos.line() as usize);
+        let row = record
+            .deserialize::<T>(Some(&headers))
+            .map_err(|e| WorkloadError::Parse {
+                line,
+                message: match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+                    _ => e.to_string(),
+                },
+            })?;
+
+        rows.push((Some(line), row));
+    }
+
+    Ok(rows)
+}
+
+fn line_of_offset(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_csv_workload(content: &str) -> Result<Vec<BaseProcess>, WorkloadError> {
+        parse_workload(content, WorkloadFormat::Csv)
+    }
+
+    #[test]
+    fn parses_bursts_into_cpu_and_io_time() {
+        let processes = parse_csv_workload("pid,arrival,bursts\n1,0,5 3 2\n").unwrap();
+
+        assert_eq!(processes[0].burst_time, 7);
+        assert_eq!(processes[0].io_time(), 3);
+    }
+
+    #[test]
+    fn rejects_cpu_bursts_adding_up_past_u32() {
+        let error = parse_csv_workload("pid,arrival,bursts\n1,0,4000000000 1 4000000000\n").unwrap_err();
+
+        assert!(matches!(error, WorkloadError::Parse { line: 2, ref message } if message.contains("CPU bursts")));
+    }
+
+    #[test]
+    fn rejects_io_bursts_adding_up_past_u32() {
+        let error = parse_csv_workload("pid,arrival,bursts\n1,0,1 4000000000 1 4000000000 1\n").unwrap_err();
+
+        assert!(matches!(error, WorkloadError::Parse { line: 2, ref message } if message.contains("I/O bursts")));
+    }
+
+    #[test]
+    fn writes_every_column_it_reads_back() {
+        let processes = vec![
+            BaseProcess::new(1, 0, 0)
+                .with_bursts(vec![Burst::Cpu(4), Burst::Io(3), Burst::Cpu(2)])
+                .with_priority(2)
+                .with_tickets(50)
+                .with_nice(-5)
+                .with_affinity(vec![0, 2])
+                .with_history(vec![3, 5])
+                .with_sections(vec![CriticalSection::new("A", 1, 2), CriticalSection::new("B", 4, 1)])
+                .with_group("alice"),
+            BaseProcess::new(2, 3, 6),
+        ];
+
+        let mut csv = Vec::new();
+        write_workload_csv(&processes, &mut csv).unwrap();
+        let read = parse_csv_workload(&String::from_utf8(csv).unwrap()).unwrap();
+
+        assert_eq!(format!("{:?}", read), format!("{:?}", processes));
+    }
+
+    #[test]
+    fn rejects_a_critical_section_ending_past_u32() {
+        let error = parse_csv_workload("pid,arrival,bursts,sections\n1,0,5,A:4294967295:1\n").unwrap_err();
+
+        assert!(matches!(error, WorkloadError::Parse { line: 2, ref message } if message.contains("ends past")));
+    }
+}